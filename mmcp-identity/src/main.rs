@@ -5,16 +5,25 @@ use color_eyre::eyre::Result;
 mod coder;
 use coder::{decode, encode};
 
-pipeline!(encode, decode);
+pipeline!(chunks: encode | decode);
 
 #[async_std::main]
 async fn main() -> Result<()> {
     color_eyre::install()?;
+    build_info::set(build_info!());
+    let mut options = Options::parse()?;
     let mut results = vec![];
-    for mut channel in channels() {
-        let run_metrics = pipeline_run(&mut channel).await?;
-        results.push(analytics::analyze(&channel, run_metrics).await?);
+    for trial in 0..options.trials {
+        options.start_trial(trial);
+        for payload in options.payloads()? {
+            options.use_payload(&payload)?;
+            options
+                .run_parallel(options.channels()?, &mut results, |_, channel| {
+                    Box::pin(pipeline_run(channel))
+                })
+                .await?;
+        }
     }
-    analytics::report(&results);
+    options.report(&results)?;
     Ok(())
 }
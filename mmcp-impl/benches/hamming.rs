@@ -0,0 +1,93 @@
+//! Encodes and finds the errors of every codeword of the Hamming codes bit by
+//! bit and through the lookup tables, then codes a payload with either, run
+//! with `cargo bench -p mmcp-impl --bench hamming` and again with
+//! `--features bitwise-hamming` to have the coders work bit by bit.
+
+use mmcp::coder::Coder;
+use std::{hint::black_box, time::Instant};
+
+#[allow(dead_code)]
+#[path = "../src/bits.rs"]
+mod bits;
+#[allow(dead_code)]
+#[path = "../src/coder.rs"]
+mod coder;
+
+use coder::Hamming;
+
+// codewords encoded or checked per measurement.
+const CODEWORDS: u32 = 1 << 22;
+// bytes of the payload coded per measurement.
+const PAYLOAD_BYTES: usize = 1 << 20;
+
+fn main() {
+    measure::<7, 4>();
+    measure::<15, 11>();
+    measure::<31, 26>();
+}
+
+fn measure<const N: usize, const K: usize>() {
+    let coded = if cfg!(feature = "bitwise-hamming") {
+        "bit by bit"
+    } else {
+        "table"
+    };
+    for info in 0..1 << K.min(16) {
+        let codeword = Hamming::<N, K>::encode_bits(info);
+        assert_eq!(Hamming::<N, K>::encode_block(info), codeword);
+        for position in 1..=N {
+            let received = codeword ^ Hamming::<N, K>::mask(position);
+            assert_eq!(Hamming::<N, K>::error_index(received), position);
+        }
+    }
+    let bitwise = time(Hamming::<N, K>::encode_bits);
+    let table = time(Hamming::<N, K>::encode_block);
+    println!(
+        "Hamming({},{}) encode: bit by bit {:.1} ns, {} {:.1} ns per codeword, {:.1}x",
+        N,
+        K,
+        bitwise,
+        coded,
+        table,
+        bitwise / table
+    );
+    let bitwise = time(|codeword| Hamming::<N, K>::error_index_bits(codeword) as u32);
+    let table = time(|codeword| Hamming::<N, K>::error_index(codeword) as u32);
+    println!(
+        "Hamming({},{}) syndrome: bit by bit {:.1} ns, {} {:.1} ns per codeword, {:.1}x",
+        N,
+        K,
+        bitwise,
+        coded,
+        table,
+        bitwise / table
+    );
+
+    let payload: Vec<u8> = (0..PAYLOAD_BYTES).map(|byte| byte as u8).collect();
+    let mut hamming = Hamming::<N, K>::new();
+    let start = Instant::now();
+    let encoded = hamming.encode(&payload).unwrap();
+    let encoding = start.elapsed();
+    let decoded = hamming.decode(black_box(&encoded)).unwrap();
+    let decoding = start.elapsed() - encoding;
+    assert_eq!(decoded, payload);
+    let megabytes = PAYLOAD_BYTES as f64 / 1e6;
+    println!(
+        "Hamming({},{}) coder: encode {:.1} MB/s, decode {:.1} MB/s",
+        N,
+        K,
+        megabytes / encoding.as_secs_f64(),
+        megabytes / decoding.as_secs_f64()
+    );
+}
+
+// nanoseconds per codeword, the words counting up.
+fn time(code: impl Fn(u32) -> u32) -> f64 {
+    let start = Instant::now();
+    let mut codewords = 0;
+    for word in 0..CODEWORDS {
+        codewords ^= code(black_box(word));
+    }
+    black_box(codewords);
+    start.elapsed().as_nanos() as f64 / CODEWORDS as f64
+}
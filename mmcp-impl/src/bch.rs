@@ -0,0 +1,227 @@
+use color_eyre::eyre::Result;
+use mmcp::coder::{Coder, SegmentStats};
+
+use crate::{
+    bits::{BitReader, BitWriter},
+    galois::Field,
+};
+
+// systematic binary BCH code of length n = 2^m - 1 correcting t bit errors.
+// bit i of a codeword word is the coefficient of x^i, the info bits occupy
+// the highest k coefficients. codewords travel MSB first in whole bytes.
+// the last codeword of a stream is shortened: its highest info bits are
+// zero and the decoder clears them before correcting.
+pub(super) struct Bch {
+    field: Field,
+    t: usize,
+    generator: u32,
+    parity_bits: usize,
+    // zero info bits of the last codeword of the stream last encoded.
+    shortened: usize,
+    stats: SegmentStats,
+}
+
+impl Bch {
+    pub(super) fn new(m: u32, t: usize) -> Self {
+        assert!(
+            (3..=5).contains(&m),
+            "BCH codes are supported for m = 3..=5"
+        );
+        let field = Field::new(m);
+        let n = field.order();
+
+        // g(x) is the product of the distinct minimal polynomials of a^1..a^2t.
+        let mut generator = vec![1u16];
+        let mut covered = vec![false; n];
+        for i in 1..=2 * t {
+            if covered[i % n] {
+                continue;
+            }
+            let mut minimal = vec![1u16];
+            let mut conjugate = i % n;
+            while !covered[conjugate] {
+                covered[conjugate] = true;
+                minimal = field.poly_mul(&minimal, &[field.alpha_pow(conjugate), 1]);
+                conjugate = conjugate * 2 % n;
+            }
+            generator = field.poly_mul(&generator, &minimal);
+        }
+        let parity_bits = generator.len() - 1;
+        assert!(parity_bits < n, "t = {} leaves no room for info bits", t);
+        let generator = generator
+            .iter()
+            .enumerate()
+            .fold(0u32, |g, (i, &c)| g | (c as u32) << i);
+
+        Self {
+            field,
+            t,
+            generator,
+            parity_bits,
+            shortened: 0,
+            stats: SegmentStats::default(),
+        }
+    }
+
+    fn n(&self) -> usize {
+        self.field.order()
+    }
+
+    fn k(&self) -> usize {
+        self.n() - self.parity_bits
+    }
+
+    fn codeword_bytes(&self) -> usize {
+        self.n().div_ceil(8)
+    }
+
+    // c(x) = d(x) x^(n-k) + (d(x) x^(n-k) mod g(x))
+    fn encode_block(&self, info: u32) -> u32 {
+        let shifted = info << self.parity_bits;
+        let mut remainder = shifted;
+        for degree in (self.parity_bits..self.n()).rev() {
+            if (remainder >> degree) & 1 == 1 {
+                remainder ^= self.generator << (degree - self.parity_bits);
+            }
+        }
+        shifted | remainder
+    }
+
+    // correct up to t bit errors and return the info bits.
+    fn decode_block(&mut self, mut codeword: u32) -> u32 {
+        let syndromes = (1..=2 * self.t)
+            .map(|j| {
+                (0..self.n())
+                    .filter(|&i| (codeword >> i) & 1 == 1)
+                    .fold(0, |s, i| s ^ self.field.alpha_pow(i * j))
+            })
+            .collect::<Vec<_>>();
+        if syndromes.iter().all(|&s| s == 0) {
+            self.stats.passed += 1;
+        } else {
+            let locator = self.field.berlekamp_massey(&syndromes);
+            let positions = self.field.error_positions(&locator, self.n());
+            if positions.len() == locator.len() - 1 {
+                positions.iter().for_each(|p| codeword ^= 1 << p);
+                self.stats.corrected += 1;
+            } else {
+                self.stats.uncorrectable += 1;
+            }
+        }
+        codeword >> self.parity_bits
+    }
+}
+
+impl Coder for Bch {
+    fn name(&self) -> String {
+        format!("BCH({},{})", self.n(), self.k())
+    }
+
+    fn encode(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+        self.shortened = self.shortening(data.len()) as usize;
+        let padding = 8 * self.codeword_bytes() - self.n();
+        let codewords = (8 * data.len()).div_ceil(self.k());
+        let mut encoded = vec![];
+        let mut reader = BitReader::new(data);
+        for index in 0..codewords {
+            let shortening = if index + 1 == codewords {
+                self.shortened
+            } else {
+                0
+            };
+            let info = reader.read(self.k() - shortening).unwrap();
+            let codeword = self.encode_block(info) << padding;
+            encoded.extend_from_slice(&codeword.to_be_bytes()[4 - self.codeword_bytes()..]);
+        }
+        Ok(encoded)
+    }
+
+    fn decode(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+        self.stats = SegmentStats::default();
+        let padding = 8 * self.codeword_bytes() - self.n();
+        let mut decoded = vec![];
+        let mut writer = BitWriter::default();
+        let codewords = data.len() / self.codeword_bytes();
+        for (index, bytes) in data.chunks_exact(self.codeword_bytes()).enumerate() {
+            let shortening = if index + 1 == codewords {
+                self.shortened
+            } else {
+                0
+            };
+            let codeword = bytes.iter().fold(0u32, |w, &b| w << 8 | b as u32) >> padding;
+            let known = ((1 << shortening) - 1) << (self.n() - shortening);
+            let info = self.decode_block(codeword & !known);
+            writer.write(info, self.k() - shortening, &mut decoded);
+        }
+        Ok(decoded)
+    }
+
+    fn shortening(&self, data_bytes: usize) -> u32 {
+        ((self.k() - 8 * data_bytes % self.k()) % self.k()) as u32
+    }
+
+    fn codeword_bits(&self) -> Option<usize> {
+        Some(self.k())
+    }
+
+    fn segment_stats(&self) -> Option<SegmentStats> {
+        Some(self.stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mmcp::rng::SplitMix64;
+
+    // flips `errors` distinct bits among the n sent bits of every codeword.
+    fn corrupt(coder: &Bch, encoded: &mut [u8], errors: usize, rng: &mut SplitMix64) {
+        for codeword in encoded.chunks_mut(coder.codeword_bytes()) {
+            let mut positions = vec![];
+            while positions.len() < errors {
+                let position = rng.below(coder.n());
+                if !positions.contains(&position) {
+                    positions.push(position);
+                }
+            }
+            for bit in positions {
+                codeword[bit / 8] ^= 0x80 >> (bit % 8);
+            }
+        }
+    }
+
+    #[test]
+    fn corrects_up_to_t_errors_per_codeword() {
+        let mut rng = SplitMix64::new(5);
+        for (m, t) in [(3, 1), (4, 2), (5, 3), (5, 5)] {
+            let mut coder = Bch::new(m, t);
+            for len in [1, 7, 100] {
+                let data = (0..len).map(|_| rng.next_u64() as u8).collect::<Vec<_>>();
+                let mut encoded = coder.encode(&data).unwrap();
+                assert_eq!(coder.decode(&encoded).unwrap(), data, "{}", coder.name());
+
+                corrupt(&coder, &mut encoded, t, &mut rng);
+                assert_eq!(
+                    coder.decode(&encoded).unwrap(),
+                    data,
+                    "{} of {} bytes",
+                    coder.name(),
+                    len
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn fails_beyond_t_errors_per_codeword() {
+        let mut rng = SplitMix64::new(6);
+        let mut coder = Bch::new(4, 2);
+        let data = (0..70).map(|_| rng.next_u64() as u8).collect::<Vec<_>>();
+        let mut encoded = coder.encode(&data).unwrap();
+        corrupt(&coder, &mut encoded, 3, &mut rng);
+        assert_ne!(coder.decode(&encoded).unwrap(), data);
+        let stats = coder.segment_stats().unwrap();
+        assert_eq!(stats.passed, 0);
+        assert_eq!(stats.corrected + stats.uncorrectable, 80);
+    }
+}
@@ -0,0 +1,55 @@
+// reads fixed width groups of bits MSB first, zero filling the last group.
+pub(super) struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> BitReader<'a> {
+    pub(super) fn new(data: &'a [u8]) -> Self {
+        Self { data, position: 0 }
+    }
+
+    pub(super) fn read(&mut self, width: usize) -> Option<u32> {
+        if self.position >= self.data.len() * 8 {
+            return None;
+        }
+        let mut value = 0u32;
+        for _ in 0..width {
+            let bit = self
+                .data
+                .get(self.position / 8)
+                .map_or(0, |byte| (byte >> (7 - self.position % 8)) & 1);
+            value = value << 1 | bit as u32;
+            self.position += 1;
+        }
+        Some(value)
+    }
+}
+
+// collects fixed width groups of bits MSB first into whole bytes.
+#[derive(Default)]
+pub(super) struct BitWriter {
+    buffer: u64,
+    count: usize,
+}
+
+impl BitWriter {
+    pub(super) fn write(&mut self, value: u32, width: usize, output: &mut Vec<u8>) {
+        self.buffer = self.buffer << width | value as u64;
+        self.count += width;
+        while self.count >= 8 {
+            self.count -= 8;
+            output.push((self.buffer >> self.count) as u8);
+        }
+        self.buffer &= (1 << self.count) - 1;
+    }
+
+    // zero fill and emit a trailing partial byte.
+    pub(super) fn flush(&mut self, output: &mut Vec<u8>) {
+        if self.count > 0 {
+            output.push((self.buffer << (8 - self.count)) as u8);
+            self.buffer = 0;
+            self.count = 0;
+        }
+    }
+}
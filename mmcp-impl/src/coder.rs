@@ -1,207 +1,573 @@
-use std::{error, vec};
-
-use async_std::prelude::*;
 use color_eyre::eyre::Result;
-
-// encode message using hamming code process.
-pub(super) async fn encode(
-    mut stream: impl Stream<Item = u8> + Unpin,
-) -> Result<impl Stream<Item = u8>> {
-    let mut data = vec![];
-    while let Some(byte) = stream.next().await {
-        data.push(byte);
-    }
-    let data = encode_data(&data);
-    let output = async_std::stream::from_iter(data);
-    Ok(output)
+use mmcp::coder::{Coder, CodewordStats, SegmentStats};
+
+use crate::bits::{BitReader, BitWriter};
+
+// generic Hamming(n,k) code with n = 2^m - 1 and k = n - m.
+// every codeword is stored MSB first in the smallest number of whole bytes,
+// so bit position i (1-based) of the codeword is bit (8 * bytes - i) of the word.
+// packed codes send the n bits of every codeword back to back instead.
+// the last codeword of a stream is shortened: its leading info bits are
+// zero, packed codes leave them out and the decoder knows them to be zero.
+
+// least reliable positions the soft decoder flips, 2^3 tries per codeword.
+const CHASE_POSITIONS: usize = 3;
+
+pub(super) struct Hamming<const N: usize, const K: usize> {
+    packed: bool,
+    // zero info bits of the last codeword of the stream last encoded.
+    shortened: usize,
+    stats: SegmentStats,
+    codewords: CodewordStats,
 }
 
-pub(super) async fn decode(
-    mut stream: impl Stream<Item = u8> + Unpin,
-) -> Result<impl Stream<Item = u8>> {
-    let mut data = vec![];
-    while let Some(byte) = stream.next().await {
-        data.push(byte);
+impl<const N: usize, const K: usize> Hamming<N, K> {
+    // number of bytes a single codeword occupies on the channel.
+    const CODEWORD_BYTES: usize = N.div_ceil(8);
+
+    pub(super) fn new() -> Self {
+        const {
+            assert!(
+                N < 32 && N > K && N == (1 << (N - K)) - 1,
+                "Hamming code requires n = 2^m - 1 and k = n - m"
+            )
+        };
+        Self {
+            packed: false,
+            shortened: 0,
+            stats: SegmentStats::default(),
+            codewords: CodewordStats::default(),
+        }
     }
-    let data = decode_data(&data);
-    let output = async_std::stream::from_iter(data);
-    Ok(output)
-}
 
-fn encode_data(data: &[u8]) -> Vec<u8> {
-    let mut segments = vec![];
-    for byte in data {
-    
-        // enumerating bits from left to right.
-        // b0 = c3, b1 = c5, b2 = c6, b3 = c7
-        let c3 = (byte & 0b1000_0000) >> 7;
-        let c5 = (byte & 0b0100_0000) >> 6;
-        let c6 = (byte & 0b0010_0000) >> 5;
-        let c7 = (byte & 0b0001_0000) >> 4;
+    pub(super) fn packed() -> Self {
+        Self {
+            packed: true,
+            ..Self::new()
+        }
+    }
 
+    // the codewords of every value of each byte of the info bits, lowest
+    // byte first. the code is linear, so the codeword of some info bits is
+    // the xor of the codewords of their bytes.
+    const ENCODE_TABLE: [[u32; 256]; 4] = Self::encode_table();
+
+    const fn encode_table() -> [[u32; 256]; 4] {
+        let mut table = [[0; 256]; 4];
+        let mut byte = 0;
+        while byte < K.div_ceil(8) {
+            let mut value = 0;
+            while value < 256 {
+                table[byte][value] = Self::encode_bits((value as u32) << (8 * byte));
+                value += 1;
+            }
+            byte += 1;
+        }
+        table
+    }
 
-        // calculate parity bits for upper 4 bits of the byte:
-        let p1 = c3 ^ c5 ^ c7;
-        let p2 = c3 ^ c6 ^ c7;
-        let p4 = c5 ^ c6 ^ c7;
+    // a table lookup per byte of the info bits.
+    pub(super) fn encode_block(info: u32) -> u32 {
+        if cfg!(feature = "bitwise-hamming") {
+            return Self::encode_bits(info);
+        }
+        Self::ENCODE_TABLE[..K.div_ceil(8)]
+            .iter()
+            .enumerate()
+            .fold(0, |codeword, (byte, table)| {
+                codeword ^ table[(info >> (8 * byte)) as usize & 0xff]
+            })
+    }
 
+    // place the k info bits on the non power of two positions and
+    // derive the parity bits from the syndrome of the info bits.
+    pub(super) const fn encode_bits(info: u32) -> u32 {
+        let mut codeword = 0u32;
+        let mut syndrome = 0;
+        let mut info_bit = K;
+        let mut position = 1;
+        while position <= N {
+            if !position.is_power_of_two() {
+                info_bit -= 1;
+                if (info >> info_bit) & 1 == 1 {
+                    codeword |= Self::mask(position);
+                    syndrome ^= position;
+                }
+            }
+            position += 1;
+        }
+        let mut parity = 0;
+        while parity < N - K {
+            if (syndrome >> parity) & 1 == 1 {
+                codeword |= Self::mask(1 << parity);
+            }
+            parity += 1;
+        }
+        codeword
+    }
 
-        // encode the byte with parity bits.
-        let segment_up = p1 << 7 | p2 << 6 | c3 << 5 | p4 << 4 | c5 << 3 | c6 << 2 | c7 << 1;
-        //print!("upper bits: {}, segment: {}. ",(byte>>4), &segment_up);
-        segments.push(segment_up);
+    // codeword positions of the first info bits, left out when shortening.
+    fn shortened_positions(shortening: usize) -> impl Iterator<Item = usize> {
+        (1..=N)
+            .filter(|position| !position.is_power_of_two())
+            .take(shortening)
+    }
+
+    // positions a codeword with the given shortening is sent with.
+    fn sent_positions(shortening: usize) -> impl Iterator<Item = usize> {
+        (1..=N).filter(move |&position| {
+            !Self::shortened_positions(shortening).any(|shortened| shortened == position)
+        })
+    }
 
+    // only the last codeword of a stream is shortened.
+    fn shortening_of(index: usize, codewords: usize, shortened: usize) -> usize {
+        if index + 1 == codewords {
+            shortened
+        } else {
+            0
+        }
+    }
 
-        // extract info bits from lower 4 bits of the byte.
-        let c3 = (byte & 0b0000_1000) >> 3;
-        let c5 = (byte & 0b0000_0100) >> 2;
-        let c6 = (byte & 0b0000_0010) >> 1;
-        let c7 = byte & 0b0000_0001;
+    // codewords in a received stream of the given number of bytes.
+    fn codewords(&self, bytes: usize, shortened: usize) -> usize {
+        if self.packed {
+            (8 * bytes + shortened) / N
+        } else {
+            bytes / Self::CODEWORD_BYTES
+        }
+    }
 
-        // pairty for lower 4 bits of the byte:
-        let p1 = c3 ^ c5 ^ c7;
-        let p2 = c3 ^ c6 ^ c7;
-        let p4 = c5 ^ c6 ^ c7; 
+    // decodes a stream whose last codeword was shortened by the given number
+    // of info bits.
+    fn decode_shortened(&mut self, data: &[u8], shortened: usize) -> Result<Vec<u8>> {
+        self.stats = SegmentStats::default();
+        self.codewords = CodewordStats::default();
+        let codewords = self.codewords(data.len(), shortened);
+        let mut original_data = vec![];
+        let mut bits = BitWriter::default();
+        let mut unpacker = BitReader::new(data);
+        for index in 0..codewords {
+            let shortening = Self::shortening_of(index, codewords, shortened);
+            let codeword = if self.packed {
+                Self::sent_positions(shortening)
+                    .filter(|_| unpacker.read(1) == Some(1))
+                    .fold(0, |word, position| word | Self::mask(position))
+            } else {
+                data[index * Self::CODEWORD_BYTES..(index + 1) * Self::CODEWORD_BYTES]
+                    .iter()
+                    .fold(0u32, |word, &byte| word << 8 | byte as u32)
+            };
+            let info = self.decode_block(index, codeword, shortening);
+            bits.write(info, K - shortening, &mut original_data);
+        }
+        tracing::debug!(
+            codewords,
+            corrected = self.stats.corrected,
+            uncorrectable = self.stats.uncorrectable,
+            "decoded"
+        );
+        Ok(original_data)
+    }
 
-        // encode the byte with parity bits.
-        let segment_low = p1 << 7 | p2 << 6 | c3 << 5 | p4 << 4 | c5 << 3 | c6 << 2 | c7 << 1;
-        //print!("lower bits: {}, segment: {}. \n",(byte&15), &segment_low);
-        segments.push(segment_low);
+    // correct a single bit error and return the info bits. the shortened
+    // positions are known to be zero, an error pointing at one of them is
+    // beyond correction.
+    fn decode_block(&mut self, index: usize, mut codeword: u32, shortening: usize) -> u32 {
+        for position in Self::shortened_positions(shortening) {
+            codeword &= !Self::mask(position);
+        }
+        let error_index = Self::error_index(codeword);
+        if Self::shortened_positions(shortening).any(|position| position == error_index) {
+            tracing::trace!(codeword, error_index, "uncorrectable");
+            self.stats.uncorrectable += 1;
+            self.codewords.uncorrectable.push(index);
+        } else if error_index != 0 {
+            tracing::trace!(codeword, error_index, "corrected");
+            codeword ^= Self::mask(error_index);
+            self.stats.corrected += 1;
+            self.codewords.corrected.push(index);
+        } else {
+            self.stats.passed += 1;
+        }
+        Self::info_bits(codeword)
     }
 
-    // interleave the segments.
-    let interleave_encoded = interleave_segments(&mut segments);
-    interleave_encoded
-}
+    // Chase decoding: the hard decision is tried with every combination of
+    // flips on the least reliable positions, the single error correction of
+    // each try gives a candidate and the one disagreeing least with the
+    // ratios wins. erased bits have a ratio of zero and are flipped first, so
+    // up to two erasures per codeword are filled. llrs[i] belongs to codeword
+    // position i + 1.
+    fn decode_block_soft(&mut self, index: usize, llrs: &[f32]) -> u32 {
+        let hard = (1..=N)
+            .filter(|&position| llrs[position - 1] < 0.0)
+            .fold(0, |word, position| word | Self::mask(position));
+        let mut positions = (1..=N).collect::<Vec<_>>();
+        positions.sort_by(|&a, &b| llrs[a - 1].abs().total_cmp(&llrs[b - 1].abs()));
+        let unreliable = &positions[..CHASE_POSITIONS.min(N)];
+
+        let cost = |codeword: u32| -> f32 {
+            (1..=N)
+                .filter(|&position| (codeword ^ hard) & Self::mask(position) != 0)
+                .map(|position| llrs[position - 1].abs())
+                .sum()
+        };
+        let mut best = (f32::INFINITY, hard);
+        for flips in 0..1usize << unreliable.len() {
+            let mut codeword = unreliable
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| (flips >> i) & 1 == 1)
+                .fold(hard, |word, (_, &position)| word ^ Self::mask(position));
+            let error_index = Self::error_index(codeword);
+            if error_index != 0 {
+                codeword ^= Self::mask(error_index);
+            }
+            let cost = cost(codeword);
+            if cost < best.0 {
+                best = (cost, codeword);
+            }
+        }
+        if best.1 != hard {
+            self.stats.corrected += 1;
+            self.codewords.corrected.push(index);
+        } else {
+            self.stats.passed += 1;
+        }
+        Self::info_bits(best.1)
+    }
 
+    // info bits reside on the non power of two positions.
+    pub(super) fn info_bits(codeword: u32) -> u32 {
+        let mut info = 0u32;
+        for position in (1..=N).filter(|p| !p.is_power_of_two()) {
+            info = info << 1 | ((codeword & Self::mask(position)) != 0) as u32;
+        }
+        info
+    }
 
+    // the syndromes of every value of each byte of a codeword, lowest byte
+    // first, xored like the codewords of ENCODE_TABLE.
+    const SYNDROME_TABLE: [[u8; 256]; 4] = Self::syndrome_table();
+
+    const fn syndrome_table() -> [[u8; 256]; 4] {
+        let mut table = [[0; 256]; 4];
+        let mut byte = 0;
+        while byte < Self::CODEWORD_BYTES {
+            let mut value = 0;
+            while value < 256 {
+                table[byte][value] = Self::error_index_bits((value as u32) << (8 * byte)) as u8;
+                value += 1;
+            }
+            byte += 1;
+        }
+        table
+    }
 
-// perform block interleaving on the segments.
-fn interleave_segments(segments: &mut Vec<u8>) -> Vec<u8> {
-    let mut interleaved_data = vec![];
-    let bytes = segments.len();
+    // a table lookup per byte of the codeword.
+    pub(super) fn error_index(codeword: u32) -> usize {
+        if cfg!(feature = "bitwise-hamming") {
+            return Self::error_index_bits(codeword);
+        }
+        Self::SYNDROME_TABLE[..Self::CODEWORD_BYTES]
+            .iter()
+            .enumerate()
+            .fold(0, |index, (byte, table)| {
+                index ^ table[(codeword >> (8 * byte)) as usize & 0xff] as usize
+            })
+    }
 
-    // add padding to make the number of bytes a multiple of 8.
-    if bytes % 8 != 0 {
-        add_padding(segments);
+    // performs xor of positions of bits set to 1.
+    pub(super) const fn error_index_bits(codeword: u32) -> usize {
+        let mut index = 0;
+        let mut position = 1;
+        while position <= N {
+            if codeword & Self::mask(position) != 0 {
+                index ^= position;
+            }
+            position += 1;
+        }
+        index
     }
 
-    // block is 8 bytes long.
-    for block in (0..bytes).step_by(8) {
-        let interleaved_block = interleave_block(&segments[block..block + 8].to_vec());
-        interleaved_data.extend(interleaved_block);
+    pub(super) const fn mask(position: usize) -> u32 {
+        1 << (8 * Self::CODEWORD_BYTES - position)
     }
-    interleaved_data
 }
 
-// interleave 8 bytes of data.
-fn interleave_block(block: &Vec<u8>) -> Vec<u8> {
-    let mut interleave = vec![];
-    let mut interleaved_byte = 0b0000_0000;
-    let mut count = 0b0u8;
-    for i in 0..8 {
-        for byte in block.into_iter() {
-            interleaved_byte |= ((byte >> (7-i)) & 1 ) << (7-count);
-            count += 1;
-            if count == 8 { // we have interleaved 8 bits
-                interleave.push(interleaved_byte);
-                interleaved_byte = 0b0000_0000;
-                count = 0;
+impl<const N: usize, const K: usize> Coder for Hamming<N, K> {
+    fn name(&self) -> String {
+        if self.packed {
+            format!("Hamming({},{}) packed", N, K)
+        } else {
+            format!("Hamming({},{})", N, K)
+        }
+    }
+
+    fn encode(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+        self.shortened = self.shortening(data.len()) as usize;
+        let codewords = (8 * data.len()).div_ceil(K);
+        let mut segments = vec![];
+        let mut bits = BitReader::new(data);
+        let mut packer = BitWriter::default();
+        for index in 0..codewords {
+            let shortening = Self::shortening_of(index, codewords, self.shortened);
+            let codeword = Self::encode_block(bits.read(K - shortening).unwrap());
+            if self.packed {
+                for position in Self::sent_positions(shortening) {
+                    packer.write(
+                        (codeword & Self::mask(position) != 0) as u32,
+                        1,
+                        &mut segments,
+                    );
+                }
+            } else {
+                segments.extend_from_slice(&codeword.to_be_bytes()[4 - Self::CODEWORD_BYTES..]);
             }
-        }    
+        }
+        packer.flush(&mut segments);
+        tracing::debug!(
+            bytes = data.len(),
+            codewords,
+            shortened = self.shortened,
+            "encoded"
+        );
+        Ok(segments)
     }
-    interleave
-}
 
+    fn decode(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+        self.decode_shortened(data, self.shortened)
+    }
 
-fn decode_data(data: &[u8]) -> Vec<u8> {
-    let deinterleaved = interleave_segments(&mut data.to_vec());
+    fn decode_batch(&mut self, data: &[u8], data_bytes: usize) -> Result<Vec<u8>> {
+        self.decode_shortened(data, self.shortening(data_bytes) as usize)
+    }
 
-    // correct the errors in the deinterleaved data.
-    let corrected = deinterleaved.iter().map(|byte| {
-        let error_index = get_error_index(&byte);
-        if error_index != 0 {// check if error occured.
-            byte ^ (1 << 8-error_index) // flip the bit
-        } 
-        else {
-            *byte
+    // the fewest whole bytes filling whole codewords. packed codewords share
+    // bytes, so only unpacked codes can split the stream.
+    fn block(&self) -> Option<(usize, usize)> {
+        if self.packed {
+            return None;
         }
-    }).collect::<Vec<u8>>();
+        let bytes = (1..=K).find(|bytes| 8 * bytes % K == 0)?;
+        Some((bytes, 8 * bytes / K * Self::CODEWORD_BYTES))
+    }
 
-    // decode the corrected data.
-    let decoded = corrected.iter().map(|byte| {
-        let info_byte = get_info_bits(&byte);
-        info_byte
-    }).collect::<Vec<u8>>();
+    fn shortening(&self, data_bytes: usize) -> u32 {
+        ((K - 8 * data_bytes % K) % K) as u32
+    }
 
-    // remove padding from the decoded data.
-    //remove_padding(&mut decoded.to_vec());
+    fn codeword_bits(&self) -> Option<usize> {
+        Some(K)
+    }
 
-    // merge the upper and lower info bits to form the original data.
-    let mut original_data = vec![];
-    for i in (0..decoded.len()).step_by(2) {
-        let upper = decoded[i];
-        let lower = decoded[i + 1];
-        let merged = merge_info_bits(upper, lower);
-        original_data.push(merged);
+    fn decode_soft(&mut self, llrs: &[f32]) -> Result<Vec<u8>> {
+        self.stats = SegmentStats::default();
+        self.codewords = CodewordStats::default();
+        // reorder the ratios of every byte to the MSB first bit order of the
+        // codewords, which then start every N or every 8 * bytes ratios.
+        let llrs = llrs
+            .chunks_exact(8)
+            .flat_map(|byte| byte.iter().rev().copied())
+            .collect::<Vec<_>>();
+        let codewords = self.codewords(llrs.len() / 8, self.shortened);
+        let mut original_data = vec![];
+        let mut bits = BitWriter::default();
+        let mut received = llrs.iter();
+        for index in 0..codewords {
+            let shortening = Self::shortening_of(index, codewords, self.shortened);
+            // the shortened positions are known to be zero.
+            let mut codeword = [f32::INFINITY; N];
+            if self.packed {
+                for position in Self::sent_positions(shortening) {
+                    codeword[position - 1] = received.next().copied().unwrap_or(0.0);
+                }
+            } else {
+                let segment = &llrs[index * 8 * Self::CODEWORD_BYTES..][..N];
+                for position in Self::sent_positions(shortening) {
+                    codeword[position - 1] = segment[position - 1];
+                }
+            }
+            let info = self.decode_block_soft(index, &codeword);
+            bits.write(info, K - shortening, &mut original_data);
+        }
+        Ok(original_data)
+    }
+
+    fn codeword_stats(&self) -> Option<CodewordStats> {
+        Some(self.codewords.clone())
+    }
+
+    fn segment_stats(&self) -> Option<SegmentStats> {
+        Some(self.stats)
     }
-    original_data
-    
 }
 
-// performs xor of positions of bits set to 1.
-fn get_error_index (byte: &u8) -> u8 {
-    let mut error_index = 0;
-    for i in 1..8 {
-        if byte & (1 << i) != 0 {
-            error_index ^= 8-i;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the benchmark includes this file too, so no generator from the crate.
+    fn payload(len: usize) -> Vec<u8> {
+        (0..len as u32)
+            .map(|i| (i.wrapping_mul(2_654_435_761) >> 24) as u8)
+            .collect()
+    }
+
+    // flips the bit at a codeword position of every codeword, another
+    // position each codeword, and decodes the result.
+    fn decode_with_errors<const N: usize, const K: usize>(
+        coder: &mut Hamming<N, K>,
+        data: &[u8],
+        positions: &[usize],
+    ) -> Vec<u8> {
+        let mut encoded = coder.encode(data).unwrap();
+        let codewords = (8 * data.len()).div_ceil(K);
+        // a packed shortened codeword has fewer positions, leave it be.
+        let flipped = if coder.packed {
+            codewords - 1
+        } else {
+            codewords
+        };
+        for index in 0..flipped {
+            for &position in positions {
+                let position = (index + position) % N + 1;
+                let bit = if coder.packed {
+                    index * N + position - 1
+                } else {
+                    8 * index * Hamming::<N, K>::CODEWORD_BYTES + position - 1
+                };
+                encoded[bit / 8] ^= 0x80 >> (bit % 8);
+            }
         }
+        coder.decode(&encoded).unwrap()
     }
-    error_index
-}
 
-// info bits reside on indecies 2, 4, 5, 6.
-//  always returns byte with infor bits at the rightmost position.
-fn get_info_bits (byte: &u8) -> u8 {
-    let mut info_byte = 0b0000_0000;
-    info_byte |= ((byte >> 5) & 1) << 3;
-    info_byte |= ((byte >> 3) & 1) << 2;
-    info_byte |= ((byte >> 2) & 1) << 1;
-    info_byte |= (byte >> 1) & 1;
-    info_byte
-}
+    fn corrects_single_errors<const N: usize, const K: usize>() {
+        for mut coder in [Hamming::<N, K>::new(), Hamming::<N, K>::packed()] {
+            for len in [1, 2, 3, 7, 11, 26, 100, 1000] {
+                let data = payload(len);
+                let encoded = coder.encode(&data).unwrap();
+                assert_eq!(coder.decode(&encoded).unwrap(), data, "{}", coder.name());
+
+                assert_eq!(
+                    decode_with_errors(&mut coder, &data, &[0]),
+                    data,
+                    "{} of {} bytes",
+                    coder.name(),
+                    len
+                );
+                assert_eq!(coder.segment_stats().unwrap().uncorrectable, 0);
+            }
+        }
+    }
 
-fn remove_padding(data: &mut Vec<u8>) {
-    let padding = data.pop().unwrap() as usize;
-    //println!("Padding: {}", padding);
-    for _ in 0..padding {
-        data.pop();
+    #[test]
+    fn hamming_corrects_a_single_error_per_codeword() {
+        corrects_single_errors::<3, 1>();
+        corrects_single_errors::<7, 4>();
+        corrects_single_errors::<15, 11>();
+        corrects_single_errors::<31, 26>();
     }
-}
 
-fn add_padding(data: &mut Vec<u8>) {
-    let padding = 8 - data.len() % 8;
-    //println!("Padding: {}", padding);
-    for i in 0..padding {
-        data.push(i as u8);
+    #[test]
+    fn hamming_miscorrects_two_errors_per_codeword() {
+        let data = payload(64);
+        let mut coder = Hamming::<7, 4>::new();
+        // the syndrome of two errors points at a third position.
+        let decoded = decode_with_errors(&mut coder, &data, &[0, 1]);
+        assert_ne!(decoded, data);
+        let stats = coder.segment_stats().unwrap();
+        assert_eq!(stats.corrected, 128);
+        assert_eq!(stats.passed, 0);
     }
-}
 
-fn merge_info_bits(upper: u8, lower: u8) -> u8 {
-    let mut info_byte = 0b0000_0000;
-    info_byte |= (upper << 4) | lower;
-    info_byte
-}
+    #[test]
+    fn hamming_codes_nothing_to_nothing() {
+        let mut coder = Hamming::<15, 11>::packed();
+        assert!(coder.encode(&[]).unwrap().is_empty());
+        assert!(coder.decode(&[]).unwrap().is_empty());
+    }
+
+    // ratios of the encoded bits in `to_bits` order, with the given
+    // codeword positions of every unpacked codeword erased.
+    fn soft_decode<const N: usize, const K: usize>(
+        coder: &mut Hamming<N, K>,
+        data: &[u8],
+        erased: &[usize],
+    ) -> Vec<u8> {
+        let encoded = coder.encode(data).unwrap();
+        let codeword_bits = 8 * Hamming::<N, K>::CODEWORD_BYTES;
+        let mut llrs = encoded
+            .iter()
+            .flat_map(|&byte| (0..8).map(move |i| if byte >> i & 1 == 0 { 1.0 } else { -1.0 }))
+            .collect::<Vec<f32>>();
+        for codeword in llrs.chunks_mut(codeword_bits) {
+            for &position in erased {
+                // position p is bit 8 * bytes - p of the MSB first word.
+                let bit = codeword_bits - position;
+                codeword[(codeword_bits - 1 - bit) / 8 * 8 + bit % 8] = 0.0;
+            }
+        }
+        coder.decode_soft(&llrs).unwrap()
+    }
 
-fn gcd(a: u32, b: u32) -> u32 {
-    if b == 0 {
-        a
-    } else {
-        gcd(b, a % b)
+    #[test]
+    fn chase_decoding_fills_two_erasures_per_codeword() {
+        let data = payload(100);
+        let mut coder = Hamming::<7, 4>::new();
+        assert_eq!(soft_decode(&mut coder, &data, &[]), data);
+        assert_eq!(soft_decode(&mut coder, &data, &[3, 6]), data);
+        let mut coder = Hamming::<15, 11>::new();
+        assert_eq!(soft_decode(&mut coder, &data, &[1, 12]), data);
     }
-}
 
-fn lcm(a: u32, b: u32) -> u32 {
-    a * b / gcd(a, b)
-}
+    #[test]
+    fn encode_table_matches_the_bit_loops() {
+        for info in 0..1 << 11 {
+            assert_eq!(
+                Hamming::<15, 11>::encode_block(info),
+                Hamming::<15, 11>::encode_bits(info)
+            );
+        }
+        for codeword in 0..1 << 16 {
+            assert_eq!(
+                Hamming::<15, 11>::error_index(codeword),
+                Hamming::<15, 11>::error_index_bits(codeword)
+            );
+        }
+    }
 
+    // the one test setting the files every run of the process reads.
+    #[test]
+    fn shortened_batches_decode_while_later_ones_encode() {
+        use mmcp::{
+            analytics,
+            channel::Channel,
+            interleaver::NoInterleaver,
+            pipeline::{self, Files},
+        };
+
+        // five batches, the last one shortened, most of them on their way
+        // while the last one is encoded.
+        let payload = payload(5 * pipeline::CHUNK_SIZE + 3);
+        pipeline::set_files(Files::in_memory("shortened", payload));
+        async_std::task::block_on(async {
+            let coders: [Box<dyn Coder>; 2] = [
+                Box::new(Hamming::<15, 11>::new()),
+                Box::new(Hamming::<31, 26>::new()),
+            ];
+            for mut coder in coders {
+                let mut channel = Channel::new(0.0, 0.1).with_seed(1);
+                let run_metrics = pipeline::run(coder.as_mut(), &NoInterleaver, &mut channel)
+                    .await
+                    .unwrap();
+                let analytics = analytics::analyze(&channel, run_metrics).await.unwrap();
+                assert_eq!(analytics.channel_bit_errors(), 0);
+                assert_eq!(analytics.residual_bit_errors(), 0, "{}", coder.name());
+                assert_eq!(analytics.missing_bytes(), 0, "{}", coder.name());
+            }
+        });
+    }
+}
@@ -0,0 +1,63 @@
+use color_eyre::eyre::Result;
+use mmcp::{
+    coder::{Coder, FrameStats, SegmentStats},
+    interleaver::Interleaver,
+};
+
+// outer code, interleaver and inner code in series, so bursts left by
+// the inner decoder are spread over several outer codewords.
+pub(super) struct Concatenated {
+    outer: Box<dyn Coder>,
+    interleaver: Box<dyn Interleaver>,
+    inner: Box<dyn Coder>,
+}
+
+impl Concatenated {
+    pub(super) fn new(
+        outer: Box<dyn Coder>,
+        interleaver: Box<dyn Interleaver>,
+        inner: Box<dyn Coder>,
+    ) -> Self {
+        Self {
+            outer,
+            interleaver,
+            inner,
+        }
+    }
+}
+
+impl Coder for Concatenated {
+    fn name(&self) -> String {
+        format!(
+            "{} + {} + {}",
+            self.outer.name(),
+            self.interleaver.name(),
+            self.inner.name()
+        )
+    }
+
+    fn encode(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+        let outer = self.outer.encode(data)?;
+        self.inner.encode(&self.interleaver.interleave(&outer))
+    }
+
+    fn decode(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+        let inner = self.inner.decode(data)?;
+        self.outer.decode(&self.interleaver.deinterleave(&inner))
+    }
+
+    // the outer decoder has the final say on every segment.
+    fn segment_stats(&self) -> Option<SegmentStats> {
+        self.outer.segment_stats()
+    }
+
+    fn layer_stats(&self) -> Vec<(String, SegmentStats)> {
+        let mut layers = self.outer.layer_stats();
+        layers.extend(self.inner.layer_stats());
+        layers
+    }
+
+    fn frame_stats(&self) -> Option<FrameStats> {
+        self.outer.frame_stats()
+    }
+}
@@ -0,0 +1,370 @@
+use color_eyre::eyre::Result;
+use mmcp::{
+    coder::{Coder, SegmentStats},
+    framing::crc32,
+    rng::SplitMix64,
+};
+
+// robust soliton parameters.
+const SOLITON_C: f64 = 0.1;
+const SOLITON_DELTA: f64 = 0.5;
+// dense parity symbols of the precode, they let the decoder recover the few
+// source symbols an LT code leaves uncovered.
+const PRECODE_SYMBOLS: usize = 16;
+// block index, packet id and payload length precede the symbol, the CRC follows it.
+const HEADER_BYTES: usize = 12;
+const CRC_BYTES: usize = 4;
+
+// packet id and payload of an intact packet.
+type Packet = (u32, Vec<u8>);
+
+// raptor style fountain code: the payload is split into source blocks of up to
+// `block_symbols` symbols, a block is extended by a dense precode and sent as
+// (1 + overhead) * k self-describing LT packets, each the XOR of a pseudo
+// random subset of the intermediate symbols. corrupted packets are dropped,
+// any sufficient subset restores the block.
+pub(super) struct Fountain {
+    symbol_size: usize,
+    block_symbols: usize,
+    overhead: f64,
+    stats: SegmentStats,
+}
+
+impl Fountain {
+    pub(super) fn new(symbol_size: usize, block_symbols: usize, overhead: f64) -> Self {
+        assert!(
+            symbol_size > 0 && block_symbols > 0,
+            "empty fountain blocks"
+        );
+        Self {
+            symbol_size,
+            block_symbols,
+            overhead,
+            stats: SegmentStats::default(),
+        }
+    }
+
+    // encoded packets sent for a block of k source symbols.
+    fn packets(&self, k: usize) -> u32 {
+        (k as f64 * (1.0 + self.overhead)).ceil() as u32
+    }
+
+    fn encode_block(&self, index: u32, block: &[u8], total: u32, encoded: &mut Vec<u8>) {
+        let mut symbols = block
+            .chunks(self.symbol_size)
+            .map(<[u8]>::to_vec)
+            .collect::<Vec<_>>();
+        if let Some(last) = symbols.last_mut() {
+            last.resize(self.symbol_size, 0);
+        }
+        let k = symbols.len();
+        for sources in precode(k) {
+            let mut parity = vec![0u8; self.symbol_size];
+            for source in sources {
+                parity
+                    .iter_mut()
+                    .zip(&symbols[source])
+                    .for_each(|(p, s)| *p ^= s);
+            }
+            symbols.push(parity);
+        }
+        let soliton = robust_soliton(symbols.len());
+        for id in 0..self.packets(k) {
+            let mut payload = vec![0u8; self.symbol_size];
+            for source in neighbours(index, id, symbols.len(), &soliton) {
+                payload
+                    .iter_mut()
+                    .zip(&symbols[source])
+                    .for_each(|(p, s)| *p ^= s);
+            }
+            let start = encoded.len();
+            encoded.extend_from_slice(&index.to_be_bytes());
+            encoded.extend_from_slice(&id.to_be_bytes());
+            encoded.extend_from_slice(&total.to_be_bytes());
+            encoded.extend_from_slice(&payload);
+            let crc = crc32(&encoded[start..]);
+            encoded.extend_from_slice(&crc.to_be_bytes());
+        }
+    }
+
+    // peeling decoder, returns the block if every source symbol was recovered.
+    fn decode_block(&self, index: u32, len: usize, packets: Vec<Packet>) -> Option<Vec<u8>> {
+        let k = len.div_ceil(self.symbol_size);
+        let l = k + PRECODE_SYMBOLS;
+        let soliton = robust_soliton(l);
+        // every precode symbol XORed with its sources gives a zero symbol.
+        let constraints = precode(k).into_iter().enumerate().map(|(j, mut sources)| {
+            sources.push(k + j);
+            (sources, vec![0u8; self.symbol_size])
+        });
+        let mut equations = packets
+            .into_iter()
+            .map(|(id, payload)| (neighbours(index, id, l, &soliton), payload))
+            .chain(constraints)
+            .collect::<Vec<_>>();
+        let mut references = vec![vec![]; l];
+        for (packet, (sources, _)) in equations.iter().enumerate() {
+            sources.iter().for_each(|&s| references[s].push(packet));
+        }
+
+        let mut known: Vec<Option<Vec<u8>>> = vec![None; l];
+        let mut ripple = (0..equations.len())
+            .filter(|&p| equations[p].0.len() == 1)
+            .collect::<Vec<_>>();
+        while let Some(packet) = ripple.pop() {
+            let Some(&source) = equations[packet].0.first() else {
+                continue;
+            };
+            if known[source].is_some() {
+                continue;
+            }
+            let symbol = equations[packet].1.clone();
+            for &other in &references[source] {
+                let (sources, payload) = &mut equations[other];
+                if let Some(position) = sources.iter().position(|&s| s == source) {
+                    sources.swap_remove(position);
+                    payload.iter_mut().zip(&symbol).for_each(|(p, s)| *p ^= s);
+                    if sources.len() == 1 {
+                        ripple.push(other);
+                    }
+                }
+            }
+            known[source] = Some(symbol);
+        }
+
+        // the ripple ran dry, solve the remaining equations by gaussian elimination.
+        let unknown = (0..l).filter(|&s| known[s].is_none()).collect::<Vec<_>>();
+        if !unknown.is_empty() {
+            let mut column = vec![usize::MAX; l];
+            unknown.iter().enumerate().for_each(|(c, &s)| column[s] = c);
+            let words = unknown.len().div_ceil(64);
+            let mut rows = equations
+                .into_iter()
+                .filter(|(sources, _)| !sources.is_empty())
+                .map(|(sources, payload)| {
+                    let mut row = vec![0u64; words];
+                    for c in sources.iter().map(|&s| column[s]) {
+                        row[c / 64] ^= 1 << (c % 64);
+                    }
+                    (row, payload)
+                })
+                .collect::<Vec<_>>();
+            for c in 0..unknown.len() {
+                let bit = |row: &[u64]| row[c / 64] >> (c % 64) & 1 == 1;
+                let pivot = (c..rows.len()).find(|&r| bit(&rows[r].0))?;
+                rows.swap(c, pivot);
+                let (pivot_row, pivot_payload) = rows[c].clone();
+                for (r, (row, payload)) in rows.iter_mut().enumerate() {
+                    if r != c && bit(row) {
+                        row.iter_mut().zip(&pivot_row).for_each(|(a, b)| *a ^= b);
+                        payload
+                            .iter_mut()
+                            .zip(&pivot_payload)
+                            .for_each(|(a, b)| *a ^= b);
+                    }
+                }
+            }
+            for (&source, (_, payload)) in unknown.iter().zip(rows) {
+                known[source] = Some(payload);
+            }
+        }
+
+        let mut block = known
+            .into_iter()
+            .take(k)
+            .collect::<Option<Vec<_>>>()?
+            .concat();
+        block.truncate(len);
+        Some(block)
+    }
+}
+
+// ideal soliton plus the robust spike, as a cumulative distribution over degrees 1..=k.
+fn robust_soliton(k: usize) -> Vec<f64> {
+    let kf = k as f64;
+    let r = SOLITON_C * (kf / SOLITON_DELTA).ln() * kf.sqrt();
+    let spike = (kf / r.max(1.0)).floor().max(1.0) as usize;
+    let weights = (1..=k)
+        .map(|d| {
+            let ideal = if d == 1 {
+                1.0 / kf
+            } else {
+                1.0 / (d * (d - 1)) as f64
+            };
+            let robust = match d {
+                d if d < spike => r / (d as f64 * kf),
+                d if d == spike => r * (r / SOLITON_DELTA).ln().max(0.0) / kf,
+                _ => 0.0,
+            };
+            ideal + robust
+        })
+        .collect::<Vec<_>>();
+    let total = weights.iter().sum::<f64>();
+    weights
+        .iter()
+        .scan(0.0, |sum, w| {
+            *sum += w / total;
+            Some(*sum)
+        })
+        .collect()
+}
+
+// source symbols of every precode symbol, each source is taken with probability 1/2.
+fn precode(k: usize) -> Vec<Vec<usize>> {
+    (0..PRECODE_SYMBOLS)
+        .map(|j| {
+            let mut rng = SplitMix64::new(u64::MAX - j as u64);
+            (0..k).filter(|_| rng.next_u64() & 1 == 1).collect()
+        })
+        .collect()
+}
+
+// intermediate symbols combined into packet `id`, derived from the packet header alone.
+fn neighbours(block: u32, id: u32, l: usize, soliton: &[f64]) -> Vec<usize> {
+    let mut rng = SplitMix64::new((block as u64) << 32 | id as u64);
+    let uniform = rng.next_f64();
+    let degree = soliton.iter().position(|&p| uniform <= p).unwrap_or(l - 1) + 1;
+    let mut sources = Vec::with_capacity(degree);
+    while sources.len() < degree {
+        let source = rng.below(l);
+        if !sources.contains(&source) {
+            sources.push(source);
+        }
+    }
+    sources
+}
+
+impl Coder for Fountain {
+    fn name(&self) -> String {
+        format!(
+            "Fountain({}x{} B, +{:.0}%)",
+            self.block_symbols,
+            self.symbol_size,
+            self.overhead * 100.0
+        )
+    }
+
+    fn encode(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+        let mut encoded = vec![];
+        for (index, block) in data
+            .chunks(self.block_symbols * self.symbol_size)
+            .enumerate()
+        {
+            self.encode_block(index as u32, block, data.len() as u32, &mut encoded);
+        }
+        Ok(encoded)
+    }
+
+    fn decode(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+        self.stats = SegmentStats::default();
+        let block_bytes = self.block_symbols * self.symbol_size;
+        let packet_bytes = HEADER_BYTES + self.symbol_size + CRC_BYTES;
+
+        // packets whose CRC fails are treated as erasures, any intact one
+        // tells the payload length and so the number of blocks.
+        let mut total = 0;
+        let mut blocks: Vec<Vec<Packet>> = vec![];
+        for packet in data.chunks_exact(packet_bytes) {
+            let (content, crc) = packet.split_at(packet_bytes - CRC_BYTES);
+            if crc32(content).to_be_bytes() != crc {
+                continue;
+            }
+            let field =
+                |i: usize| u32::from_be_bytes(content[4 * i..4 * i + 4].try_into().unwrap());
+            let (index, id) = (field(0) as usize, field(1));
+            if total == 0 {
+                total = field(2) as usize;
+                blocks.resize_with(total.div_ceil(block_bytes), Vec::new);
+            }
+            if let Some(block) = blocks.get_mut(index) {
+                block.push((id, content[HEADER_BYTES..].to_vec()));
+            }
+        }
+
+        let mut decoded = vec![];
+        for (index, packets) in blocks.into_iter().enumerate() {
+            let len = block_bytes.min(total - index * block_bytes);
+            let received = packets.len();
+            let sent = self.packets(len.div_ceil(self.symbol_size)) as usize;
+            match self.decode_block(index as u32, len, packets) {
+                Some(block) => {
+                    if received == sent {
+                        self.stats.passed += 1;
+                    } else {
+                        self.stats.corrected += 1;
+                    }
+                    decoded.extend(block);
+                }
+                // unrecoverable blocks are filled with zeros to keep the payload aligned.
+                None => {
+                    self.stats.uncorrectable += 1;
+                    decoded.resize(decoded.len() + len, 0);
+                }
+            }
+        }
+        Ok(decoded)
+    }
+
+    fn segment_stats(&self) -> Option<SegmentStats> {
+        Some(self.stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn payload(len: usize) -> Vec<u8> {
+        let mut rng = SplitMix64::new(len as u64);
+        (0..len).map(|_| rng.next_u64() as u8).collect()
+    }
+
+    // corrupts every packet the filter picks by packet index.
+    fn corrupt(encoded: &mut [u8], packet_bytes: usize, pick: impl Fn(usize) -> bool) {
+        for (index, packet) in encoded.chunks_mut(packet_bytes).enumerate() {
+            if pick(index) {
+                packet[HEADER_BYTES] ^= 0x01;
+            }
+        }
+    }
+
+    #[test]
+    fn round_trips_blocks_of_any_length() {
+        let mut coder = Fountain::new(32, 256, 0.25);
+        for len in [1, 31, 32, 1000, 256 * 32, 3 * 256 * 32 + 5] {
+            let data = payload(len);
+            let encoded = coder.encode(&data).unwrap();
+            assert_eq!(coder.decode(&encoded).unwrap(), data, "{len} bytes");
+            assert_eq!(coder.segment_stats().unwrap().uncorrectable, 0);
+        }
+    }
+
+    #[test]
+    fn restores_blocks_from_the_intact_packets() {
+        let mut coder = Fountain::new(32, 256, 0.25);
+        let data = payload(2 * 256 * 32);
+        let mut encoded = coder.encode(&data).unwrap();
+        // every tenth packet, well within the overhead.
+        corrupt(&mut encoded, HEADER_BYTES + 32 + CRC_BYTES, |index| {
+            index % 10 == 3
+        });
+        assert_eq!(coder.decode(&encoded).unwrap(), data);
+        let stats = coder.segment_stats().unwrap();
+        assert_eq!((stats.corrected, stats.uncorrectable), (2, 0));
+    }
+
+    #[test]
+    fn fills_blocks_short_of_packets_with_zeros() {
+        let mut coder = Fountain::new(32, 256, 0.25);
+        let data = payload(2 * 256 * 32);
+        let mut encoded = coder.encode(&data).unwrap();
+        // every other packet, more than the overhead makes up for.
+        corrupt(&mut encoded, HEADER_BYTES + 32 + CRC_BYTES, |index| {
+            index % 2 == 0
+        });
+        let decoded = coder.decode(&encoded).unwrap();
+        assert_eq!(decoded.len(), data.len());
+        assert!(decoded.iter().all(|&byte| byte == 0));
+        assert_eq!(coder.segment_stats().unwrap().uncorrectable, 2);
+    }
+}
@@ -0,0 +1,179 @@
+// primitive polynomials for GF(2^m), indexed by m.
+const PRIMITIVE_POLYNOMIALS: [u32; 17] = [
+    0, 0, 0b111, 0xB, 0x13, 0x25, 0x43, 0x89, 0x11D, 0x211, 0x409, 0x805, 0x1053, 0x201B, 0x4443,
+    0x8003, 0x1100B,
+];
+
+// arithmetic in GF(2^m) backed by log/antilog tables.
+pub(super) struct Field {
+    m: u32,
+    exp: Vec<u16>,
+    log: Vec<u16>,
+}
+
+impl Field {
+    pub(super) fn new(m: u32) -> Self {
+        assert!(
+            (2..=16).contains(&m),
+            "symbol size must be between 2 and 16 bits"
+        );
+        let order = (1usize << m) - 1;
+        // the exponent table is doubled so products never need a modulo.
+        let mut exp = vec![0u16; 2 * order];
+        let mut log = vec![0u16; order + 1];
+        let mut x = 1u32;
+        for i in 0..order {
+            exp[i] = x as u16;
+            exp[i + order] = x as u16;
+            log[x as usize] = i as u16;
+            x <<= 1;
+            if x > order as u32 {
+                x ^= PRIMITIVE_POLYNOMIALS[m as usize];
+            }
+        }
+        Self { m, exp, log }
+    }
+
+    pub(super) fn bits(&self) -> u32 {
+        self.m
+    }
+
+    // number of non zero elements, which is also the full code length.
+    pub(super) fn order(&self) -> usize {
+        (1 << self.m) - 1
+    }
+
+    pub(super) fn alpha_pow(&self, power: usize) -> u16 {
+        self.exp[power % self.order()]
+    }
+
+    pub(super) fn mul(&self, a: u16, b: u16) -> u16 {
+        if a == 0 || b == 0 {
+            return 0;
+        }
+        self.exp[self.log[a as usize] as usize + self.log[b as usize] as usize]
+    }
+
+    pub(super) fn div(&self, a: u16, b: u16) -> u16 {
+        assert!(b != 0, "division by zero in GF(2^m)");
+        if a == 0 {
+            return 0;
+        }
+        self.exp[self.log[a as usize] as usize + self.order() - self.log[b as usize] as usize]
+    }
+
+    pub(super) fn inv(&self, a: u16) -> u16 {
+        self.div(1, a)
+    }
+
+    // evaluate a polynomial given lowest degree coefficient first.
+    pub(super) fn eval(&self, poly: &[u16], x: u16) -> u16 {
+        poly.iter()
+            .rev()
+            .fold(0, |acc, &coefficient| self.mul(acc, x) ^ coefficient)
+    }
+
+    // multiply two polynomials given lowest degree coefficient first.
+    pub(super) fn poly_mul(&self, a: &[u16], b: &[u16]) -> Vec<u16> {
+        let mut product = vec![0u16; a.len() + b.len() - 1];
+        for (i, &x) in a.iter().enumerate() {
+            for (j, &y) in b.iter().enumerate() {
+                product[i + j] ^= self.mul(x, y);
+            }
+        }
+        product
+    }
+
+    // Berlekamp-Massey: shortest LFSR (error locator) generating the syndromes.
+    pub(super) fn berlekamp_massey(&self, syndromes: &[u16]) -> Vec<u16> {
+        let mut locator = vec![1u16];
+        let mut previous = vec![1u16];
+        let mut length = 0;
+        let mut shift = 1;
+        let mut last_discrepancy = 1u16;
+        for n in 0..syndromes.len() {
+            let discrepancy = (1..=length).fold(syndromes[n], |d, i| {
+                d ^ self.mul(*locator.get(i).unwrap_or(&0), syndromes[n - i])
+            });
+            if discrepancy == 0 {
+                shift += 1;
+                continue;
+            }
+            let scale = self.div(discrepancy, last_discrepancy);
+            let mut next = locator.clone();
+            next.resize(next.len().max(previous.len() + shift), 0);
+            for (i, &coefficient) in previous.iter().enumerate() {
+                next[i + shift] ^= self.mul(scale, coefficient);
+            }
+            if 2 * length <= n {
+                previous = locator;
+                length = n + 1 - length;
+                last_discrepancy = discrepancy;
+                shift = 1;
+            } else {
+                shift += 1;
+            }
+            locator = next;
+        }
+        locator.truncate(length + 1);
+        locator
+    }
+
+    // Chien search: powers p in 0..len for which alpha^-p is a root.
+    pub(super) fn error_positions(&self, locator: &[u16], len: usize) -> Vec<usize> {
+        (0..len)
+            .filter(|&p| {
+                let x = self.alpha_pow(self.order() - p % self.order());
+                self.eval(locator, x) == 0
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_element_has_an_inverse() {
+        for m in [2, 4, 8, 12] {
+            let field = Field::new(m);
+            for a in 1..=field.order() as u16 {
+                assert_eq!(field.mul(a, field.inv(a)), 1, "GF(2^{m})");
+                assert_eq!(field.div(field.mul(a, 7 % a + 1), 7 % a + 1), a);
+            }
+            assert_eq!(field.mul(0, 5), 0);
+        }
+    }
+
+    #[test]
+    fn alpha_generates_the_field() {
+        let field = Field::new(8);
+        let mut powers = (0..field.order())
+            .map(|i| field.alpha_pow(i))
+            .collect::<Vec<_>>();
+        powers.sort();
+        assert_eq!(powers, (1..=255).collect::<Vec<_>>());
+        assert_eq!(field.alpha_pow(field.order()), 1);
+    }
+
+    #[test]
+    fn locator_of_the_syndromes_finds_the_errors() {
+        let field = Field::new(4);
+        // errors of value 1 at x^2 and x^9, syndrome j is a^(2j) + a^(9j).
+        let syndromes = (0..4)
+            .map(|j| field.alpha_pow(2 * j) ^ field.alpha_pow(9 * j))
+            .collect::<Vec<_>>();
+        let locator = field.berlekamp_massey(&syndromes);
+        assert_eq!(locator.len(), 3);
+        assert_eq!(field.error_positions(&locator, 15), [2, 9]);
+        // (1 + a^2 x)(1 + a^9 x) evaluated at a root.
+        assert_eq!(
+            field.eval(
+                &field.poly_mul(&[1, field.alpha_pow(2)], &[1, field.alpha_pow(9)]),
+                field.alpha_pow(13)
+            ),
+            0
+        );
+    }
+}
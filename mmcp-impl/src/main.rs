@@ -1,20 +1,769 @@
 #![forbid(unsafe_code)]
 
-use mmcp::*;
 use color_eyre::eyre::Result;
+#[cfg(feature = "wasm")]
+use mmcp::sandbox::WasmCoder;
+use mmcp::{
+    adaptive::AdaptiveRate,
+    analytics::Analytics,
+    arq::{GoBackN, HybridArq, SelectiveRepeat, StopAndWait},
+    bench::Bench,
+    channel::Channel,
+    coder::{Coder, Uncoded},
+    compression::Compressed,
+    encryption::{Cipher, Encrypted},
+    feedback::{Duplex, Feedback},
+    framing::{CrcFraming, FrameSync, Packetizer},
+    interleaver::{
+        BlockInterleaver, ByteInterleaver, ConvolutionalInterleaver, HelicalInterleaver,
+        Interleaver, NoInterleaver, SeededInterleaver,
+    },
+    line_coding::EightBTenB,
+    monte_carlo::MonteCarlo,
+    pipeline::{Pipeline, PipelineBuilder},
+    plugin::Plugin,
+    puncturing::{PuncturePattern, Punctured},
+    registry::{ChunkCoder, Registry, StreamCoder},
+    sweep::Sweep,
+    *,
+};
+mod bch;
+mod bits;
 mod coder;
-use coder::{decode, encode};
+mod concatenated;
+mod fountain;
+// the stream stages of the other binaries, so the registry offers them too.
+mod galois;
+#[path = "../../mmcp-identity/src/coder.rs"]
+mod identity;
+mod polar;
+mod reed_muller;
+mod reed_solomon;
+mod repetition;
+mod secded;
+#[path = "../../mmcp-student/src/coder.rs"]
+mod student;
+mod turbo;
+use bch::Bch;
+use coder::Hamming;
+use concatenated::Concatenated;
+use fountain::Fountain;
+use polar::Polar;
+use reed_muller::ReedMuller;
+use reed_solomon::ReedSolomon;
+use repetition::Repetition;
+use secded::Secded;
+use turbo::Turbo;
 
-pipeline!(encode, decode);
+// on-air packet size, a fountain packet of 32 byte symbols fills one exactly.
+const PACKET_BYTES: usize = 56;
+const PACKET_LOSSES: [f64; 3] = [0.01, 0.05, 0.1];
+// insertion and deletion probability per bit.
+const SYNC_ERROR_RATES: [f64; 2] = [1e-5, 1e-4];
+// payload bytes per retransmitted frame and how often it is sent at most.
+const ARQ_FRAME_BYTES: usize = 64;
+const ARQ_ATTEMPTS: usize = 8;
+// bytes compressed together, what one residual error costs at most.
+const COMPRESSION_BLOCK_BYTES: usize = 64 * 1024;
+// plaintext bytes under one nonce and, with AES-GCM, one tag.
+const CIPHER_FRAME_BYTES: usize = 256;
+
+// every coder of the workspace under the name `--coder` selects it by.
+fn registry() -> Registry {
+    Registry::new()
+        .register("hamming74", || Box::new(Hamming::<7, 4>::new()))
+        .register("hamming74-packed", || Box::new(Hamming::<7, 4>::packed()))
+        .register("hamming1511", || Box::new(Hamming::<15, 11>::new()))
+        .register("hamming3126", || Box::new(Hamming::<31, 26>::new()))
+        .register("secded", || Box::new(Secded::default()))
+        .register("reed-solomon", || Box::new(ReedSolomon::new(8, 32)))
+        .register("bch", || Box::new(Bch::new(4, 2)))
+        .register("turbo", || Box::new(Turbo::new(6)))
+        .register("polar", || Box::new(Polar::new(256, 128)))
+        .register("reed-muller", || Box::new(ReedMuller::new(3)))
+        .register("fountain", || Box::new(Fountain::new(32, 256, 0.25)))
+        .register("identity", || {
+            Box::new(ChunkCoder::new(
+                "mmcp-identity",
+                identity::encode,
+                identity::decode,
+            ))
+        })
+        .register("student", || {
+            Box::new(StreamCoder::new(
+                "mmcp-student",
+                student::encode,
+                student::decode,
+            ))
+        })
+}
+
+fn interleavers() -> Vec<Box<dyn Interleaver>> {
+    vec![
+        Box::new(ByteInterleaver::new(8, 255)),
+        Box::new(HelicalInterleaver::new(8, 255)),
+        Box::new(SeededInterleaver::new(0x5EED, 8 * 255)),
+    ]
+}
+
+// coders sending short segments, bit interleaving spreads bursts over them.
+fn segment_coders() -> Vec<Box<dyn Coder>> {
+    let mut coders: Vec<Box<dyn Coder>> = vec![
+        Box::new(Hamming::<7, 4>::new()),
+        Box::new(Hamming::<7, 4>::packed()),
+        Box::new(Hamming::<15, 11>::new()),
+        Box::new(Hamming::<31, 26>::new()),
+        Box::new(Secded::default()),
+        Box::new(CrcFraming::new(256, Box::new(Hamming::<7, 4>::new()))),
+    ];
+    // the same concatenation with every interleaving strategy.
+    for interleaver in interleavers() {
+        coders.push(Box::new(Concatenated::new(
+            Box::new(ReedSolomon::new(8, 32)),
+            interleaver,
+            Box::new(Hamming::<7, 4>::new()),
+        )));
+    }
+    coders
+}
+
+fn coders() -> Vec<Box<dyn Coder>> {
+    vec![
+        Box::new(ReedSolomon::new(8, 32)),
+        Box::new(Bch::new(4, 2)),
+        Box::new(Bch::new(5, 3)),
+        Box::new(Turbo::new(6)),
+        Box::new(Polar::new(128, 64)),
+        Box::new(Polar::new(256, 128)),
+        Box::new(Polar::new(512, 256)),
+        Box::new(ReedMuller::new(3)),
+        Box::new(ReedMuller::new(5)),
+        Box::new(Fountain::new(32, 256, 0.25)),
+        // packets are kept contiguous so a failed turbo block erases as few as possible.
+        Box::new(Concatenated::new(
+            Box::new(Fountain::new(32, 256, 0.25)),
+            Box::new(ByteInterleaver::new(1, 48)),
+            Box::new(Turbo::new(6)),
+        )),
+    ]
+}
+
+// every coder together with the interleaver between it and the channel.
+fn runs() -> Vec<(Box<dyn Coder>, Box<dyn Interleaver>)> {
+    let mut runs = vec![];
+    for coder in segment_coders() {
+        runs.push((coder, Box::new(BlockInterleaver) as Box<dyn Interleaver>));
+    }
+    runs.push((Box::new(Hamming::<7, 4>::new()), Box::new(NoInterleaver)));
+    runs.push((
+        Box::new(Hamming::<7, 4>::new()),
+        Box::new(ConvolutionalInterleaver::new(8, 16)),
+    ));
+    for coder in coders() {
+        runs.push((coder, Box::new(NoInterleaver)));
+    }
+    runs
+}
+
+// coders with a soft decoder, run again on channels reporting reliabilities
+// and on channels erasing bits to compare against their hard decision rows.
+fn soft_runs() -> Vec<(Box<dyn Coder>, Box<dyn Interleaver>)> {
+    vec![
+        (Box::new(Hamming::<7, 4>::new()), Box::new(BlockInterleaver)),
+        (
+            Box::new(Hamming::<15, 11>::new()),
+            Box::new(BlockInterleaver),
+        ),
+    ]
+}
+
+// Hamming(7,4) without the unused lowest bit of every segment, at rate 4/7,
+// and without parity bit 4 as well, at rate 2/3.
+fn punctured_coders() -> Vec<Box<dyn Coder>> {
+    vec![
+        Box::new(Punctured::new(
+            PuncturePattern::new(8, vec![vec![1, 2, 3, 4, 5, 6, 7]]),
+            Box::new(Hamming::<7, 4>::new()),
+        )),
+        Box::new(Punctured::new(
+            PuncturePattern::new(8, vec![vec![1, 2, 3, 5, 6, 7]]),
+            Box::new(Hamming::<7, 4>::new()),
+        )),
+    ]
+}
+
+// coders packetized for channels losing whole packets.
+fn packet_coders() -> Vec<Box<dyn Coder>> {
+    vec![
+        Box::new(Packetizer::new(
+            PACKET_BYTES,
+            Box::new(Hamming::<7, 4>::new()),
+        )),
+        Box::new(Packetizer::new(
+            PACKET_BYTES,
+            Box::new(Fountain::new(32, 256, 0.25)),
+        )),
+    ]
+}
+
+// codes behind compression. a residual error anywhere in a compressed block
+// takes the whole block with it.
+fn compressed_coders() -> Vec<Box<dyn Coder>> {
+    vec![
+        Box::new(Compressed::new(
+            COMPRESSION_BLOCK_BYTES,
+            Box::new(Hamming::<7, 4>::new()),
+        )),
+        Box::new(Compressed::new(
+            COMPRESSION_BLOCK_BYTES,
+            Box::new(Concatenated::new(
+                Box::new(ReedSolomon::new(8, 32)),
+                Box::new(ByteInterleaver::new(8, 255)),
+                Box::new(Hamming::<7, 4>::new()),
+            )),
+        )),
+    ]
+}
+
+// the same code under an authenticated and an unauthenticated cipher. a
+// residual error costs a whole frame under the first, one bit under the other.
+fn encrypted_coders() -> Vec<Box<dyn Coder>> {
+    [Cipher::AesGcm, Cipher::AesCtr]
+        .into_iter()
+        .map(|cipher| {
+            Box::new(Encrypted::new(
+                cipher,
+                CIPHER_FRAME_BYTES,
+                Box::new(Hamming::<7, 4>::new()),
+            )) as Box<dyn Coder>
+        })
+        .collect()
+}
+
+// compression, encryption and a concatenated code as separate stages, put
+// together at runtime.
+fn built_pipelines() -> Vec<Pipeline> {
+    vec![PipelineBuilder::new()
+        .stage(Box::new(Compressed::new(
+            COMPRESSION_BLOCK_BYTES,
+            Box::new(Uncoded),
+        )))
+        .stage(Box::new(Encrypted::new(
+            Cipher::AesGcm,
+            CIPHER_FRAME_BYTES,
+            Box::new(Uncoded),
+        )))
+        .stage(Box::new(ReedSolomon::new(8, 32)))
+        .stage(Box::new(Hamming::<7, 4>::new()))
+        .interleaver(Box::new(BlockInterleaver))
+        .build()]
+}
+
+// 8b/10b line coding alone and outside a code. a channel error in a code
+// group corrupts the whole byte, so the code sees bursts.
+fn line_coders() -> Vec<Box<dyn Coder>> {
+    vec![
+        Box::new(EightBTenB::new(Box::new(Uncoded))),
+        Box::new(EightBTenB::new(Box::new(Hamming::<7, 4>::new()))),
+    ]
+}
+
+// coders on channels inserting and deleting bits, only sync words let the
+// receiver regain alignment.
+fn sync_coders() -> Vec<Box<dyn Coder>> {
+    vec![
+        Box::new(Hamming::<7, 4>::new()),
+        Box::new(FrameSync::new(48, Box::new(Fountain::new(32, 256, 0.25)))),
+    ]
+}
+
+// interleavers of growing depth in front of the same code, for channels
+// with fades of different lengths.
+fn depth_runs() -> Vec<(Box<dyn Coder>, Box<dyn Interleaver>)> {
+    let interleavers: Vec<Box<dyn Interleaver>> = vec![
+        Box::new(NoInterleaver),
+        Box::new(BlockInterleaver),
+        Box::new(ConvolutionalInterleaver::new(8, 16)),
+        Box::new(ConvolutionalInterleaver::new(16, 32)),
+    ];
+    interleavers
+        .into_iter()
+        .map(|interleaver| {
+            (
+                Box::new(Hamming::<7, 4>::new()) as Box<dyn Coder>,
+                interleaver,
+            )
+        })
+        .collect()
+}
+
+// stages retransmitting frames on request of their receiver.
+fn arq_stages() -> Vec<Box<dyn Duplex>> {
+    vec![
+        Box::new(StopAndWait::new(
+            ARQ_FRAME_BYTES,
+            ARQ_ATTEMPTS,
+            Box::new(Uncoded),
+        )),
+        Box::new(StopAndWait::new(
+            ARQ_FRAME_BYTES,
+            ARQ_ATTEMPTS,
+            Box::new(Hamming::<7, 4>::new()),
+        )),
+    ]
+}
+
+// incremental redundancy: the info bits of every Hamming(7,4) codeword
+// first, its parity bits on request.
+fn hybrid_stages() -> Vec<Box<dyn Duplex>> {
+    vec![Box::new(HybridArq::new(
+        ARQ_FRAME_BYTES,
+        PuncturePattern::new(8, vec![vec![1, 2, 3, 5], vec![4, 6, 7]]),
+        ARQ_ATTEMPTS,
+        Box::new(Hamming::<7, 4>::new()),
+    ))]
+}
+
+// no coding, Hamming(7,4) and Hamming(7,4) on a threefold repetition, chosen
+// by the error rate the receiver reports.
+fn adaptive_stages() -> Vec<Box<dyn Duplex>> {
+    vec![Box::new(AdaptiveRate::new(
+        ARQ_FRAME_BYTES,
+        vec![
+            Box::new(Uncoded),
+            Box::new(Hamming::<7, 4>::new()),
+            Box::new(Concatenated::new(
+                Box::new(Hamming::<7, 4>::new()),
+                Box::new(NoInterleaver),
+                Box::new(Repetition::new(3)),
+            )),
+        ],
+        16,
+        0.001,
+        0.01,
+    ))]
+}
+
+// go-back-n and selective repeat with the same window, compared on the same
+// channel trace.
+fn window_stages() -> Vec<(Box<dyn Duplex>, Box<dyn Duplex>)> {
+    [4, 16]
+        .into_iter()
+        .map(|window| {
+            (
+                Box::new(GoBackN::new(
+                    ARQ_FRAME_BYTES,
+                    window,
+                    ARQ_ATTEMPTS,
+                    Box::new(Hamming::<7, 4>::new()),
+                )) as Box<dyn Duplex>,
+                Box::new(SelectiveRepeat::new(
+                    ARQ_FRAME_BYTES,
+                    window,
+                    ARQ_ATTEMPTS,
+                    Box::new(Hamming::<7, 4>::new()),
+                )) as Box<dyn Duplex>,
+            )
+        })
+        .collect()
+}
+
+// acknowledgements travel back over a channel with rare bursts.
+fn feedback(options: &Options) -> Feedback {
+    Feedback::new(options.prepare(Channel::gilbert_elliott(0.001, 0.1, 0.0001, 0.5)))
+}
+
+// runs the coder over the channel prepared by the options, one report row.
+async fn evaluate(
+    options: &Options,
+    coder: &mut dyn Coder,
+    interleaver: &dyn Interleaver,
+    channel: Channel,
+    results: &mut Vec<Analytics>,
+) -> Result<()> {
+    let mut channel = options.prepare(channel);
+    let run_metrics = pipeline::run(coder, interleaver, &mut channel).await?;
+    options.save_trace(results.len(), &channel)?;
+    results.push(analytics::analyze(&channel, run_metrics).await?);
+    Ok(())
+}
+
+// runs the built pipeline over the channel prepared by the options, one report row.
+async fn evaluate_pipeline(
+    options: &Options,
+    pipeline: &mut Pipeline,
+    channel: Channel,
+    results: &mut Vec<Analytics>,
+) -> Result<()> {
+    let mut channel = options.prepare(channel);
+    let run_metrics = pipeline.run(&mut channel).await?;
+    options.save_trace(results.len(), &channel)?;
+    results.push(analytics::analyze(&channel, run_metrics).await?);
+    Ok(())
+}
+
+// runs every sandboxed coder over the channels, one report row each after
+// the uncoded rows. a coder failing a run only loses that row.
+#[cfg(feature = "wasm")]
+async fn evaluate_sandboxed(options: &Options, results: &mut Vec<Analytics>) -> Result<()> {
+    for channel in options.channels()? {
+        options.baseline(channel, results).await?;
+    }
+    for path in &options.wasm {
+        let mut coder = WasmCoder::load(path, options.sandbox_limits())?;
+        for mut channel in options.channels()? {
+            let run = results.len();
+            match pipeline::run(&mut coder, &NoInterleaver, &mut channel).await {
+                Ok(run_metrics) => {
+                    options.save_trace(run, &channel)?;
+                    results.push(analytics::analyze(&channel, run_metrics).await?);
+                }
+                Err(error) => eprintln!(
+                    "{} failed on {}: {}",
+                    coder.name(),
+                    channel.channel_information().model(),
+                    error
+                ),
+            }
+        }
+    }
+    Ok(())
+}
+
+// runs the stage over the channel and a fresh feedback channel, one report row.
+async fn evaluate_duplex(
+    options: &Options,
+    stage: &mut dyn Duplex,
+    channel: Channel,
+    results: &mut Vec<Analytics>,
+) -> Result<()> {
+    let mut channel = options.prepare(channel);
+    let mut feedback = feedback(options);
+    let run_metrics = pipeline::run_duplex(stage, &mut channel, &mut feedback).await?;
+    options.save_trace(results.len(), &channel)?;
+    results.push(analytics::analyze(&channel, run_metrics).await?);
+    Ok(())
+}
 
 #[async_std::main]
 async fn main() -> Result<()> {
     color_eyre::install()?;
+    build_info::set(build_info!());
+    let mut options = Options::parse()?;
     let mut results = vec![];
-    for mut channel in channels() {
-        let run_metrics = pipeline_run(&mut channel).await?;
-        results.push(analytics::analyze(&channel, run_metrics).await?);
+    for trial in 0..options.trials {
+        options.start_trial(trial);
+        for payload in options.payloads()? {
+            options.use_payload(&payload)?;
+            evaluate_all(&options, &mut results).await?;
+        }
+    }
+    options.report(&results)?;
+    Ok(())
+}
+
+// every run the options ask for with the current payload.
+async fn evaluate_all(options: &Options, results: &mut Vec<Analytics>) -> Result<()> {
+    // the selected coders, or the configured ones, or else every plugin
+    // loaded, each over the same channels.
+    let mut registry = registry();
+    let mut selected = if options.coders.is_empty() {
+        options.config.coders.clone()
+    } else {
+        options.coders.clone()
+    };
+    for path in &options.plugins {
+        let plugin = Plugin::load(path)?;
+        if options.coders.is_empty() && options.config.coders.is_empty() {
+            selected.push(plugin.name());
+        }
+        registry = registry.register(&plugin.name(), move || Box::new(plugin.clone()));
+    }
+    // the selected coders, or else those sending short segments.
+    if options.sweep {
+        if !options.no_baseline {
+            results.extend(
+                Sweep::default()
+                    .run(&mut Uncoded, &NoInterleaver, options.seed())
+                    .await?,
+            );
+        }
+        let coders = match selected.is_empty() {
+            true => segment_coders(),
+            false => selected
+                .iter()
+                .map(|name| registry.create(name))
+                .collect::<Result<_>>()?,
+        };
+        for mut coder in coders {
+            results.extend(
+                Sweep::default()
+                    .run(coder.as_mut(), &BlockInterleaver, options.seed())
+                    .await?,
+            );
+        }
+        return Ok(());
+    }
+    #[cfg(feature = "wasm")]
+    if !options.wasm.is_empty() {
+        evaluate_sandboxed(options, results).await?;
+        return Ok(());
+    }
+    if !options.bench.is_empty() {
+        let bench = Bench {
+            sizes: options.bench.clone(),
+            warmup: options.bench_warmup,
+            iterations: options.bench_iterations,
+        };
+        let names = match selected.is_empty() {
+            true => registry.names().into_iter().map(str::to_string).collect(),
+            false => selected,
+        };
+        for name in &names {
+            let mut coder = registry.create(name)?;
+            let interleaver = options.config.interleaver.build();
+            results.extend(
+                bench
+                    .run(
+                        coder.as_mut(),
+                        interleaver.as_ref(),
+                        || options.channels(),
+                        options.seed(),
+                    )
+                    .await?,
+            );
+        }
+        return Ok(());
+    }
+    // the uncoded trials first, as with the sweep.
+    if let Some(target_errors) = options.target_errors {
+        let monte_carlo = MonteCarlo {
+            target_errors,
+            max_trials: options.max_trials,
+        };
+        if !options.no_baseline {
+            results.extend(
+                monte_carlo
+                    .run(
+                        &mut Uncoded,
+                        &NoInterleaver,
+                        || options.channels(),
+                        options.seed(),
+                    )
+                    .await?,
+            );
+        }
+        let names = match selected.is_empty() {
+            true => registry.names().into_iter().map(str::to_string).collect(),
+            false => selected,
+        };
+        for name in &names {
+            let mut coder = registry.create(name)?;
+            let interleaver = options.config.interleaver.build();
+            results.extend(
+                monte_carlo
+                    .run(
+                        coder.as_mut(),
+                        interleaver.as_ref(),
+                        || options.channels(),
+                        options.seed(),
+                    )
+                    .await?,
+            );
+        }
+        return Ok(());
+    }
+    // the rows of every channel next to each other, coders in the order
+    // selected, each run with a coder and a copy of the channel of its own,
+    // so every coder sees the same errors.
+    if !selected.is_empty() {
+        let mut runs = vec![];
+        let mut channels = vec![];
+        for index in 0..options.channels()?.len() {
+            if !options.no_baseline {
+                runs.push(None);
+                channels.push(options.channels()?.swap_remove(index));
+            }
+            for name in &selected {
+                runs.push(Some(name));
+                channels.push(options.channels()?.swap_remove(index));
+            }
+        }
+        // unknown names fail before anything runs.
+        for name in &selected {
+            registry.create(name)?;
+        }
+        options
+            .run_parallel(channels, results, |run, channel| match runs[run] {
+                Some(name) => {
+                    let coder = registry.create(name);
+                    let interleaver = options.config.interleaver.build();
+                    Box::pin(async move {
+                        pipeline::run(coder?.as_mut(), interleaver.as_ref(), channel).await
+                    })
+                }
+                None => pipeline::run_uncoded(channel),
+            })
+            .await?;
+        return Ok(());
+    }
+    // a replayed trace only makes sense for channels flipping bits.
+    if let Some(trace) = &options.replay {
+        let channel = Channel::import(trace, options.trace_format)?;
+        options.baseline(channel, results).await?;
+        for (mut coder, interleaver) in runs() {
+            let channel = Channel::import(trace, options.trace_format)?;
+            evaluate(
+                options,
+                coder.as_mut(),
+                interleaver.as_ref(),
+                channel,
+                results,
+            )
+            .await?;
+        }
+        return Ok(());
+    }
+    // the uncoded rows of every set of channels come first.
+    for channel in options.config.channels() {
+        options.baseline(channel, results).await?;
+    }
+    for (mut coder, interleaver) in runs() {
+        for channel in options.config.channels() {
+            evaluate(
+                options,
+                coder.as_mut(),
+                interleaver.as_ref(),
+                channel,
+                results,
+            )
+            .await?;
+        }
+    }
+    for channel in fading_channels() {
+        options.baseline(channel, results).await?;
+    }
+    for (mut coder, interleaver) in depth_runs() {
+        for channel in fading_channels() {
+            evaluate(
+                options,
+                coder.as_mut(),
+                interleaver.as_ref(),
+                channel,
+                results,
+            )
+            .await?;
+        }
+    }
+    for (mut coder, interleaver) in soft_runs() {
+        for mode in [Channel::with_soft_output, Channel::with_erasures] {
+            for channel in options.config.channels() {
+                let channel = mode(channel);
+                evaluate(
+                    options,
+                    coder.as_mut(),
+                    interleaver.as_ref(),
+                    channel,
+                    results,
+                )
+                .await?;
+            }
+        }
+    }
+    for mut coder in punctured_coders() {
+        for channel in options.config.channels() {
+            let channel = channel.with_soft_output();
+            evaluate(options, coder.as_mut(), &BlockInterleaver, channel, results).await?;
+        }
+    }
+    // hard and soft decisions over the Eb/N0 range.
+    for channel in awgn_channels() {
+        options.baseline(channel, results).await?;
+    }
+    for (mut coder, interleaver) in soft_runs() {
+        for soft in [false, true] {
+            for channel in awgn_channels() {
+                let channel = if soft {
+                    channel.with_soft_output()
+                } else {
+                    channel
+                };
+                evaluate(
+                    options,
+                    coder.as_mut(),
+                    interleaver.as_ref(),
+                    channel,
+                    results,
+                )
+                .await?;
+            }
+        }
+    }
+    for loss in PACKET_LOSSES {
+        let channel = Channel::packet_loss(PACKET_BYTES, loss);
+        options.baseline(channel, results).await?;
+    }
+    for mut coder in packet_coders() {
+        for loss in PACKET_LOSSES {
+            let channel = Channel::packet_loss(PACKET_BYTES, loss);
+            evaluate(options, coder.as_mut(), &NoInterleaver, channel, results).await?;
+        }
+    }
+    for mut coder in compressed_coders() {
+        for channel in options.config.channels() {
+            evaluate(options, coder.as_mut(), &BlockInterleaver, channel, results).await?;
+        }
+    }
+    for mut coder in encrypted_coders() {
+        for channel in options.config.channels() {
+            evaluate(options, coder.as_mut(), &BlockInterleaver, channel, results).await?;
+        }
+    }
+    for mut pipeline in built_pipelines() {
+        for channel in options.config.channels() {
+            evaluate_pipeline(options, &mut pipeline, channel, results).await?;
+        }
+    }
+    for mut coder in line_coders() {
+        for channel in options.config.channels() {
+            evaluate(options, coder.as_mut(), &NoInterleaver, channel, results).await?;
+        }
+    }
+    for rate in SYNC_ERROR_RATES {
+        let channel = Channel::insertion_deletion(rate, rate);
+        options.baseline(channel, results).await?;
+    }
+    for mut coder in sync_coders() {
+        for rate in SYNC_ERROR_RATES {
+            let channel = Channel::insertion_deletion(rate, rate);
+            evaluate(options, coder.as_mut(), &NoInterleaver, channel, results).await?;
+        }
+    }
+    for channel in arq_channels() {
+        options.baseline(channel, results).await?;
+    }
+    for mut stage in arq_stages() {
+        for channel in arq_channels() {
+            evaluate_duplex(options, stage.as_mut(), channel, results).await?;
+        }
+    }
+    for mut stage in hybrid_stages() {
+        for channel in arq_channels() {
+            evaluate_duplex(options, stage.as_mut(), channel, results).await?;
+        }
+    }
+    for mut stage in adaptive_stages() {
+        for channel in arq_channels().into_iter().chain(fading_channels()) {
+            evaluate_duplex(options, stage.as_mut(), channel, results).await?;
+        }
+    }
+    for (mut go_back, mut selective) in window_stages() {
+        for (channel, same) in arq_channels().into_iter().zip(arq_channels()) {
+            evaluate_duplex(options, go_back.as_mut(), channel, results).await?;
+            evaluate_duplex(options, selective.as_mut(), same, results).await?;
+            let (selective, rest) = results.split_last_mut().unwrap();
+            selective.compare_bandwidth(rest.last().unwrap());
+        }
     }
-    analytics::report(&results);
     Ok(())
 }
@@ -0,0 +1,191 @@
+use color_eyre::eyre::Result;
+use mmcp::{coder::Coder, to_bits, to_byte};
+
+// Bhattacharyya parameter of the channel the frozen set is designed for.
+const DESIGN_PARAMETER: f64 = 0.5;
+
+// polar code of length n carrying k info bits on the most reliable synthetic
+// channels, decoded by successive cancellation. bits are packed LSB first.
+pub(super) struct Polar {
+    n: usize,
+    k: usize,
+    frozen: Vec<bool>,
+}
+
+impl Polar {
+    pub(super) fn new(n: usize, k: usize) -> Self {
+        assert!(
+            n.is_power_of_two() && n >= 2,
+            "block length must be a power of two"
+        );
+        assert!(k > 0 && k <= n, "info bits must fit into the block");
+        let reliability = bhattacharyya(n, DESIGN_PARAMETER);
+        let mut order = (0..n).collect::<Vec<_>>();
+        order.sort_by(|&a, &b| reliability[a].total_cmp(&reliability[b]));
+        let mut frozen = vec![true; n];
+        order.iter().take(k).for_each(|&i| frozen[i] = false);
+        Self { n, k, frozen }
+    }
+
+    fn encode_block(&self, info: &[u8]) -> Vec<u8> {
+        let mut info = info.iter();
+        let mut x = self
+            .frozen
+            .iter()
+            .map(|&frozen| {
+                if frozen {
+                    0
+                } else {
+                    *info.next().unwrap_or(&0)
+                }
+            })
+            .collect::<Vec<_>>();
+        // x = u * F^(kron m) with F = [1 0; 1 1]
+        let mut half = 1;
+        while half < self.n {
+            for block in x.chunks_mut(2 * half) {
+                let (upper, lower) = block.split_at_mut(half);
+                upper
+                    .iter_mut()
+                    .zip(lower.iter())
+                    .for_each(|(u, l)| *u ^= l);
+            }
+            half *= 2;
+        }
+        x
+    }
+}
+
+// reliability of the synthetic channels, lower is better.
+fn bhattacharyya(n: usize, z: f64) -> Vec<f64> {
+    if n == 1 {
+        return vec![z];
+    }
+    let mut worse = bhattacharyya(n / 2, 2.0 * z - z * z);
+    worse.extend(bhattacharyya(n / 2, z * z));
+    worse
+}
+
+// successive cancellation decoding, returns (u, x) estimates.
+fn successive_cancellation(llr: &[f32], frozen: &[bool]) -> (Vec<u8>, Vec<u8>) {
+    if llr.len() == 1 {
+        let bit = if frozen[0] { 0 } else { (llr[0] < 0.0) as u8 };
+        return (vec![bit], vec![bit]);
+    }
+    let half = llr.len() / 2;
+    let (upper, lower) = llr.split_at(half);
+    let (frozen_upper, frozen_lower) = frozen.split_at(half);
+
+    // f: min-sum check node combination.
+    let llr_upper = upper
+        .iter()
+        .zip(lower)
+        .map(|(a, b)| a.signum() * b.signum() * a.abs().min(b.abs()))
+        .collect::<Vec<_>>();
+    let (mut u, x_upper) = successive_cancellation(&llr_upper, frozen_upper);
+
+    // g: variable node combination given the upper decisions.
+    let llr_lower = upper
+        .iter()
+        .zip(lower)
+        .zip(&x_upper)
+        .map(|((a, b), &x)| b + if x == 0 { *a } else { -a })
+        .collect::<Vec<_>>();
+    let (u_lower, x_lower) = successive_cancellation(&llr_lower, frozen_lower);
+
+    u.extend(u_lower);
+    let mut x = x_upper
+        .iter()
+        .zip(&x_lower)
+        .map(|(a, b)| a ^ b)
+        .collect::<Vec<_>>();
+    x.extend(x_lower);
+    (u, x)
+}
+
+impl Coder for Polar {
+    fn name(&self) -> String {
+        format!("Polar({},{})", self.n, self.k)
+    }
+
+    fn encode(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+        let bits = data.iter().flat_map(|&b| to_bits(b)).collect::<Vec<_>>();
+        let encoded = bits
+            .chunks(self.k)
+            .flat_map(|info| self.encode_block(info))
+            .collect::<Vec<_>>();
+        Ok(encoded.chunks(8).map(to_byte).collect())
+    }
+
+    fn decode(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+        let llrs = data
+            .iter()
+            .flat_map(|&b| to_bits(b))
+            .map(|bit| if bit == 0 { 1.0 } else { -1.0 })
+            .collect::<Vec<f32>>();
+        let mut bits = vec![];
+        for block in llrs.chunks_exact(self.n) {
+            let (u, _) = successive_cancellation(block, &self.frozen);
+            bits.extend(
+                u.iter()
+                    .zip(&self.frozen)
+                    .filter(|(_, &frozen)| !frozen)
+                    .map(|(&bit, _)| bit),
+            );
+        }
+        Ok(bits.chunks_exact(8).map(to_byte).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mmcp::rng::SplitMix64;
+
+    fn payload(len: usize) -> Vec<u8> {
+        let mut rng = SplitMix64::new(len as u64);
+        (0..len).map(|_| rng.next_u64() as u8).collect()
+    }
+
+    #[test]
+    fn round_trips_for_every_rate() {
+        for (n, k) in [(8, 4), (64, 32), (256, 128), (1024, 512), (256, 256)] {
+            let mut coder = Polar::new(n, k);
+            let data = payload(4 * k / 8);
+            let encoded = coder.encode(&data).unwrap();
+            assert_eq!(encoded.len(), 4 * n / 8);
+            assert_eq!(coder.decode(&encoded).unwrap(), data, "{}", coder.name());
+        }
+    }
+
+    #[test]
+    fn frozen_set_holds_the_least_reliable_channels() {
+        let coder = Polar::new(8, 4);
+        // the Arikan construction for n = 8 at z = 0.5.
+        assert_eq!(
+            coder.frozen,
+            [true, true, true, false, true, false, false, false]
+        );
+    }
+
+    #[test]
+    fn corrects_a_few_errors_but_not_many() {
+        let mut coder = Polar::new(256, 128);
+        let data = payload(64);
+        let encoded = coder.encode(&data).unwrap();
+        let mut rng = SplitMix64::new(7);
+        // a single error per block.
+        let mut received = encoded.clone();
+        for block in received.chunks_mut(32) {
+            block[rng.below(32)] ^= 1 << rng.below(8);
+        }
+        assert_eq!(coder.decode(&received).unwrap(), data);
+
+        // every fourth bit on average.
+        let mut received = encoded.clone();
+        for byte in received.iter_mut() {
+            *byte ^= (rng.next_u64() & rng.next_u64()) as u8;
+        }
+        assert_ne!(coder.decode(&received).unwrap(), data);
+    }
+}
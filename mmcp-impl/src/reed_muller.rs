@@ -0,0 +1,151 @@
+use color_eyre::eyre::Result;
+use mmcp::{
+    coder::{Coder, SegmentStats},
+    to_bits, to_byte,
+};
+
+// first order Reed-Muller code RM(1,m) of length 2^m carrying m + 1 info bits.
+// bit j of a codeword is a0 + a1 x1(j) + ... + am xm(j), where xi(j) is bit
+// i - 1 of j. codewords are packed LSB first.
+pub(super) struct ReedMuller {
+    m: usize,
+    stats: SegmentStats,
+}
+
+impl ReedMuller {
+    pub(super) fn new(m: usize) -> Self {
+        assert!((1..=10).contains(&m), "m must be between 1 and 10");
+        Self {
+            m,
+            stats: SegmentStats::default(),
+        }
+    }
+
+    fn n(&self) -> usize {
+        1 << self.m
+    }
+
+    fn k(&self) -> usize {
+        self.m + 1
+    }
+
+    // info is (a0, a1, ..., am).
+    fn encode_block(&self, info: &[u8]) -> Vec<u8> {
+        (0..self.n())
+            .map(|j| (0..self.m).fold(info[0], |bit, i| bit ^ (info[i + 1] & (j >> i) as u8 & 1)))
+            .collect()
+    }
+
+    // Reed's majority logic: every pair of positions differing only in
+    // coordinate i votes for ai, a0 is the majority of what is left.
+    fn decode_block(&mut self, codeword: &[u8]) -> Vec<u8> {
+        let half = self.n() / 2;
+        let mut info = vec![0u8; self.k()];
+        for i in 0..self.m {
+            let votes = (0..self.n())
+                .filter(|j| j & (1 << i) == 0)
+                .filter(|&j| codeword[j] ^ codeword[j | 1 << i] == 1)
+                .count();
+            info[i + 1] = (2 * votes > half) as u8;
+        }
+        let mut without_a0 = info.clone();
+        without_a0[0] = 0;
+        let ones = self
+            .encode_block(&without_a0)
+            .iter()
+            .zip(codeword)
+            .filter(|(a, b)| a != b)
+            .count();
+        info[0] = (2 * ones > self.n()) as u8;
+
+        if self.encode_block(&info) == codeword {
+            self.stats.passed += 1;
+        } else {
+            self.stats.corrected += 1;
+        }
+        info
+    }
+}
+
+impl Coder for ReedMuller {
+    fn name(&self) -> String {
+        format!("RM(1,{})", self.m)
+    }
+
+    fn encode(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+        let bits = data.iter().flat_map(|&b| to_bits(b)).collect::<Vec<_>>();
+        let mut encoded = vec![];
+        for info in bits.chunks(self.k()) {
+            let mut info = info.to_vec();
+            info.resize(self.k(), 0);
+            encoded.extend(self.encode_block(&info));
+        }
+        Ok(encoded.chunks(8).map(to_byte).collect())
+    }
+
+    fn decode(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+        self.stats = SegmentStats::default();
+        let bits = data.iter().flat_map(|&b| to_bits(b)).collect::<Vec<_>>();
+        let mut decoded = vec![];
+        for codeword in bits.chunks_exact(self.n()) {
+            decoded.extend(self.decode_block(codeword));
+        }
+        Ok(decoded.chunks_exact(8).map(to_byte).collect())
+    }
+
+    fn segment_stats(&self) -> Option<SegmentStats> {
+        Some(self.stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mmcp::rng::SplitMix64;
+
+    // flips `errors` distinct bits of every codeword.
+    fn corrupt(coder: &ReedMuller, encoded: &mut [u8], errors: usize, rng: &mut SplitMix64) {
+        for codeword in encoded.chunks_mut(coder.n() / 8) {
+            let mut positions = vec![];
+            while positions.len() < errors {
+                let position = rng.below(coder.n());
+                if !positions.contains(&position) {
+                    positions.push(position);
+                }
+            }
+            for bit in positions {
+                codeword[bit / 8] ^= 1 << (bit % 8);
+            }
+        }
+    }
+
+    #[test]
+    fn corrects_fewer_errors_than_a_quarter_of_the_length() {
+        let mut rng = SplitMix64::new(8);
+        for m in 3..=6 {
+            let mut coder = ReedMuller::new(m);
+            // whole codewords for every m.
+            let data = (0..(m + 1) * 8)
+                .map(|_| rng.next_u64() as u8)
+                .collect::<Vec<_>>();
+            let mut encoded = coder.encode(&data).unwrap();
+            assert_eq!(coder.decode(&encoded).unwrap(), data, "{}", coder.name());
+            assert_eq!(coder.segment_stats().unwrap().corrected, 0);
+
+            let errors = (1 << (m - 2)) - 1;
+            corrupt(&coder, &mut encoded, errors, &mut rng);
+            assert_eq!(coder.decode(&encoded).unwrap(), data, "{}", coder.name());
+            assert_eq!(coder.segment_stats().unwrap().passed, 0);
+        }
+    }
+
+    #[test]
+    fn fails_at_half_the_minimum_distance() {
+        let mut rng = SplitMix64::new(9);
+        let mut coder = ReedMuller::new(4);
+        let data = (0..40).map(|_| rng.next_u64() as u8).collect::<Vec<_>>();
+        let mut encoded = coder.encode(&data).unwrap();
+        corrupt(&coder, &mut encoded, 4, &mut rng);
+        assert_ne!(coder.decode(&encoded).unwrap(), data);
+    }
+}
@@ -0,0 +1,241 @@
+use color_eyre::eyre::Result;
+use mmcp::coder::{Coder, SegmentStats};
+
+use crate::{
+    bits::{BitReader, BitWriter},
+    galois::Field,
+};
+
+// systematic RS(n,k) over GF(2^m) with n = 2^m - 1 and k = n - parity.
+// symbols travel in whole bytes (two bytes for m > 8), the last codeword
+// of the stream is shortened to the remaining data symbols.
+pub(super) struct ReedSolomon {
+    field: Field,
+    parity: usize,
+    generator: Vec<u16>,
+    stats: SegmentStats,
+}
+
+impl ReedSolomon {
+    pub(super) fn new(symbol_bits: u32, parity: usize) -> Self {
+        let field = Field::new(symbol_bits);
+        assert!(
+            parity > 0 && parity < field.order(),
+            "parity length must be between 1 and 2^m - 2 symbols"
+        );
+        // g(x) = (x - a^0)(x - a^1)...(x - a^(parity - 1))
+        let generator = (0..parity).fold(vec![1u16], |g, i| {
+            field.poly_mul(&g, &[field.alpha_pow(i), 1])
+        });
+        Self {
+            field,
+            parity,
+            generator,
+            stats: SegmentStats::default(),
+        }
+    }
+
+    fn n(&self) -> usize {
+        self.field.order()
+    }
+
+    fn k(&self) -> usize {
+        self.n() - self.parity
+    }
+
+    fn symbol_bytes(&self) -> usize {
+        (self.field.bits() as usize).div_ceil(8)
+    }
+
+    // message symbols followed by the remainder of message * x^parity / g(x).
+    // symbols are ordered highest degree first.
+    fn encode_block(&self, message: &[u16]) -> Vec<u16> {
+        let mut remainder = vec![0u16; self.parity];
+        for &symbol in message {
+            let feedback = symbol ^ remainder[0];
+            remainder.rotate_left(1);
+            remainder[self.parity - 1] = 0;
+            if feedback != 0 {
+                for (i, r) in remainder.iter_mut().enumerate() {
+                    *r ^= self
+                        .field
+                        .mul(feedback, self.generator[self.parity - 1 - i]);
+                }
+            }
+        }
+        let mut codeword = message.to_vec();
+        codeword.extend(remainder);
+        codeword
+    }
+
+    // correct up to parity / 2 symbol errors in place.
+    fn decode_block(&mut self, codeword: &mut [u16]) {
+        let len = codeword.len();
+        let syndromes = (0..self.parity)
+            .map(|i| {
+                let x = self.field.alpha_pow(i);
+                codeword
+                    .iter()
+                    .fold(0, |acc, &symbol| self.field.mul(acc, x) ^ symbol)
+            })
+            .collect::<Vec<_>>();
+        if syndromes.iter().all(|&s| s == 0) {
+            self.stats.passed += 1;
+            return;
+        }
+
+        let locator = self.field.berlekamp_massey(&syndromes);
+        let positions = self.field.error_positions(&locator, len);
+        if positions.is_empty() || positions.len() != locator.len() - 1 {
+            self.stats.uncorrectable += 1;
+            return;
+        }
+
+        // Forney: e = X * omega(X^-1) / locator'(X^-1)
+        let mut evaluator = self.field.poly_mul(&syndromes, &locator);
+        evaluator.truncate(self.parity);
+        let derivative = locator
+            .iter()
+            .enumerate()
+            .skip(1)
+            .map(|(i, &c)| if i % 2 == 1 { c } else { 0 })
+            .collect::<Vec<_>>();
+        for position in positions {
+            let x = self.field.alpha_pow(position);
+            let x_inv = self.field.inv(x);
+            let numerator = self.field.mul(x, self.field.eval(&evaluator, x_inv));
+            let denominator = self.field.eval(&derivative, x_inv);
+            if denominator == 0 {
+                self.stats.uncorrectable += 1;
+                return;
+            }
+            codeword[len - 1 - position] ^= self.field.div(numerator, denominator);
+        }
+        self.stats.corrected += 1;
+    }
+}
+
+impl Coder for ReedSolomon {
+    fn name(&self) -> String {
+        format!("RS({},{})", self.n(), self.k())
+    }
+
+    fn encode(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+        let bits = self.field.bits() as usize;
+        let mut symbols = vec![];
+        let mut reader = BitReader::new(data);
+        while let Some(symbol) = reader.read(bits) {
+            symbols.push(symbol as u16);
+        }
+
+        let mut encoded = vec![];
+        for message in symbols.chunks(self.k()) {
+            for symbol in self.encode_block(message) {
+                encoded.extend_from_slice(&symbol.to_be_bytes()[2 - self.symbol_bytes()..]);
+            }
+        }
+        Ok(encoded)
+    }
+
+    fn decode(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+        self.stats = SegmentStats::default();
+        let bits = self.field.bits() as usize;
+        let mask = self.field.order() as u16;
+        let mut symbols = data
+            .chunks_exact(self.symbol_bytes())
+            .map(|bytes| bytes.iter().fold(0u16, |s, &b| s << 8 | b as u16) & mask)
+            .collect::<Vec<_>>();
+
+        let mut decoded = vec![];
+        let mut writer = BitWriter::default();
+        let n = self.n();
+        for codeword in symbols.chunks_mut(n) {
+            if codeword.len() <= self.parity {
+                break;
+            }
+            self.decode_block(codeword);
+            let data_symbols = codeword.len() - self.parity;
+            for &symbol in &codeword[..data_symbols] {
+                writer.write(symbol as u32, bits, &mut decoded);
+            }
+        }
+        Ok(decoded)
+    }
+
+    fn codeword_bits(&self) -> Option<usize> {
+        Some(self.k() * self.field.bits() as usize)
+    }
+
+    fn segment_stats(&self) -> Option<SegmentStats> {
+        Some(self.stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mmcp::rng::SplitMix64;
+
+    // corrupts `errors` distinct symbols of every codeword, each by a non
+    // zero value.
+    fn corrupt(coder: &ReedSolomon, encoded: &mut [u8], errors: usize, rng: &mut SplitMix64) {
+        let symbol_bytes = coder.symbol_bytes();
+        for codeword in encoded.chunks_mut(coder.n() * symbol_bytes) {
+            let symbols = codeword.len() / symbol_bytes;
+            let mut positions = vec![];
+            while positions.len() < errors.min(symbols) {
+                let position = rng.below(symbols);
+                if !positions.contains(&position) {
+                    positions.push(position);
+                }
+            }
+            for position in positions {
+                let error = 1 + rng.below(coder.field.order()) as u16;
+                let symbol = &mut codeword[position * symbol_bytes..][..symbol_bytes];
+                for (byte, error) in symbol
+                    .iter_mut()
+                    .zip(&error.to_be_bytes()[2 - symbol_bytes..])
+                {
+                    *byte ^= error;
+                }
+            }
+        }
+    }
+
+    fn payload(len: usize, rng: &mut SplitMix64) -> Vec<u8> {
+        (0..len).map(|_| rng.next_u64() as u8).collect()
+    }
+
+    #[test]
+    fn corrects_up_to_half_the_parity_symbols() {
+        let mut rng = SplitMix64::new(3);
+        for (mut coder, len) in [
+            (ReedSolomon::new(8, 32), 2 * 223 + 50),
+            (ReedSolomon::new(4, 4), 60),
+            (ReedSolomon::new(10, 16), 1000),
+        ] {
+            let data = payload(len, &mut rng);
+            let mut encoded = coder.encode(&data).unwrap();
+            assert_eq!(coder.decode(&encoded).unwrap(), data, "{}", coder.name());
+
+            corrupt(&coder, &mut encoded, coder.parity / 2, &mut rng);
+            assert_eq!(coder.decode(&encoded).unwrap(), data, "{}", coder.name());
+            let stats = coder.segment_stats().unwrap();
+            assert_eq!(stats.uncorrectable, 0);
+            assert!(stats.corrected > 0);
+        }
+    }
+
+    #[test]
+    fn detects_more_errors_than_it_corrects() {
+        let mut rng = SplitMix64::new(4);
+        let mut coder = ReedSolomon::new(8, 32);
+        let data = payload(3 * 223, &mut rng);
+        let mut encoded = coder.encode(&data).unwrap();
+        corrupt(&coder, &mut encoded, 17, &mut rng);
+        assert_ne!(coder.decode(&encoded).unwrap(), data);
+        let stats = coder.segment_stats().unwrap();
+        assert_eq!(stats.uncorrectable, 3);
+        assert_eq!(stats.corrected, 0);
+    }
+}
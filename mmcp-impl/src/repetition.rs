@@ -0,0 +1,63 @@
+use color_eyre::eyre::Result;
+use mmcp::coder::{Coder, SegmentStats};
+
+// sends every byte `copies` times in a row, the receiver takes the majority
+// of every bit. a byte whose copies disagree counts as corrected.
+pub(super) struct Repetition {
+    copies: usize,
+    stats: SegmentStats,
+}
+
+impl Repetition {
+    pub(super) fn new(copies: usize) -> Self {
+        assert!(
+            copies % 2 == 1,
+            "majority votes need an odd number of copies"
+        );
+        Self {
+            copies,
+            stats: SegmentStats::default(),
+        }
+    }
+}
+
+impl Coder for Repetition {
+    fn name(&self) -> String {
+        format!("Repetition({})", self.copies)
+    }
+
+    fn encode(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+        Ok(data
+            .iter()
+            .flat_map(|&byte| std::iter::repeat_n(byte, self.copies))
+            .collect())
+    }
+
+    fn decode(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+        self.stats = SegmentStats::default();
+        Ok(data
+            .chunks_exact(self.copies)
+            .map(|copies| {
+                let byte = (0..8)
+                    .filter(|bit| {
+                        copies
+                            .iter()
+                            .filter(|&&copy| (copy >> bit) & 1 == 1)
+                            .count()
+                            > self.copies / 2
+                    })
+                    .fold(0, |byte, bit| byte | 1 << bit);
+                if copies.iter().all(|&copy| copy == byte) {
+                    self.stats.passed += 1;
+                } else {
+                    self.stats.corrected += 1;
+                }
+                byte
+            })
+            .collect())
+    }
+
+    fn segment_stats(&self) -> Option<SegmentStats> {
+        Some(self.stats)
+    }
+}
@@ -0,0 +1,142 @@
+use color_eyre::eyre::Result;
+use mmcp::coder::{Coder, CodewordStats, SegmentStats};
+
+use crate::coder::Hamming;
+
+type Inner = Hamming<7, 4>;
+
+// extended Hamming(8,4): the otherwise unused lowest bit of every
+// Hamming(7,4) segment carries the overall parity of the segment,
+// so double bit errors are detected instead of miscorrected.
+#[derive(Default)]
+pub(super) struct Secded {
+    stats: SegmentStats,
+    codewords: CodewordStats,
+}
+
+impl Secded {
+    fn encode_segment(info: u8) -> u8 {
+        let segment = Inner::encode_block(info as u32) as u8;
+        segment | (segment.count_ones() % 2) as u8
+    }
+
+    fn decode_segment(&mut self, index: usize, mut segment: u8) -> u8 {
+        let error_index = Inner::error_index(segment as u32);
+        let parity_error = segment.count_ones() % 2 == 1;
+        match (error_index, parity_error) {
+            (0, false) => self.stats.passed += 1,
+            // single error, possibly in the overall parity bit itself.
+            (error_index, true) => {
+                if error_index != 0 {
+                    segment ^= Inner::mask(error_index) as u8;
+                }
+                self.stats.corrected += 1;
+                self.codewords.corrected.push(index);
+            }
+            // even number of errors, leave the segment as received.
+            (_, false) => {
+                self.stats.uncorrectable += 1;
+                self.codewords.uncorrectable.push(index);
+            }
+        }
+        Inner::info_bits(segment as u32) as u8
+    }
+}
+
+impl Coder for Secded {
+    fn name(&self) -> String {
+        "SECDED(8,4)".to_string()
+    }
+
+    fn encode(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+        Ok(data
+            .iter()
+            .flat_map(|byte| [byte >> 4, byte & 0b0000_1111])
+            .map(Self::encode_segment)
+            .collect())
+    }
+
+    fn decode(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+        self.stats = SegmentStats::default();
+        self.codewords = CodewordStats::default();
+        let nibbles = data
+            .iter()
+            .enumerate()
+            .map(|(index, &segment)| self.decode_segment(index, segment))
+            .collect::<Vec<_>>();
+        Ok(nibbles
+            .chunks_exact(2)
+            .map(|pair| pair[0] << 4 | pair[1])
+            .collect())
+    }
+
+    fn codeword_bits(&self) -> Option<usize> {
+        Some(4)
+    }
+
+    fn codeword_stats(&self) -> Option<CodewordStats> {
+        Some(self.codewords.clone())
+    }
+
+    fn segment_stats(&self) -> Option<SegmentStats> {
+        Some(self.stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn payload() -> Vec<u8> {
+        (0..=255).collect()
+    }
+
+    #[test]
+    fn single_errors_are_corrected() {
+        let data = payload();
+        let mut coder = Secded::default();
+        let encoded = coder.encode(&data).unwrap();
+        assert_eq!(coder.decode(&encoded).unwrap(), data);
+        assert_eq!(coder.segment_stats().unwrap().passed, 2 * data.len() as u64);
+
+        // every bit of a segment, the overall parity bit included.
+        let mut received = encoded.clone();
+        for (index, segment) in received.iter_mut().enumerate() {
+            *segment ^= 1 << (index % 8);
+        }
+        assert_eq!(coder.decode(&received).unwrap(), data);
+        assert_eq!(
+            coder.segment_stats().unwrap().corrected,
+            encoded.len() as u64
+        );
+        assert_eq!(
+            coder.codeword_stats().unwrap().corrected,
+            (0..encoded.len()).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn double_errors_are_detected_and_left_alone() {
+        let data = payload();
+        let mut coder = Secded::default();
+        let mut received = coder.encode(&data).unwrap();
+        for (index, segment) in received.iter_mut().enumerate() {
+            *segment ^= 0b11 << (index % 7);
+        }
+        let decoded = coder.decode(&received).unwrap();
+        let stats = coder.segment_stats().unwrap();
+        assert_eq!(stats.uncorrectable, received.len() as u64);
+        assert_eq!(stats.corrected, 0);
+        assert_eq!(
+            coder.codeword_stats().unwrap().uncorrectable,
+            (0..received.len()).collect::<Vec<_>>()
+        );
+        // the info bits are the received ones, not a third miscorrection.
+        let received_info = received
+            .chunks_exact(2)
+            .map(|pair| Inner::info_bits(pair[0] as u32) << 4 | Inner::info_bits(pair[1] as u32))
+            .map(|byte| byte as u8)
+            .collect::<Vec<_>>();
+        assert_eq!(decoded, received_info);
+    }
+}
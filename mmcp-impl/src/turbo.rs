@@ -0,0 +1,224 @@
+use color_eyre::eyre::Result;
+use mmcp::{coder::Coder, to_bits, to_byte};
+
+// block length and quadratic permutation polynomial of the internal interleaver.
+const BLOCK_BITS: usize = 1024;
+const QPP_F1: usize = 31;
+const QPP_F2: usize = 64;
+// damping of the extrinsic information exchanged by max-log-MAP decoders.
+const EXTRINSIC_SCALE: f32 = 0.7;
+const STATES: usize = 4;
+
+// rate 1/3 parallel concatenated code of two (1, 5/7) recursive systematic
+// convolutional encoders. every block is sent as systematic bits followed by
+// both parity sequences, bits packed LSB first.
+pub(super) struct Turbo {
+    iterations: usize,
+    permutation: Vec<usize>,
+}
+
+impl Turbo {
+    pub(super) fn new(iterations: usize) -> Self {
+        let permutation = (0..BLOCK_BITS)
+            .map(|i| (QPP_F1 * i + QPP_F2 * i * i) % BLOCK_BITS)
+            .collect();
+        Self {
+            iterations,
+            permutation,
+        }
+    }
+
+    fn interleave<T: Copy>(&self, values: &[T]) -> Vec<T> {
+        self.permutation.iter().map(|&i| values[i]).collect()
+    }
+
+    fn deinterleave<T: Copy + Default>(&self, values: &[T]) -> Vec<T> {
+        let mut output = vec![T::default(); values.len()];
+        for (&i, &value) in self.permutation.iter().zip(values) {
+            output[i] = value;
+        }
+        output
+    }
+
+    fn decode_block(&self, systematic: &[f32], parity1: &[f32], parity2: &[f32]) -> Vec<u8> {
+        let systematic2 = self.interleave(systematic);
+        let mut apriori = vec![0f32; BLOCK_BITS];
+        let mut posterior = vec![0f32; BLOCK_BITS];
+        for _ in 0..self.iterations {
+            let llr1 = max_log_map(systematic, parity1, &apriori);
+            let extrinsic1 = extrinsic(&llr1, systematic, &apriori);
+            let apriori2 = self.interleave(&extrinsic1);
+            let llr2 = max_log_map(&systematic2, parity2, &apriori2);
+            let extrinsic2 = extrinsic(&llr2, &systematic2, &apriori2);
+            apriori = self.deinterleave(&extrinsic2);
+            posterior = self.deinterleave(&llr2);
+        }
+        posterior.iter().map(|&llr| (llr < 0.0) as u8).collect()
+    }
+}
+
+// state is (a[k-1], a[k-2]) of the feedback register, returns (parity, next state).
+fn rsc_step(state: usize, bit: u8) -> (u8, usize) {
+    let (d1, d2) = ((state >> 1) as u8, (state & 1) as u8);
+    let feedback = bit ^ d1 ^ d2;
+    (feedback ^ d2, (feedback as usize) << 1 | d1 as usize)
+}
+
+fn rsc_encode(bits: &[u8]) -> Vec<u8> {
+    let mut state = 0;
+    bits.iter()
+        .map(|&bit| {
+            let (parity, next) = rsc_step(state, bit);
+            state = next;
+            parity
+        })
+        .collect()
+}
+
+fn extrinsic(llr: &[f32], systematic: &[f32], apriori: &[f32]) -> Vec<f32> {
+    llr.iter()
+        .zip(systematic)
+        .zip(apriori)
+        .map(|((l, s), a)| EXTRINSIC_SCALE * (l - s - a))
+        .collect()
+}
+
+// max-log BCJR over the 4 state trellis, LLRs are log P(0) / P(1).
+fn max_log_map(systematic: &[f32], parity: &[f32], apriori: &[f32]) -> Vec<f32> {
+    let len = systematic.len();
+    let sign = |bit: u8| if bit == 0 { 0.5 } else { -0.5 };
+    let branch = |k: usize, bit: u8, parity_bit: u8| {
+        sign(bit) * (systematic[k] + apriori[k]) + sign(parity_bit) * parity[k]
+    };
+
+    // the encoder starts in state 0 and is left unterminated.
+    let mut alpha = vec![[f32::NEG_INFINITY; STATES]; len + 1];
+    alpha[0][0] = 0.0;
+    for k in 0..len {
+        for state in 0..STATES {
+            for bit in 0..2 {
+                let (parity_bit, next) = rsc_step(state, bit);
+                let metric = alpha[k][state] + branch(k, bit, parity_bit);
+                alpha[k + 1][next] = alpha[k + 1][next].max(metric);
+            }
+        }
+        let max = alpha[k + 1]
+            .iter()
+            .copied()
+            .fold(f32::NEG_INFINITY, f32::max);
+        alpha[k + 1].iter_mut().for_each(|a| *a -= max);
+    }
+
+    let mut beta = [0f32; STATES];
+    let mut llr = vec![0f32; len];
+    for k in (0..len).rev() {
+        let mut best = [f32::NEG_INFINITY; 2];
+        let mut previous = [f32::NEG_INFINITY; STATES];
+        for (state, previous) in previous.iter_mut().enumerate() {
+            for bit in 0..2 {
+                let (parity_bit, next) = rsc_step(state, bit);
+                let gamma = branch(k, bit, parity_bit);
+                let path = alpha[k][state] + gamma + beta[next];
+                best[bit as usize] = best[bit as usize].max(path);
+                *previous = previous.max(gamma + beta[next]);
+            }
+        }
+        llr[k] = best[0] - best[1];
+        let max = previous.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+        beta = previous.map(|b| b - max);
+    }
+    llr
+}
+
+impl Coder for Turbo {
+    fn name(&self) -> String {
+        format!("Turbo(1/3, {} iterations)", self.iterations)
+    }
+
+    fn encode(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+        let bits = data.iter().flat_map(|&b| to_bits(b)).collect::<Vec<_>>();
+        let mut encoded = vec![];
+        for block in bits.chunks(BLOCK_BITS) {
+            let mut block = block.to_vec();
+            block.resize(BLOCK_BITS, 0);
+            let parity1 = rsc_encode(&block);
+            let parity2 = rsc_encode(&self.interleave(&block));
+            encoded.extend(block);
+            encoded.extend(parity1);
+            encoded.extend(parity2);
+        }
+        Ok(encoded.chunks(8).map(to_byte).collect())
+    }
+
+    fn decode(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+        // hard channel decisions, the magnitude does not matter for max-log-MAP.
+        let llrs = data
+            .iter()
+            .flat_map(|&b| to_bits(b))
+            .map(|bit| if bit == 0 { 1.0 } else { -1.0 })
+            .collect::<Vec<f32>>();
+        let mut bits = vec![];
+        for block in llrs.chunks_exact(3 * BLOCK_BITS) {
+            let (systematic, parity) = block.split_at(BLOCK_BITS);
+            let (parity1, parity2) = parity.split_at(BLOCK_BITS);
+            bits.extend(self.decode_block(systematic, parity1, parity2));
+        }
+        Ok(bits.chunks_exact(8).map(to_byte).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mmcp::rng::SplitMix64;
+
+    fn payload(len: usize) -> Vec<u8> {
+        let mut rng = SplitMix64::new(len as u64);
+        (0..len).map(|_| rng.next_u64() as u8).collect()
+    }
+
+    // flips every bit with the given probability.
+    fn flip(encoded: &mut [u8], probability: f64, seed: u64) {
+        let mut rng = SplitMix64::new(seed);
+        for byte in encoded.iter_mut() {
+            for bit in 0..8 {
+                if rng.next_f64() < probability {
+                    *byte ^= 1 << bit;
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn round_trips_whole_blocks() {
+        let mut coder = Turbo::new(6);
+        let data = payload(4 * BLOCK_BITS / 8);
+        let encoded = coder.encode(&data).unwrap();
+        assert_eq!(encoded.len(), 3 * data.len());
+        assert_eq!(coder.decode(&encoded).unwrap(), data);
+    }
+
+    #[test]
+    fn pads_the_last_block_with_zeros() {
+        let mut coder = Turbo::new(6);
+        let data = payload(100);
+        let encoded = coder.encode(&data).unwrap();
+        let decoded = coder.decode(&encoded).unwrap();
+        assert_eq!(decoded.len(), BLOCK_BITS / 8);
+        assert_eq!(&decoded[..100], data);
+        assert!(decoded[100..].iter().all(|&byte| byte == 0));
+    }
+
+    #[test]
+    fn corrects_sparse_errors_but_not_dense_ones() {
+        let mut coder = Turbo::new(6);
+        let data = payload(4 * BLOCK_BITS / 8);
+        let mut encoded = coder.encode(&data).unwrap();
+        flip(&mut encoded, 0.02, 1);
+        assert_eq!(coder.decode(&encoded).unwrap(), data);
+
+        let mut encoded = coder.encode(&data).unwrap();
+        flip(&mut encoded, 0.3, 2);
+        assert_ne!(coder.decode(&encoded).unwrap(), data);
+    }
+}
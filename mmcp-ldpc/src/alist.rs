@@ -0,0 +1,67 @@
+use color_eyre::eyre::{bail, eyre, Result, WrapErr};
+use std::path::Path;
+
+// sparse parity-check matrix as stored in MacKay's alist format.
+pub(super) struct ParityCheckMatrix {
+    pub(super) columns: usize,
+    // for every check (row) the variable nodes (columns) it covers.
+    pub(super) checks: Vec<Vec<usize>>,
+}
+
+impl ParityCheckMatrix {
+    pub(super) fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .wrap_err_with(|| format!("failed to read alist file {}", path.display()))?;
+        Self::parse(&text).wrap_err_with(|| format!("invalid alist file {}", path.display()))
+    }
+
+    // N M / max column and row weight / column weights / row weights /
+    // N lines of 1-based row indices / M lines of 1-based column indices.
+    pub(super) fn parse(text: &str) -> Result<Self> {
+        let mut numbers = text.split_whitespace().map(|token| {
+            token
+                .parse::<usize>()
+                .map_err(|_| eyre!("unexpected token {:?}", token))
+        });
+        let mut next = || {
+            numbers
+                .next()
+                .unwrap_or_else(|| Err(eyre!("unexpected end of file")))
+        };
+
+        let (columns, rows) = (next()?, next()?);
+        let (max_column_weight, max_row_weight) = (next()?, next()?);
+        let column_weights = (0..columns).map(|_| next()).collect::<Result<Vec<_>>>()?;
+        let row_weights = (0..rows).map(|_| next()).collect::<Result<Vec<_>>>()?;
+
+        // the column lists are redundant, skip them (zero padded to the max weight).
+        for _ in 0..columns * max_column_weight {
+            next()?;
+        }
+        let mut checks = vec![];
+        for weight in row_weights {
+            let mut check = vec![];
+            for position in 0..max_row_weight {
+                let column = next()?;
+                if position < weight {
+                    if column == 0 || column > columns {
+                        bail!("column index {} out of range", column);
+                    }
+                    check.push(column - 1);
+                }
+            }
+            checks.push(check);
+        }
+
+        let mut degrees = vec![0; columns];
+        checks
+            .iter()
+            .flatten()
+            .for_each(|&column| degrees[column] += 1);
+        if degrees != column_weights {
+            bail!("column weights do not match the row lists");
+        }
+        Ok(Self { columns, checks })
+    }
+}
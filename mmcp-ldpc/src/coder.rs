@@ -0,0 +1,307 @@
+use color_eyre::eyre::{bail, Result};
+use mmcp::{
+    coder::{Coder, SegmentStats},
+    to_bits, to_byte,
+};
+use std::path::Path;
+
+use crate::alist::ParityCheckMatrix;
+
+#[derive(Clone, Copy)]
+pub(super) enum Decoder {
+    BitFlipping,
+    MinSum,
+}
+
+// systematic LDPC code defined by a sparse parity-check matrix H.
+// codewords are packed back to back into the byte stream, LSB first.
+#[derive(Clone)]
+pub(super) struct Ldpc {
+    n: usize,
+    checks: Vec<Vec<usize>>,
+    // checks every variable node takes part in, as (check, position in check).
+    variables: Vec<Vec<(usize, usize)>>,
+    // columns carrying info bits, all other columns are pivots of H.
+    info_columns: Vec<usize>,
+    // (pivot column, info bit indices) of the reduced row echelon form of H.
+    parity_equations: Vec<(usize, Vec<usize>)>,
+    decoder: Decoder,
+    max_iterations: usize,
+    stats: SegmentStats,
+}
+
+impl Ldpc {
+    pub(super) fn from_alist(
+        path: impl AsRef<Path>,
+        decoder: Decoder,
+        max_iterations: usize,
+    ) -> Result<Self> {
+        Self::new(ParityCheckMatrix::load(path)?, decoder, max_iterations)
+    }
+
+    fn new(h: ParityCheckMatrix, decoder: Decoder, max_iterations: usize) -> Result<Self> {
+        let n = h.columns;
+        let mut variables = vec![vec![]; n];
+        for (check, columns) in h.checks.iter().enumerate() {
+            for (position, &column) in columns.iter().enumerate() {
+                variables[column].push((check, position));
+            }
+        }
+
+        // gaussian elimination over GF(2) to find an encoder.
+        let mut rows = h
+            .checks
+            .iter()
+            .map(|columns| {
+                let mut row = vec![0u8; n];
+                columns.iter().for_each(|&c| row[c] ^= 1);
+                row
+            })
+            .collect::<Vec<_>>();
+        let mut pivots = vec![];
+        for column in 0..n {
+            let rank = pivots.len();
+            let Some(pivot) = (rank..rows.len()).find(|&r| rows[r][column] == 1) else {
+                continue;
+            };
+            rows.swap(rank, pivot);
+            let pivot_row = rows[rank].clone();
+            for (r, row) in rows.iter_mut().enumerate() {
+                if r != rank && row[column] == 1 {
+                    row.iter_mut().zip(&pivot_row).for_each(|(b, p)| *b ^= p);
+                }
+            }
+            pivots.push(column);
+        }
+        let info_columns = (0..n).filter(|c| !pivots.contains(c)).collect::<Vec<_>>();
+        if info_columns.is_empty() {
+            bail!("parity-check matrix has full column rank, no room for info bits");
+        }
+        let parity_equations = pivots
+            .iter()
+            .zip(&rows)
+            .map(|(&pivot, row)| {
+                let bits = info_columns
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, &column)| row[column] == 1)
+                    .map(|(index, _)| index)
+                    .collect();
+                (pivot, bits)
+            })
+            .collect();
+
+        Ok(Self {
+            n,
+            checks: h.checks,
+            variables,
+            info_columns,
+            parity_equations,
+            decoder,
+            max_iterations,
+            stats: SegmentStats::default(),
+        })
+    }
+
+    fn k(&self) -> usize {
+        self.info_columns.len()
+    }
+
+    fn encode_block(&self, info: &[u8]) -> Vec<u8> {
+        let mut codeword = vec![0u8; self.n];
+        for (&column, &bit) in self.info_columns.iter().zip(info) {
+            codeword[column] = bit;
+        }
+        for (pivot, bits) in &self.parity_equations {
+            codeword[*pivot] = bits.iter().fold(0, |p, &i| p ^ info[i]);
+        }
+        codeword
+    }
+
+    fn syndrome_ok(&self, codeword: &[u8]) -> bool {
+        self.checks
+            .iter()
+            .all(|check| check.iter().fold(0, |p, &v| p ^ codeword[v]) == 0)
+    }
+
+    // returns whether the decoder converged to a valid codeword, adding the
+    // iterations it took to the metrics of the run.
+    fn decode_block(&self, codeword: &mut [u8]) -> bool {
+        let (converged, iterations) = match self.decoder {
+            Decoder::BitFlipping => self.bit_flipping(codeword),
+            Decoder::MinSum => self.min_sum(codeword),
+        };
+        mmcp::metrics::add("decoder iterations", iterations as f64);
+        converged
+    }
+
+    // Gallager bit flipping: flip the bits taking part in most failed checks.
+    fn bit_flipping(&self, codeword: &mut [u8]) -> (bool, usize) {
+        let mut failed = vec![false; self.checks.len()];
+        let mut votes = vec![0; self.n];
+        for iteration in 0..self.max_iterations {
+            for (failed, check) in failed.iter_mut().zip(&self.checks) {
+                *failed = check.iter().fold(0, |p, &v| p ^ codeword[v]) == 1;
+            }
+            for (votes, checks) in votes.iter_mut().zip(&self.variables) {
+                *votes = checks.iter().filter(|(c, _)| failed[*c]).count();
+            }
+            let max = votes.iter().copied().max().unwrap_or(0);
+            if max == 0 {
+                return (true, iteration);
+            }
+            for (bit, &votes) in codeword.iter_mut().zip(&votes) {
+                if votes == max {
+                    *bit ^= 1;
+                }
+            }
+        }
+        (self.syndrome_ok(codeword), self.max_iterations)
+    }
+
+    // normalized min-sum on hard channel decisions mapped to +-1 LLRs.
+    fn min_sum(&self, codeword: &mut [u8]) -> (bool, usize) {
+        const NORMALIZATION: f32 = 0.75;
+        let channel = codeword
+            .iter()
+            .map(|&bit| if bit == 0 { 1.0 } else { -1.0 })
+            .collect::<Vec<f32>>();
+        let mut totals = channel.clone();
+        let mut check_to_variable = self
+            .checks
+            .iter()
+            .map(|check| vec![0f32; check.len()])
+            .collect::<Vec<_>>();
+        let mut incoming = vec![];
+        for iteration in 1..=self.max_iterations {
+            for (check, messages) in self.checks.iter().zip(&mut check_to_variable) {
+                // variable to check messages exclude the message of the receiving check.
+                incoming.clear();
+                incoming.extend(
+                    check
+                        .iter()
+                        .zip(messages.iter())
+                        .map(|(&v, m)| totals[v] - m),
+                );
+                // every outgoing message is the product of the other signs and
+                // the smallest other magnitude, so the two smallest suffice.
+                let sign = incoming.iter().fold(1f32, |s, l| s * l.signum());
+                let (min, second) = incoming.iter().fold((f32::MAX, f32::MAX), |(a, b), l| {
+                    let l = l.abs();
+                    if l < a {
+                        (l, a)
+                    } else {
+                        (a, b.min(l))
+                    }
+                });
+                for (message, l) in messages.iter_mut().zip(&incoming) {
+                    let magnitude = if l.abs() == min { second } else { min };
+                    *message = NORMALIZATION * sign * l.signum() * magnitude;
+                }
+            }
+            for ((total, llr), checks) in totals.iter_mut().zip(&channel).zip(&self.variables) {
+                *total = llr
+                    + checks
+                        .iter()
+                        .map(|&(c, p)| check_to_variable[c][p])
+                        .sum::<f32>();
+            }
+            for (bit, total) in codeword.iter_mut().zip(&totals) {
+                *bit = (*total < 0.0) as u8;
+            }
+            if self.syndrome_ok(codeword) {
+                return (true, iteration);
+            }
+        }
+        (false, self.max_iterations)
+    }
+}
+
+impl Coder for Ldpc {
+    fn name(&self) -> String {
+        let decoder = match self.decoder {
+            Decoder::BitFlipping => "bit flipping",
+            Decoder::MinSum => "min-sum",
+        };
+        format!("LDPC({},{}) {}", self.n, self.k(), decoder)
+    }
+
+    fn encode(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+        let bits = data.iter().flat_map(|&b| to_bits(b)).collect::<Vec<_>>();
+        let mut codewords = vec![];
+        for info in bits.chunks(self.k()) {
+            let mut info = info.to_vec();
+            info.resize(self.k(), 0);
+            codewords.extend(self.encode_block(&info));
+        }
+        Ok(codewords.chunks(8).map(to_byte).collect())
+    }
+
+    fn decode(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+        self.stats = SegmentStats::default();
+        let bits = data.iter().flat_map(|&b| to_bits(b)).collect::<Vec<_>>();
+        let mut info = vec![];
+        for codeword in bits.chunks_exact(self.n) {
+            let mut codeword = codeword.to_vec();
+            if self.syndrome_ok(&codeword) {
+                self.stats.passed += 1;
+            } else if self.decode_block(&mut codeword) {
+                self.stats.corrected += 1;
+            } else {
+                self.stats.uncorrectable += 1;
+            }
+            info.extend(self.info_columns.iter().map(|&column| codeword[column]));
+        }
+        Ok(info.chunks_exact(8).map(to_byte).collect())
+    }
+
+    fn segment_stats(&self) -> Option<SegmentStats> {
+        Some(self.stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ldpc(decoder: Decoder) -> Ldpc {
+        Ldpc::from_alist(crate::PARITY_CHECK_MATRIX, decoder, crate::MAX_ITERATIONS).unwrap()
+    }
+
+    // twelve bytes, two codewords of 48 payload bits.
+    const PAYLOAD: &[u8] = b"LDPC payload";
+
+    #[test]
+    fn round_trips_without_errors() {
+        for decoder in [Decoder::BitFlipping, Decoder::MinSum] {
+            let mut ldpc = ldpc(decoder);
+            assert_eq!(ldpc.k(), 48);
+            let encoded = ldpc.encode(PAYLOAD).unwrap();
+            assert_eq!(encoded.len(), 24);
+            assert_eq!(ldpc.decode(&encoded).unwrap(), PAYLOAD);
+            let stats = ldpc.segment_stats().unwrap();
+            assert_eq!(
+                (stats.passed, stats.corrected, stats.uncorrectable),
+                (2, 0, 0)
+            );
+        }
+    }
+
+    #[test]
+    fn corrects_a_single_error_anywhere_in_a_codeword() {
+        for decoder in [Decoder::BitFlipping, Decoder::MinSum] {
+            let mut ldpc = ldpc(decoder);
+            let encoded = ldpc.encode(PAYLOAD).unwrap();
+            for bit in 0..ldpc.n {
+                let mut received = encoded.clone();
+                received[bit / 8] ^= 0x80 >> (bit % 8);
+                assert_eq!(ldpc.decode(&received).unwrap(), PAYLOAD, "bit {}", bit);
+                let stats = ldpc.segment_stats().unwrap();
+                assert_eq!(
+                    (stats.passed, stats.corrected, stats.uncorrectable),
+                    (1, 1, 0)
+                );
+            }
+        }
+    }
+}
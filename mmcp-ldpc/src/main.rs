@@ -0,0 +1,54 @@
+#![forbid(unsafe_code)]
+
+use mmcp::{interleaver::NoInterleaver, *};
+use clap::Parser;
+use color_eyre::eyre::Result;
+mod alist;
+mod coder;
+use coder::{Decoder, Ldpc};
+
+const PARITY_CHECK_MATRIX: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/resources/ldpc_96_48.alist"
+);
+const MAX_ITERATIONS: usize = 20;
+
+#[derive(Parser)]
+struct Args {
+    /// Parity check matrix in alist format.
+    #[arg(default_value = PARITY_CHECK_MATRIX)]
+    alist: String,
+    #[command(flatten)]
+    options: Options,
+}
+
+#[async_std::main]
+async fn main() -> Result<()> {
+    color_eyre::install()?;
+    build_info::set(build_info!());
+    let args = Args::parse();
+    let mut options = args.options.settle()?;
+    // reported by every run, zero for those decoding nothing.
+    metrics::register("decoder iterations");
+    let mut results = vec![];
+    for trial in 0..options.trials {
+        options.start_trial(trial);
+        for payload in options.payloads()? {
+            options.use_payload(&payload)?;
+            for decoder in [Decoder::BitFlipping, Decoder::MinSum] {
+                let coder = Ldpc::from_alist(&args.alist, decoder, MAX_ITERATIONS)?;
+                options
+                    .run_parallel(options.channels()?, &mut results, |_, channel| {
+                        // a copy per run, the runs may share no state.
+                        let mut coder = coder.clone();
+                        Box::pin(async move {
+                            pipeline::run(&mut coder, &NoInterleaver, channel).await
+                        })
+                    })
+                    .await?;
+            }
+        }
+    }
+    options.report(&results)?;
+    Ok(())
+}
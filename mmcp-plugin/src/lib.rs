@@ -0,0 +1,74 @@
+// an example coder loaded at runtime by `mmcp-impl --plugin`, a threefold
+// repetition code decided by bitwise majority. it only depends on the C
+// entry points documented in `mmcp::plugin`.
+
+use std::ffi::c_char;
+
+const COPIES: usize = 3;
+
+#[no_mangle]
+pub extern "C" fn mmcp_abi_version() -> u32 {
+    1
+}
+
+#[no_mangle]
+pub extern "C" fn mmcp_name() -> *const c_char {
+    c"Repetition(3) plugin".as_ptr()
+}
+
+/// # Safety
+/// `data` points to `len` readable bytes, `output` and `output_len` are writable.
+#[no_mangle]
+pub unsafe extern "C" fn mmcp_encode(
+    data: *const u8,
+    len: usize,
+    output: *mut *mut u8,
+    output_len: *mut usize,
+) -> i32 {
+    let data = input(data, len);
+    let encoded = data
+        .iter()
+        .flat_map(|&byte| [byte; COPIES])
+        .collect::<Vec<_>>();
+    hand_over(encoded, output, output_len);
+    0
+}
+
+/// # Safety
+/// As for [`mmcp_encode`].
+#[no_mangle]
+pub unsafe extern "C" fn mmcp_decode(
+    data: *const u8,
+    len: usize,
+    output: *mut *mut u8,
+    output_len: *mut usize,
+) -> i32 {
+    let data = input(data, len);
+    let decoded = data
+        .chunks_exact(COPIES)
+        .map(|copies| (copies[0] & copies[1]) | (copies[0] & copies[2]) | (copies[1] & copies[2]))
+        .collect::<Vec<_>>();
+    hand_over(decoded, output, output_len);
+    0
+}
+
+/// # Safety
+/// `data` and `len` come from one call of [`mmcp_encode`] or [`mmcp_decode`].
+#[no_mangle]
+pub unsafe extern "C" fn mmcp_free(data: *mut u8, len: usize) {
+    drop(Box::from_raw(std::ptr::slice_from_raw_parts_mut(data, len)));
+}
+
+unsafe fn input<'a>(data: *const u8, len: usize) -> &'a [u8] {
+    match len {
+        0 => &[],
+        _ => std::slice::from_raw_parts(data, len),
+    }
+}
+
+// leaks the buffer to the caller until it calls `mmcp_free`.
+unsafe fn hand_over(data: Vec<u8>, output: *mut *mut u8, output_len: *mut usize) {
+    let data = Box::into_raw(data.into_boxed_slice());
+    *output_len = data.len();
+    *output = data as *mut u8;
+}
@@ -1,24 +1,34 @@
-use async_std::prelude::*;
+use async_std::{prelude::*, stream};
 use color_eyre::eyre::Result;
+use mmcp::pipeline;
+
+// bytes coded at a time, all the stages hold on to.
+const BLOCK_BYTES: usize = 1024;
 
 pub(super) async fn encode(
-    mut stream: impl Stream<Item = u8> + Unpin,
+    stream: impl Stream<Item = u8> + Unpin,
 ) -> Result<impl Stream<Item = u8>> {
-    let mut data = vec![];
-    while let Some(byte) = stream.next().await {
-        data.push(byte);
-    }
-    let output = async_std::stream::from_iter(data);
+    let output = pipeline::blocks(stream, BLOCK_BYTES).flat_map(|block| {
+        let encoded = encode_block(block);
+        stream::from_iter(encoded)
+    });
     Ok(output)
 }
 
 pub(super) async fn decode(
-    mut stream: impl Stream<Item = u8> + Unpin,
+    stream: impl Stream<Item = u8> + Unpin,
 ) -> Result<impl Stream<Item = u8>> {
-    let mut data = vec![];
-    while let Some(byte) = stream.next().await {
-        data.push(byte);
-    }
-    let output = async_std::stream::from_iter(data);
+    let output = pipeline::blocks(stream, BLOCK_BYTES).flat_map(|block| {
+        let decoded = decode_block(block);
+        stream::from_iter(decoded)
+    });
     Ok(output)
 }
+
+fn encode_block(block: Vec<u8>) -> Vec<u8> {
+    block
+}
+
+fn decode_block(block: Vec<u8>) -> Vec<u8> {
+    block
+}
@@ -10,11 +10,34 @@ pipeline!(encode, decode);
 #[async_std::main]
 async fn main() -> Result<()> {
     color_eyre::install()?;
+    build_info::set(build_info!());
+    let mut options = Options::parse()?;
     let mut results = vec![];
-    for mut channel in channels() {
-        let run_metrics = pipeline_run(&mut channel).await?;
-        results.push(analytics::analyze(&channel, run_metrics).await?);
+    for trial in 0..options.trials {
+        options.start_trial(trial);
+        for payload in options.payloads()? {
+            options.use_payload(&payload)?;
+            // the uncoded row of every channel right before the student's row.
+            let baseline = !options.no_baseline;
+            let mut channels = vec![];
+            // a fresh copy of the channel, so both rows see the same errors.
+            for (channel, copy) in options.channels()?.into_iter().zip(options.channels()?) {
+                if baseline {
+                    channels.push(copy);
+                }
+                channels.push(channel);
+            }
+            options
+                .run_parallel(channels, &mut results, |index, channel| {
+                    if baseline && index % 2 == 0 {
+                        pipeline::run_uncoded(channel)
+                    } else {
+                        Box::pin(pipeline_run(channel))
+                    }
+                })
+                .await?;
+        }
     }
-    analytics::report(&results);
+    options.report(&results)?;
     Ok(())
 }
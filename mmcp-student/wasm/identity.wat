@@ -0,0 +1,28 @@
+;; the identity coder as a module for `mmcp-impl --wasm`, built with the
+;; `wasm` feature. every call gets its input at the same address and
+;; returns it unchanged as `ptr << 32 | len`.
+(module
+  (memory (export "memory") 1)
+
+  (func (export "mmcp_alloc") (param $len i32) (result i32)
+    (local $end i32)
+    (local.set $end (i32.add (i32.const 1024) (local.get $len)))
+    (block $done
+      (loop $grow
+        (br_if $done
+          (i32.le_u (local.get $end) (i32.shl (memory.size) (i32.const 16))))
+        (if (i32.eq (memory.grow (i32.const 1)) (i32.const -1))
+          (then unreachable))
+        (br $grow)))
+    (i32.const 1024))
+
+  (func $output (param $ptr i32) (param $len i32) (result i64)
+    (i64.or
+      (i64.shl (i64.extend_i32_u (local.get $ptr)) (i64.const 32))
+      (i64.extend_i32_u (local.get $len))))
+
+  (func (export "mmcp_encode") (param $ptr i32) (param $len i32) (result i64)
+    (call $output (local.get $ptr) (local.get $len)))
+
+  (func (export "mmcp_decode") (param $ptr i32) (param $len i32) (result i64)
+    (call $output (local.get $ptr) (local.get $len))))
@@ -0,0 +1,18 @@
+use std::process::Command;
+
+// the commit the library is built from, for the reports, unknown outside of
+// a git checkout.
+fn main() {
+    let commit = Command::new("git")
+        .args(["rev-parse", "--short=12", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map_or("unknown".to_string(), |commit| commit.trim().to_string());
+    println!("cargo:rustc-env=MMCP_COMMIT={}", commit);
+    // a new commit or another branch checked out.
+    println!("cargo:rerun-if-changed=../.git/HEAD");
+    println!("cargo:rerun-if-changed=../.git/refs");
+    println!("cargo:rerun-if-changed=../.git/packed-refs");
+}
@@ -0,0 +1,282 @@
+use color_eyre::eyre::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+use crate::{
+    arq::{frame, received_payload, unframe},
+    channel::Channel,
+    coder::Coder,
+    feedback::{Duplex, Feedback},
+    framing::crc32,
+};
+
+const REPORT: u8 = 0x52;
+
+/// Sends CRC-checked frames with one of several coders, weakest first, and
+/// switches between them on what the receiver reports over the feedback
+/// channel after every frame: the bit errors its decoder corrected and
+/// whether the frame came out intact. A failed frame moves to the next
+/// stronger coder at once. Otherwise the error rate of the last `window`
+/// reported frames moves to the next stronger coder above `raise` and to the
+/// next weaker one below `lower`. Failed frames are not repeated. The coder
+/// of every frame is assumed to be signalled out of band.
+pub struct AdaptiveRate {
+    frame_bytes: usize,
+    coders: Vec<Box<dyn Coder>>,
+    window: usize,
+    lower: f64,
+    raise: f64,
+    stats: RateStats,
+}
+
+impl AdaptiveRate {
+    pub fn new(
+        frame_bytes: usize,
+        coders: Vec<Box<dyn Coder>>,
+        window: usize,
+        lower: f64,
+        raise: f64,
+    ) -> Self {
+        assert!(
+            frame_bytes > 0 && frame_bytes <= u16::MAX as usize,
+            "frames carry between 1 and 65535 bytes"
+        );
+        assert!(!coders.is_empty(), "adapting needs a coder to choose");
+        assert!(
+            window > 0 && lower < raise,
+            "the rate needs a window and two thresholds"
+        );
+        Self {
+            frame_bytes,
+            coders,
+            window,
+            lower,
+            raise,
+            stats: RateStats::default(),
+        }
+    }
+
+    fn switch(&mut self, frame: u32, from: usize, to: usize) -> usize {
+        self.stats.switches.push(RateSwitch {
+            frame,
+            from: self.coders[from].name(),
+            to: self.coders[to].name(),
+        });
+        to
+    }
+}
+
+impl Duplex for AdaptiveRate {
+    fn name(&self) -> String {
+        let coders = self
+            .coders
+            .iter()
+            .map(|coder| coder.name())
+            .collect::<Vec<_>>();
+        format!("Adaptive({})/{}", coders.join(" | "), self.frame_bytes)
+    }
+
+    fn transfer(
+        &mut self,
+        data: &[u8],
+        forward: &mut Channel,
+        feedback: &mut Feedback,
+    ) -> Result<Vec<u8>> {
+        self.stats = RateStats {
+            switches: vec![],
+            frames: self.coders.iter().map(|coder| (coder.name(), 0)).collect(),
+        };
+        let mut delivered = Vec::with_capacity(data.len());
+        // corrected bits and channel bits of the last reported frames.
+        let mut reports = VecDeque::with_capacity(self.window);
+        let mut level = 0;
+        for (seq, payload) in data.chunks(self.frame_bytes).enumerate() {
+            let seq = seq as u32;
+            let coder = &mut self.coders[level];
+            let encoded = coder.encode(&frame(seq, payload))?;
+            let received = coder.decode(&forward.send(&encoded))?;
+            // the report has room for 32 bits, more than any frame fills.
+            let corrected = coder
+                .layer_stats()
+                .iter()
+                .map(|(_, stats)| stats.corrected + stats.uncorrectable)
+                .sum::<u64>()
+                .try_into()
+                .unwrap_or(u32::MAX);
+            self.stats.frames[level].1 += 1;
+            let intact = match unframe(&received) {
+                Some((number, intact)) if number == seq => {
+                    delivered.extend_from_slice(intact);
+                    true
+                }
+                _ => {
+                    delivered.extend(received_payload(&received, payload.len()));
+                    false
+                }
+            };
+            let Some((corrected, intact)) = reported(&feedback.send(&report(corrected, intact)))
+            else {
+                continue;
+            };
+            if !intact {
+                reports.clear();
+                if level + 1 < self.coders.len() {
+                    level = self.switch(seq, level, level + 1);
+                }
+                continue;
+            }
+            reports.push_back((corrected, 8 * encoded.len()));
+            if reports.len() < self.window {
+                continue;
+            }
+            let (errors, bits) = reports.iter().fold((0, 0), |(errors, bits), &(e, b)| {
+                (errors + e as usize, bits + b)
+            });
+            let rate = errors as f64 / bits as f64;
+            reports.pop_front();
+            if rate > self.raise && level + 1 < self.coders.len() {
+                level = self.switch(seq, level, level + 1);
+                reports.clear();
+            } else if rate < self.lower && level > 0 {
+                level = self.switch(seq, level, level - 1);
+                reports.clear();
+            }
+        }
+        Ok(delivered)
+    }
+
+    fn rate_stats(&self) -> Option<RateStats> {
+        Some(self.stats.clone())
+    }
+}
+
+/// Log of the coder switches of an adaptive stage.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct RateStats {
+    /// Every switch, in frame order.
+    pub switches: Vec<RateSwitch>,
+    /// Frames sent with each coder.
+    pub frames: Vec<(String, u32)>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RateSwitch {
+    /// Frame after whose report the coder changed.
+    pub frame: u32,
+    pub from: String,
+    pub to: String,
+}
+
+fn report(corrected: u32, intact: bool) -> Vec<u8> {
+    let mut report = vec![REPORT];
+    report.extend_from_slice(&corrected.to_be_bytes());
+    report.push(intact as u8);
+    report.extend_from_slice(&crc32(&report).to_be_bytes());
+    report
+}
+
+// corrected bits and outcome of an intact report.
+fn reported(message: &[u8]) -> Option<(u32, bool)> {
+    let (report, crc) = message.split_at_checked(6)?;
+    (report[0] == REPORT && crc32(report).to_be_bytes() == crc).then(|| {
+        (
+            u32::from_be_bytes(report[1..5].try_into().unwrap()),
+            report[5] == 1,
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coder::{SegmentStats, Uncoded};
+
+    const FRAME_BYTES: usize = 16;
+
+    // passes the data on as it is, reporting the same corrected bits every
+    // frame.
+    struct Corrects(u64);
+
+    impl Coder for Corrects {
+        fn name(&self) -> String {
+            format!("corrects {}", self.0)
+        }
+
+        fn encode(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+            Ok(data.to_vec())
+        }
+
+        fn decode(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+            Ok(data.to_vec())
+        }
+
+        fn segment_stats(&self) -> Option<SegmentStats> {
+            Some(SegmentStats {
+                corrected: self.0,
+                ..SegmentStats::default()
+            })
+        }
+    }
+
+    // the frames switched after, on a clean link.
+    fn switches(coders: Vec<Box<dyn Coder>>) -> Vec<u32> {
+        let data = vec![0x5a; 8 * FRAME_BYTES];
+        let mut stage = AdaptiveRate::new(FRAME_BYTES, coders, 2, 0.001, 0.01);
+        let mut forward = Channel::new(0.0, 0.0).with_seed(1);
+        let delivered = stage
+            .transfer(&data, &mut forward, &mut Feedback::perfect())
+            .unwrap();
+        assert_eq!(delivered, data);
+        let stats = stage.rate_stats().unwrap();
+        assert_eq!(
+            stats.frames.iter().map(|(_, frames)| frames).sum::<u32>(),
+            8
+        );
+        stats.switches.iter().map(|switch| switch.frame).collect()
+    }
+
+    #[test]
+    fn error_rates_between_the_thresholds_keep_the_coder() {
+        // a bit in the 208 of every frame, between 0.001 and 0.01.
+        assert!(switches(vec![Box::new(Corrects(1)), Box::new(Uncoded)]).is_empty());
+        assert!(switches(vec![Box::new(Uncoded), Box::new(Corrects(0))]).is_empty());
+    }
+
+    #[test]
+    fn error_rates_beyond_the_thresholds_switch_once_the_window_is_full() {
+        // up past raise after every second frame, down past lower after the
+        // next two.
+        assert_eq!(
+            switches(vec![Box::new(Corrects(100)), Box::new(Uncoded)]),
+            [1, 3, 5, 7]
+        );
+    }
+
+    #[test]
+    fn failed_frames_switch_to_a_stronger_coder_at_once() {
+        let mut stage = AdaptiveRate::new(
+            FRAME_BYTES,
+            vec![Box::new(Uncoded), Box::new(Corrects(0))],
+            2,
+            0.001,
+            0.01,
+        );
+        let mut forward = Channel::gilbert_elliott(0.0, 1.0, 0.5, 0.5).with_seed(1);
+        stage
+            .transfer(
+                &[0; 4 * FRAME_BYTES],
+                &mut forward,
+                &mut Feedback::perfect(),
+            )
+            .unwrap();
+        let stats = stage.rate_stats().unwrap();
+        // the strongest coder has nowhere to go.
+        assert_eq!(stats.switches.len(), 1);
+        assert_eq!(stats.switches[0].frame, 0);
+        assert_eq!(stats.switches[0].to, "corrects 0");
+        assert_eq!(
+            stats.frames,
+            [("Uncoded".to_string(), 1), ("corrects 0".to_string(), 3)]
+        );
+    }
+}
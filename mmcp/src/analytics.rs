@@ -1,45 +1,1292 @@
-use async_std::{fs::File, io::BufReader, prelude::*};
-use color_eyre::eyre::Result;
+use color_eyre::eyre::{eyre, Result};
 use num_format::{Locale, ToFormattedString};
 use prettytable::{format::Alignment, Row, Table};
-use std::{iter::FromIterator, time::Duration};
+use serde::{Deserialize, Serialize};
+use std::{
+    io::{Cursor, Write},
+    iter::FromIterator,
+    path::Path,
+    str::FromStr,
+    time::Duration,
+};
 
-use crate::channel::{Channel, ChannelInformation};
+use crate::{
+    adaptive::RateStats,
+    arq::ArqStats,
+    audio::AudioQuality,
+    build_info::{self, BuildInfo},
+    bursts::{Bursts, Gaps},
+    channel::{Channel, ChannelInformation, Model},
+    coder::{CipherStats, Coder, CompressionStats, LineStats, SegmentStats, Uncoded},
+    config::Thresholds,
+    error_map,
+    feedback::FeedbackStats,
+    framing::StreamHeader,
+    media::{MediaDamage, Mp4},
+    pipeline::{self, QueueStats, RunMetrics, Source, StageTime},
+    residuals::Residuals,
+    theory,
+    trials::{Estimate, PairedTest},
+};
 
-const BUF_SIZE: usize = 4096;
+// parts the payload is split into for the residual errors of each, which
+// pair two coders within a single run.
+const RESIDUAL_BLOCKS: usize = 32;
 
-#[derive(Debug)]
+mod compare;
+mod html;
+
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Analytics {
-    residual_bit_errors: u32,
-    channel_bit_errors: u32,
-    input_byte_count: u32,
-    channel_byte_count: u32,
+    coder: String,
+    interleaver: String,
+    payload: String,
+    residual_bit_errors: u64,
+    channel_bit_errors: u64,
+    // bytes the decoded payload lacks at its end or has beyond the original,
+    // every bit of them counted as a residual error.
+    missing_bytes: u64,
+    extra_bytes: u64,
+    input_byte_count: u64,
+    channel_byte_count: u64,
+    #[serde(with = "seconds")]
     end_to_end_time: Duration,
+    #[serde(with = "seconds::option")]
+    latency: Option<Duration>,
+    #[serde(with = "seconds::stages")]
+    busy_time: Vec<(String, Duration)>,
+    stage_time: Option<StageTime>,
+    // megabytes encoded and decoded per second by a benchmark.
+    throughput: Option<(f64, f64)>,
+    peak_memory: Option<usize>,
+    allocations: Option<u64>,
+    queues: Vec<(String, QueueStats)>,
     channel: ChannelInformation,
+    // of the bits the channel flipped and of the payload bits decoded wrong.
+    channel_bursts: Bursts,
+    residual_bursts: Bursts,
+    // residual errors by the bit of the byte they hit, most significant
+    // first, and by the payload bit of the codeword.
+    byte_positions: [u64; 8],
+    codeword_positions: Option<Vec<u64>>,
+    // residual errors by the part of the payload they hit, extra bytes in
+    // the last.
+    residual_blocks: Vec<u64>,
+    // what the residual errors hit of an MP4 payload.
+    media: Option<MediaDamage>,
+    // how loud the noise is the residual errors added to a WAV payload.
+    audio: Option<AudioQuality>,
+    segment_stats: Option<SegmentStats>,
+    layer_stats: Vec<(String, SegmentStats)>,
+    codeword_error_rate: Option<f64>,
+    // codewords the decoder corrected to a value other than the one sent,
+    // and those it passed as received although they were wrong.
+    miscorrected: Option<u64>,
+    undetected_codewords: Option<u64>,
+    frame_error_rate: Option<f64>,
+    // frames whose CRC failed and those it missed.
+    detected_frame_errors: Option<u64>,
+    undetected_frame_errors: Option<u64>,
+    // lost packets, their share of all packets and how many were recovered.
+    packets: Option<(u64, f64, u64)>,
+    line: Option<LineStats>,
+    compression: Option<CompressionStats>,
+    cipher: Option<CipherStats>,
+    feedback: Option<FeedbackStats>,
+    arq: Option<ArqStats>,
+    rates: Option<RateStats>,
+    // share of the channel bits a baseline run on the same channel needed
+    // that this run did without.
+    bandwidth_saved: Option<f64>,
+    header_valid: Option<bool>,
+    // named metrics the coders recorded, see `metrics`.
+    metrics: Vec<(String, f64)>,
+    build: BuildInfo,
+}
+
+/// The run against the payload it sent, compared while the run wrote the
+/// decoded payload.
+pub async fn analyze(channel: &Channel, mut run_metrics: RunMetrics) -> Result<Analytics> {
+    let residuals = run_metrics.output.take_residuals();
+    Analytics::new(channel, run_metrics, &pipeline::files().source(), residuals)
+}
+
+/// The run with `result` in place of its decoded payload, compared with
+/// `reference` in place of its payload, for payloads decoded or sent some
+/// other way than the run settled.
+pub async fn analyze_sources(
+    channel: &Channel,
+    run_metrics: RunMetrics,
+    reference: &Source,
+    result: &Source,
+) -> Result<Analytics> {
+    let residuals = Residuals::between(reference, result).await?;
+    Analytics::new(channel, run_metrics, reference, residuals)
+}
+
+impl Analytics {
+    /// A run of any pipeline, the decoded payload of its metrics compared with
+    /// `reference` into `residuals`, see [`Residuals::between`].
+    pub fn new(
+        channel: &Channel,
+        run_metrics: RunMetrics,
+        reference: &Source,
+        residuals: Residuals,
+    ) -> Result<Self> {
+        // neither is read again but for the boxes of an MP4 payload telling
+        // where its samples lie.
+        let mp4 = match reference {
+            Source::File(path) => Mp4::read(std::fs::File::open(path)?),
+            Source::Memory { bytes, .. } => Mp4::read(Cursor::new(&bytes[..])),
+        };
+        let frame_size = run_metrics
+            .frame_stats
+            .as_ref()
+            .map(|stats| stats.frame_size);
+        let packet_count = run_metrics
+            .packet_stats
+            .as_ref()
+            .map(|stats| stats.lost.len());
+        let codeword_bits = run_metrics.codeword_bits;
+        let input_byte_count = run_metrics.input_byte_count as usize;
+        let mut corrupted_codewords = vec![];
+        let mut corrupted_frames = vec![];
+        let mut corrupted_packets = vec![];
+        let mut residual_bursts = Bursts::default();
+        let mut corrupted_bytes = vec![];
+        let mut byte_positions = [0; 8];
+        let mut codeword_positions = codeword_bits.map(|bits| vec![0; bits]);
+        let payload_length = residuals.payload_length();
+        let mut residual_blocks = vec![0; RESIDUAL_BLOCKS];
+        let mut corrupted = |index: usize, difference: u8| {
+            if mp4.is_some() {
+                corrupted_bytes.push(index as u64);
+            }
+            residual_blocks[index * RESIDUAL_BLOCKS / payload_length as usize] +=
+                difference.count_ones() as u64;
+            for bit in (0..8).filter(|bit| difference & 0x80 >> bit != 0) {
+                residual_bursts.error((index * 8 + bit) as u64);
+                byte_positions[bit] += 1;
+                // the codewords start with those of the stream header.
+                if let (Some(codeword_bits), Some(positions)) =
+                    (codeword_bits, codeword_positions.as_mut())
+                {
+                    let position = StreamHeader::framed_bytes(index) * 8 + bit;
+                    corrupted_codewords.push(position / codeword_bits);
+                    positions[position % codeword_bits] += 1;
+                }
+            }
+            if let Some(frame_size) = frame_size {
+                corrupted_frames.push(index / frame_size);
+            }
+            // the coder is assumed to spread the input evenly over the packets.
+            if let Some(packet_count) = packet_count {
+                corrupted_packets.push(index * packet_count / input_byte_count);
+            }
+        };
+        for (index, difference) in residuals.differences() {
+            corrupted(index as usize, difference);
+        }
+        // a decoder cutting the payload short got the rest wrong, and one
+        // adding to it every byte it added.
+        let missing_bytes = residuals.missing_bytes();
+        let extra_bytes = residuals.extra_bytes();
+        for index in payload_length - missing_bytes..payload_length {
+            corrupted(index as usize, 0xff);
+        }
+        residual_blocks[RESIDUAL_BLOCKS - 1] += 8 * extra_bytes;
+        let residual_bit_errors = residuals.bit_errors() + 8 * (missing_bytes + extra_bytes);
+        residual_bursts.finish();
+        let media = mp4.map(|mp4| mp4.damage(corrupted_bytes.into_iter()));
+        let audio = residuals.audio();
+        corrupted_codewords.dedup();
+        corrupted_frames.dedup();
+        corrupted_packets.dedup();
+        error_map::save(run_metrics.output.label(), &residuals)?;
+        run_metrics.output.persist()?;
+
+        // out of the codewords holding any of the payload.
+        let codeword_error_rate = codeword_bits.map(|bits| {
+            let first = StreamHeader::framed_bytes(0) * 8 / bits;
+            let end = (StreamHeader::framed_bytes(payload_length as usize) * 8).div_ceil(bits);
+            corrupted_codewords.len() as f64 / (end - first).max(1) as f64
+        });
+
+        let (miscorrected, undetected_codewords) = match &run_metrics.codeword_stats {
+            Some(stats) => {
+                let miscorrected = stats
+                    .corrected
+                    .iter()
+                    .filter(|codeword| corrupted_codewords.binary_search(codeword).is_ok())
+                    .count();
+                let undetected = corrupted_codewords
+                    .iter()
+                    .filter(|codeword| {
+                        stats.corrected.binary_search(codeword).is_err()
+                            && stats.uncorrectable.binary_search(codeword).is_err()
+                    })
+                    .count();
+                (Some(miscorrected as u64), Some(undetected as u64))
+            }
+            None => (None, None),
+        };
+
+        // frames whose CRC matched although the payload differs from the original.
+        let (frame_error_rate, detected_frame_errors, undetected_frame_errors) =
+            match &run_metrics.frame_stats {
+                Some(stats) => {
+                    let failed = stats.passed.iter().filter(|&&passed| !passed).count();
+                    let undetected = corrupted_frames
+                        .iter()
+                        .filter(|&&frame| stats.passed.get(frame) == Some(&true))
+                        .count();
+                    (
+                        Some(failed as f64 / stats.passed.len() as f64),
+                        Some(failed as u64),
+                        Some(undetected as u64),
+                    )
+                }
+                None => (None, None, None),
+            };
+
+        // lost packets whose share of the output came out right.
+        let packets = run_metrics.packet_stats.as_ref().map(|stats| {
+            let lost = stats.lost.iter().filter(|&&lost| lost).count();
+            let recovered = stats
+                .lost
+                .iter()
+                .enumerate()
+                .filter(|&(packet, &lost)| {
+                    lost && corrupted_packets.binary_search(&packet).is_err()
+                })
+                .count();
+            (
+                lost as u64,
+                lost as f64 / stats.lost.len() as f64,
+                recovered as u64,
+            )
+        });
+
+        Ok(Analytics {
+            coder: run_metrics.coder,
+            interleaver: run_metrics.interleaver,
+            payload: reference.name(),
+            residual_bit_errors,
+            channel_bit_errors: channel.channel_bit_errors(),
+            missing_bytes,
+            extra_bytes,
+            input_byte_count: run_metrics.input_byte_count,
+            channel_byte_count: run_metrics.channel_byte_count,
+            end_to_end_time: run_metrics.end_to_end_time,
+            latency: run_metrics.latency,
+            busy_time: run_metrics.busy_time,
+            stage_time: run_metrics.stage_time,
+            throughput: None,
+            peak_memory: run_metrics.peak_memory,
+            allocations: run_metrics.allocations,
+            queues: run_metrics.queue_stats,
+            channel: channel.channel_information(),
+            channel_bursts: channel.bursts(),
+            residual_bursts,
+            byte_positions,
+            codeword_positions,
+            residual_blocks,
+            media,
+            audio,
+            segment_stats: run_metrics.segment_stats,
+            layer_stats: run_metrics.layer_stats,
+            codeword_error_rate,
+            miscorrected,
+            undetected_codewords,
+            frame_error_rate,
+            detected_frame_errors,
+            undetected_frame_errors,
+            packets,
+            line: run_metrics.line_stats,
+            compression: run_metrics.compression_stats,
+            cipher: run_metrics.cipher_stats,
+            feedback: run_metrics.feedback_stats,
+            arq: run_metrics.arq_stats,
+            rates: run_metrics.rate_stats,
+            bandwidth_saved: None,
+            header_valid: run_metrics.header_valid,
+            metrics: run_metrics.metrics,
+            build: build_info::get(),
+        })
+    }
+}
+
+/// The main figures of a report row, as kept in run manifests.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Summary {
+    pub coder: String,
+    pub interleaver: String,
+    pub payload: String,
+    pub channel: String,
+    pub end_to_end_ms: f64,
+    pub input_bits: u64,
+    pub channel_bits: u64,
+    pub channel_errors: u64,
+    pub residual_errors: u64,
+    pub header_valid: Option<bool>,
+    /// Missing in manifests written before it was kept.
+    #[serde(default)]
+    pub build: Option<BuildInfo>,
+}
+
+/// A line of the CSV file of `--csv`, plain numbers only, the times in
+/// seconds.
+#[derive(Debug, Serialize)]
+struct Record {
+    coder: String,
+    interleaver: String,
+    payload: String,
+    channel: String,
+    seed: u64,
+    end_to_end_time: f64,
+    latency: Option<f64>,
+    encode_time: Option<f64>,
+    channel_time: Option<f64>,
+    decode_time: Option<f64>,
+    input_bits: u64,
+    channel_bits: u64,
+    code_rate: f64,
+    channel_errors: u64,
+    residual_errors: u64,
+    missing_bytes: u64,
+    extra_bytes: u64,
+    channel_error_rate: f64,
+    residual_error_rate: f64,
+    coding_gain_db: Option<f64>,
+    theoretical_error_rate: Option<f64>,
+    capacity: Option<f64>,
+    rate_limit: Option<f64>,
+    channel_burst_mean: Option<f64>,
+    channel_burst_p95: Option<u64>,
+    channel_burst_max: Option<u64>,
+    residual_burst_mean: Option<f64>,
+    residual_burst_p95: Option<u64>,
+    residual_burst_max: Option<u64>,
+    byte_positions: String,
+    codeword_positions: Option<String>,
+    media_seconds_hit: Option<u32>,
+    media_seconds: Option<u32>,
+    damaged_frames: Option<u32>,
+    damaged_key_frames: Option<u32>,
+    damaged_audio_samples: Option<u32>,
+    container_damaged: Option<bool>,
+    audio_snr: Option<f64>,
+    audio_samples_hit: Option<u64>,
+    audio_samples: Option<u64>,
+    channel_gap_mean: Option<f64>,
+    channel_gap_cv: Option<f64>,
+    channel_gap_max: Option<u64>,
+    residual_gap_mean: Option<f64>,
+    residual_gap_cv: Option<f64>,
+    residual_gap_max: Option<u64>,
+    corrected: Option<u64>,
+    uncorrectable: Option<u64>,
+    passed: Option<u64>,
+    miscorrected: Option<u64>,
+    silent_codewords: Option<u64>,
+    codeword_error_rate: Option<f64>,
+    frame_error_rate: Option<f64>,
+    detected_frames: Option<u64>,
+    undetected_frames: Option<u64>,
+    peak_memory: Option<usize>,
+    allocations: Option<u64>,
+    header_valid: Option<bool>,
+    // name=value pairs separated by semicolons.
+    metrics: String,
+    crate_name: String,
+    version: String,
+    commit: String,
+}
+
+impl Analytics {
+    pub fn coder(&self) -> &str {
+        &self.coder
+    }
+
+    pub fn interleaver(&self) -> &str {
+        &self.interleaver
+    }
+
+    /// The file name of the payload.
+    pub fn payload(&self) -> &str {
+        &self.payload
+    }
+
+    /// Payload bits decoded wrong, missing and extra bytes included.
+    pub fn residual_bit_errors(&self) -> u64 {
+        self.residual_bit_errors
+    }
+
+    pub fn channel_bit_errors(&self) -> u64 {
+        self.channel_bit_errors
+    }
+
+    /// Bytes of the payload the decoded payload lacks at its end.
+    pub fn missing_bytes(&self) -> u64 {
+        self.missing_bytes
+    }
+
+    /// Bytes the decoded payload has beyond the end of the payload.
+    pub fn extra_bytes(&self) -> u64 {
+        self.extra_bytes
+    }
+
+    pub fn input_byte_count(&self) -> u64 {
+        self.input_byte_count
+    }
+
+    pub fn channel_byte_count(&self) -> u64 {
+        self.channel_byte_count
+    }
+
+    pub fn end_to_end_time(&self) -> Duration {
+        self.end_to_end_time
+    }
+
+    /// Time until the first byte of the decoded payload came out.
+    pub fn latency(&self) -> Option<Duration> {
+        self.latency
+    }
+
+    /// Time every stage spent working, by the name of the stage.
+    pub fn busy_time(&self) -> &[(String, Duration)] {
+        &self.busy_time
+    }
+
+    pub fn stage_time(&self) -> Option<StageTime> {
+        self.stage_time
+    }
+
+    /// Megabytes encoded and decoded per second, measured by a benchmark.
+    pub fn throughput(&self) -> Option<(f64, f64)> {
+        self.throughput
+    }
+
+    /// Bytes the run had allocated at most at once, None if other runs ran
+    /// at the same time.
+    pub fn peak_memory(&self) -> Option<usize> {
+        self.peak_memory
+    }
+
+    /// Allocations of the run, None if other runs ran at the same time.
+    pub fn allocations(&self) -> Option<u64> {
+        self.allocations
+    }
+
+    /// The queue after every stage, by the name of the stage.
+    pub fn queues(&self) -> &[(String, QueueStats)] {
+        &self.queues
+    }
+
+    pub fn channel(&self) -> &ChannelInformation {
+        &self.channel
+    }
+
+    /// The bursts of the bits the channel flipped.
+    pub fn channel_bursts(&self) -> &Bursts {
+        &self.channel_bursts
+    }
+
+    /// The bursts of the payload bits decoded wrong.
+    pub fn residual_bursts(&self) -> &Bursts {
+        &self.residual_bursts
+    }
+
+    /// Residual errors by the bit of the byte they hit, most significant
+    /// first.
+    pub fn byte_positions(&self) -> [u64; 8] {
+        self.byte_positions
+    }
+
+    /// Residual errors by the payload bit of the codeword they hit, for
+    /// coders with codewords.
+    pub fn codeword_positions(&self) -> Option<&[u64]> {
+        self.codeword_positions.as_deref()
+    }
+
+    /// Residual errors by the part of the payload they hit, the payload
+    /// split into equal parts, bytes the decoder added counted in the last.
+    pub fn residual_blocks(&self) -> &[u64] {
+        &self.residual_blocks
+    }
+
+    /// What the residual errors hit of an MP4 payload.
+    pub fn media(&self) -> Option<MediaDamage> {
+        self.media
+    }
+
+    /// How the decoded audio of a WAV payload compares.
+    pub fn audio(&self) -> Option<AudioQuality> {
+        self.audio
+    }
+
+    pub fn segment_stats(&self) -> Option<SegmentStats> {
+        self.segment_stats
+    }
+
+    /// The segments of every layer of a stacked coder, by its name.
+    pub fn layer_stats(&self) -> &[(String, SegmentStats)] {
+        &self.layer_stats
+    }
+
+    /// Share of the codewords holding payload with a residual error.
+    pub fn codeword_error_rate(&self) -> Option<f64> {
+        self.codeword_error_rate
+    }
+
+    /// Codewords the decoder corrected to a value other than the one sent.
+    pub fn miscorrected(&self) -> Option<u64> {
+        self.miscorrected
+    }
+
+    /// Codewords the decoder passed as received although they were wrong.
+    pub fn undetected_codewords(&self) -> Option<u64> {
+        self.undetected_codewords
+    }
+
+    pub fn frame_error_rate(&self) -> Option<f64> {
+        self.frame_error_rate
+    }
+
+    /// Frames whose CRC failed.
+    pub fn detected_frame_errors(&self) -> Option<u64> {
+        self.detected_frame_errors
+    }
+
+    /// Frames whose CRC passed although they were wrong.
+    pub fn undetected_frame_errors(&self) -> Option<u64> {
+        self.undetected_frame_errors
+    }
+
+    /// Lost packets, their share of all packets and how many of them were
+    /// recovered.
+    pub fn packets(&self) -> Option<(u64, f64, u64)> {
+        self.packets
+    }
+
+    pub fn line(&self) -> Option<LineStats> {
+        self.line
+    }
+
+    pub fn compression(&self) -> Option<CompressionStats> {
+        self.compression
+    }
+
+    pub fn cipher(&self) -> Option<CipherStats> {
+        self.cipher
+    }
+
+    pub fn feedback(&self) -> Option<FeedbackStats> {
+        self.feedback
+    }
+
+    pub fn arq(&self) -> Option<ArqStats> {
+        self.arq
+    }
+
+    pub fn rates(&self) -> Option<&RateStats> {
+        self.rates.as_ref()
+    }
+
+    /// Share of the channel bits a baseline run on the same channel needed
+    /// that this run did without, see [`Analytics::compare_bandwidth`].
+    pub fn bandwidth_saved(&self) -> Option<f64> {
+        self.bandwidth_saved
+    }
+
+    /// Whether the stream header arrived intact.
+    pub fn header_valid(&self) -> Option<bool> {
+        self.header_valid
+    }
+
+    /// Named metrics the coders recorded during the run, see
+    /// [`crate::metrics`].
+    pub fn metrics(&self) -> &[(String, f64)] {
+        &self.metrics
+    }
+
+    pub fn build(&self) -> &BuildInfo {
+        &self.build
+    }
+
+    pub fn summary(&self) -> Summary {
+        Summary {
+            coder: self.coder.clone(),
+            interleaver: self.interleaver.clone(),
+            payload: self.payload.clone(),
+            channel: self.channel_name(),
+            end_to_end_ms: self.end_to_end_time.as_micros() as f64 / 1000f64,
+            input_bits: self.input_byte_count * 8,
+            channel_bits: self.channel_byte_count * 8,
+            channel_errors: self.channel_bit_errors,
+            residual_errors: self.residual_bit_errors,
+            header_valid: self.header_valid,
+            build: Some(self.build.clone()),
+        }
+    }
+
+    fn record(&self, coding_gain: Option<(f64, bool)>) -> Record {
+        let input_bits = self.input_byte_count * 8;
+        let channel_bits = self.channel_byte_count * 8;
+        Record {
+            coder: self.coder.clone(),
+            interleaver: self.interleaver.clone(),
+            payload: self.payload.clone(),
+            channel: self.channel_name(),
+            seed: self.channel.seed(),
+            end_to_end_time: self.end_to_end_time.as_secs_f64(),
+            latency: self.latency.map(|latency| latency.as_secs_f64()),
+            encode_time: self.stage_time.map(|time| time.encode.as_secs_f64()),
+            channel_time: self.stage_time.map(|time| time.channel.as_secs_f64()),
+            decode_time: self.stage_time.map(|time| time.decode.as_secs_f64()),
+            input_bits,
+            channel_bits,
+            code_rate: input_bits as f64 / channel_bits.max(1) as f64,
+            channel_errors: self.channel_bit_errors,
+            residual_errors: self.residual_bit_errors,
+            missing_bytes: self.missing_bytes,
+            extra_bytes: self.extra_bytes,
+            channel_error_rate: self.channel_bit_errors as f64 / channel_bits.max(1) as f64,
+            residual_error_rate: self.residual_bit_errors as f64 / input_bits.max(1) as f64,
+            coding_gain_db: coding_gain.map(|(gain, _)| gain),
+            theoretical_error_rate: self.theoretical_bit_error_rate(),
+            capacity: self.shannon_limit().map(|(capacity, _)| capacity),
+            rate_limit: self.shannon_limit().map(|(_, limit)| limit),
+            channel_burst_mean: self.channel_bursts.mean(),
+            channel_burst_p95: self.channel_bursts.percentile(0.95),
+            channel_burst_max: self.channel_bursts.max(),
+            residual_burst_mean: self.residual_bursts.mean(),
+            residual_burst_p95: self.residual_bursts.percentile(0.95),
+            residual_burst_max: self.residual_bursts.max(),
+            byte_positions: histogram(&self.byte_positions),
+            codeword_positions: self.codeword_positions.as_deref().map(histogram),
+            media_seconds_hit: self.media.map(|media| media.seconds_hit),
+            media_seconds: self.media.map(|media| media.seconds),
+            damaged_frames: self.media.map(|media| media.frames),
+            damaged_key_frames: self.media.map(|media| media.key_frames),
+            damaged_audio_samples: self.media.map(|media| media.audio_samples),
+            container_damaged: self.media.map(|media| media.container),
+            audio_snr: self.audio.and_then(|audio| audio.snr),
+            audio_samples_hit: self.audio.map(|audio| audio.samples_hit),
+            audio_samples: self.audio.map(|audio| audio.samples),
+            channel_gap_mean: self.channel_bursts.gaps().mean(),
+            channel_gap_cv: self.channel_bursts.gaps().coefficient_of_variation(),
+            channel_gap_max: self.channel_bursts.gaps().max(),
+            residual_gap_mean: self.residual_bursts.gaps().mean(),
+            residual_gap_cv: self.residual_bursts.gaps().coefficient_of_variation(),
+            residual_gap_max: self.residual_bursts.gaps().max(),
+            corrected: self.segment_stats.map(|stats| stats.corrected),
+            uncorrectable: self.segment_stats.map(|stats| stats.uncorrectable),
+            passed: self.segment_stats.map(|stats| stats.passed),
+            miscorrected: self.miscorrected,
+            silent_codewords: self.silent_codewords(),
+            codeword_error_rate: self.codeword_error_rate,
+            frame_error_rate: self.frame_error_rate,
+            detected_frames: self.detected_frame_errors,
+            undetected_frames: self.undetected_frame_errors,
+            peak_memory: self.peak_memory,
+            allocations: self.allocations,
+            header_valid: self.header_valid,
+            metrics: self
+                .metrics
+                .iter()
+                .map(|(name, value)| format!("{}={}", name, value))
+                .collect::<Vec<_>>()
+                .join(";"),
+            crate_name: self.build.crate_name.clone(),
+            version: self.build.version.clone(),
+            commit: self.build.commit.clone(),
+        }
+    }
+
+    // codewords that came out wrong without the decoder noticing.
+    fn silent_codewords(&self) -> Option<u64> {
+        Some(self.miscorrected? + self.undetected_codewords?)
+    }
+
+    fn channel_name(&self) -> String {
+        format!(
+            "{}{}",
+            self.channel.model(),
+            if self.channel.erasures() {
+                ", erasures"
+            } else if self.channel.soft_output() {
+                ", soft"
+            } else {
+                ""
+            }
+        )
+    }
+
+    /// Compares the channel bits of this run against a baseline run over the
+    /// same channel trace, shown as the bandwidth saved.
+    pub fn compare_bandwidth(&mut self, baseline: &Analytics) {
+        self.bandwidth_saved =
+            Some(1.0 - self.channel_byte_count as f64 / baseline.channel_byte_count as f64);
+    }
+
+    /// The Eb/N0 in dB the coder saves against the uncoded baseline run on
+    /// the same channel, at the residual error rate of this run. The errors
+    /// of any channel count as those of BPSK over an AWGN channel at some
+    /// Eb/N0, so channels other than AWGN spend the channel bits of the
+    /// code as energy. With no residual errors it is a lower bound, true
+    /// next to it. None without a baseline or at error rates of one half
+    /// and above, which no Eb/N0 gives.
+    pub fn coding_gain(&self, runs: &[Analytics]) -> Option<(f64, bool)> {
+        let uncoded = Uncoded.name();
+        let baseline = runs.iter().find(|run| {
+            run.coder == uncoded
+                && run.payload == self.payload
+                && run.channel_name() == self.channel_name()
+                && run.channel.seed() == self.channel.seed()
+        })?;
+        if self.coder == uncoded {
+            return None;
+        }
+        let error_rate = |run: &Analytics| {
+            run.residual_bit_errors as f64 / (run.input_byte_count as f64 * 8.0).max(1.0)
+        };
+        let uncoded_rate = error_rate(baseline);
+        // a single error would have been one in all of the payload bits.
+        let (coded_rate, bound) = match self.residual_bit_errors {
+            0 => (1.0 / (self.input_byte_count as f64 * 8.0).max(1.0), true),
+            _ => (error_rate(self), false),
+        };
+        if !(0.0 < uncoded_rate && uncoded_rate < 0.5 && coded_rate < 0.5) {
+            return None;
+        }
+        // what uncoded BPSK would need for the coded error rate on top of
+        // what it had. the AWGN channel already sends every bit of the code
+        // with less energy, see `Channel::set_code_rate`.
+        let needed = theory::uncoded_eb_n0_db(coded_rate) - theory::uncoded_eb_n0_db(uncoded_rate);
+        let spent = match self.channel.model() {
+            Model::Awgn { .. } => 0.0,
+            _ => 10.0 * (self.channel_byte_count as f64 / self.input_byte_count as f64).log10(),
+        };
+        Some((needed - spent, bound))
+    }
+
+    /// The payload bit error rate theory expects of Hamming codes and the
+    /// uncoded baseline on a binary symmetric channel with the average error
+    /// rate of the channel of this run, which ignores the bursts of channels
+    /// with memory. None for other coders and channels that lose bits.
+    pub fn theoretical_bit_error_rate(&self) -> Option<f64> {
+        if self.channel.erasures() || self.channel.soft_output() {
+            return None;
+        }
+        if self.coder == Uncoded.name() {
+            return theory::crossover(&self.channel.model(), 1.0);
+        }
+        let (n, k) = self
+            .coder
+            .strip_prefix("Hamming(")?
+            .split_once(')')?
+            .0
+            .split_once(',')?;
+        let (n, k): (usize, usize) = (n.parse().ok()?, k.parse().ok()?);
+        let p = theory::crossover(&self.channel.model(), k as f64 / n as f64)?;
+        theory::hamming_bit_error_rate(n, p)
+    }
+
+    /// The capacity of the channel of this run in payload bits per channel
+    /// bit and the highest code rate any code could have had at the
+    /// residual bit error rate of this run, the Shannon limit the rate of
+    /// the run is held against. Channels other than AWGN count as binary
+    /// symmetric ones, see [`theory::capacity`]. None for channels that lose
+    /// bits or give more than hard decisions, and at residual error rates
+    /// of one half and above.
+    pub fn shannon_limit(&self) -> Option<(f64, f64)> {
+        let model = self.channel.model();
+        let hard = !self.channel.erasures() && !self.channel.soft_output();
+        if !hard && !matches!(model, Model::Awgn { .. }) {
+            return None;
+        }
+        let input_bits = self.input_byte_count as f64 * 8.0;
+        let error_rate = self.residual_bit_errors as f64 / input_bits.max(1.0);
+        if error_rate >= 0.5 {
+            return None;
+        }
+        let rate = input_bits / (self.channel_byte_count as f64 * 8.0).max(1.0);
+        let capacity = theory::capacity(&model, rate)?;
+        Some((capacity, theory::rate_limit(capacity, error_rate)))
+    }
+
+    /// Shows the megabytes a benchmark encoded and decoded per second.
+    pub fn set_throughput(&mut self, encode: f64, decode: f64) {
+        self.throughput = Some((encode, decode));
+    }
 }
 
-pub async fn analyze(channel: &Channel, run_metrics: (Duration, u32, u32)) -> Result<Analytics> {
-    let (end_to_end_time, input_byte_count, channel_byte_count) = run_metrics;
-    let input = BufReader::with_capacity(BUF_SIZE, File::open("resources/original.mp4").await?);
-    let output = BufReader::with_capacity(BUF_SIZE, File::open("result.mp4").await?);
-    let residual_bit_errors: u32 = input
-        .bytes()
-        .zip(output.bytes())
-        .map::<Result<u32>, _>(|(i, o)| Ok((i? ^ o?).count_ones()))
-        .sum()
-        .await?;
-
-    Ok(Analytics {
-        residual_bit_errors,
-        channel_bit_errors: channel.channel_bit_errors(),
-        input_byte_count,
-        channel_byte_count,
-        end_to_end_time,
-        channel: channel.channel_information(),
+/// How the report is written.
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportFormat {
+    /// The table printed to the terminal.
+    #[default]
+    Table,
+    /// One line per row with the same columns, for spreadsheets and scripts.
+    Csv,
+    /// Every field of every run with the parameters of its channel, for
+    /// scripts and plotting tools.
+    Json,
+    /// The table in Markdown, for issue trackers and lab reports.
+    #[serde(alias = "md")]
+    Markdown,
+    /// A page with the table and charts of the error rate and the overhead
+    /// of the runs, in a single file.
+    Html,
+}
+
+impl FromStr for ReportFormat {
+    type Err = color_eyre::Report;
+
+    fn from_str(format: &str) -> Result<Self> {
+        match format {
+            "table" => Ok(Self::Table),
+            "csv" => Ok(Self::Csv),
+            "json" => Ok(Self::Json),
+            "md" | "markdown" => Ok(Self::Markdown),
+            "html" => Ok(Self::Html),
+            _ => Err(eyre!(
+                "unknown report format {}, expected table, csv, json, md or html",
+                format
+            )),
+        }
+    }
+}
+
+/// The report in the format, as [`write_report`] writes it.
+pub fn report(analytics: &[Analytics], format: ReportFormat) -> Result<String> {
+    Ok(match format {
+        ReportFormat::Json => format!("{}\n", to_json(analytics)?),
+        ReportFormat::Html => {
+            let charts = [
+                html::error_rate_chart(analytics),
+                html::overhead_chart(analytics),
+            ];
+            html::page(&raw_table_from_data(analytics), &charts)
+        }
+        _ => render(raw_table_from_data(analytics), format)?,
     })
 }
 
-pub fn report(analytics: &[Analytics]) {
+/// Prints the report table, colored if the terminal supports it.
+pub fn print_report(analytics: &[Analytics]) {
+    table(analytics).printstd();
+}
+
+/// Prints a table in the format of the report table.
+pub fn print(table: Table) {
+    formatted(table).printstd();
+}
+
+/// Writes the report in the format to the file, or to standard output if
+/// there is none.
+pub fn write_report(
+    analytics: &[Analytics],
+    format: ReportFormat,
+    file: Option<&Path>,
+) -> Result<()> {
+    let report = report(analytics, format)?;
+    out(file)?.write_all(report.as_bytes())?;
+    Ok(())
+}
+
+/// Prints the runs of the JSON report after next to those of the one
+/// before, matched by coder, interleaver, payload and channel, with the
+/// residual errors, overhead and time that got worse in red.
+pub fn compare(before: &Path, after: &Path) -> Result<()> {
+    print(compare::table(before, after)?);
+    Ok(())
+}
+
+/// The thresholds every coded run failed, a line per run and threshold. The
+/// uncoded baseline runs are not held to them.
+pub fn failed_thresholds(analytics: &[Analytics], thresholds: &Thresholds) -> Vec<String> {
+    let mut failed = vec![];
+    for run in analytics.iter().filter(|run| run.coder != Uncoded.name()) {
+        let residual_error_ratio =
+            run.residual_bit_errors as f64 / (run.channel_bit_errors as f64).max(1.0);
+        let overhead = run.channel_byte_count as f64 / (run.input_byte_count as f64).max(1.0) - 1.0;
+        let e2e_ms = run.end_to_end_time.as_secs_f64() * 1000.0;
+        let checks = [
+            (
+                "residual error ratio",
+                residual_error_ratio,
+                thresholds.max_residual_error_ratio,
+            ),
+            ("overhead", overhead, thresholds.max_overhead),
+            ("E2E time in ms", e2e_ms, thresholds.max_e2e_ms),
+        ];
+        for (name, value, limit) in checks {
+            if let Some(limit) = limit.filter(|&limit| value > limit) {
+                failed.push(format!(
+                    "{}, {}: {} {:.4} over {}",
+                    run.coder,
+                    run.channel_name(),
+                    name,
+                    value,
+                    limit
+                ));
+            }
+        }
+    }
+    failed
+}
+
+/// Writes a line per run with its plain figures to the CSV file, unlike the
+/// csv report, whose cells are formatted like the table.
+pub fn write_csv(analytics: &[Analytics], path: &Path) -> Result<()> {
+    let mut writer = csv::Writer::from_path(path)?;
+    for run in analytics {
+        writer.serialize(run.record(run.coding_gain(analytics)))?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// The runs as a JSON array, every field of a run with the parameters of its
+/// channel and the times in seconds.
+pub fn to_json(analytics: &[Analytics]) -> Result<String> {
+    Ok(serde_json::to_string_pretty(analytics)?)
+}
+
+/// Writes the report of [`payload_report`] to the file, or to standard
+/// output if there is none.
+pub fn write_payload_report(
+    analytics: &[Analytics],
+    format: ReportFormat,
+    file: Option<&Path>,
+) -> Result<()> {
+    let report = payload_report(analytics, format)?;
+    out(file)?.write_all(report.as_bytes())?;
+    Ok(())
+}
+
+/// A row per payload and coder with the errors of all its channels summed
+/// up, in the order the coders first ran, for comparing coders over a batch
+/// of payloads.
+pub fn payload_report(analytics: &[Analytics], format: ReportFormat) -> Result<String> {
+    let locale = &Locale::en;
+    let mut totals: Vec<PayloadTotal> = vec![];
+    for run in analytics {
+        let same = |total: &&mut PayloadTotal| {
+            (
+                &total.first.payload,
+                &total.first.coder,
+                &total.first.interleaver,
+            ) == (&run.payload, &run.coder, &run.interleaver)
+        };
+        let total = match totals.iter_mut().find(same) {
+            Some(total) => total,
+            None => {
+                totals.push(PayloadTotal {
+                    first: run,
+                    channels: 0,
+                    input_bits: 0,
+                    channel_errors: 0,
+                    residual_errors: 0,
+                });
+                totals.last_mut().unwrap()
+            }
+        };
+        total.channels += 1;
+        total.input_bits += run.input_byte_count * 8;
+        total.channel_errors += run.channel_bit_errors;
+        total.residual_errors += run.residual_bit_errors;
+    }
+    let mut table = Table::new();
+    table.add_row(Row::from_iter(vec![
+        "Payload",
+        "Coder",
+        "Interleaver",
+        "Channels",
+        "Input Bits",
+        "Channel Errors",
+        "Residual Errors",
+        "Residual Bit Error Rate",
+    ]));
+    for total in totals {
+        table.add_row(Row::from_iter(vec![
+            total.first.payload.clone(),
+            total.first.coder.clone(),
+            total.first.interleaver.clone(),
+            total.channels.to_string(),
+            format!("{} bit", total.input_bits.to_formatted_string(locale)),
+            total.channel_errors.to_formatted_string(locale),
+            total.residual_errors.to_formatted_string(locale),
+            format!(
+                "{:.3e}",
+                total.residual_errors as f64 / total.input_bits as f64
+            ),
+        ]));
+    }
+    render(table, format)
+}
+
+// the runs of one coder over every channel with one payload.
+struct PayloadTotal<'a> {
+    first: &'a Analytics,
+    channels: u32,
+    input_bits: u64,
+    channel_errors: u64,
+    residual_errors: u64,
+}
+
+/// Writes the report of [`trials_report`] to the file, or to standard
+/// output if there is none.
+pub fn write_trials_report(
+    analytics: &[Analytics],
+    format: ReportFormat,
+    file: Option<&Path>,
+) -> Result<()> {
+    let report = trials_report(analytics, format)?;
+    out(file)?.write_all(report.as_bytes())?;
+    Ok(())
+}
+
+/// A row per payload, coder and channel with the mean of its residual
+/// errors and times over the trials of `--trials` and the 95% confidence
+/// interval around it, in the order the coders first ran.
+pub fn trials_report(analytics: &[Analytics], format: ReportFormat) -> Result<String> {
+    let mut trials: Vec<Vec<&Analytics>> = vec![];
+    for run in analytics {
+        let same = |runs: &&mut Vec<&Analytics>| {
+            let first = runs[0];
+            (
+                &first.payload,
+                &first.coder,
+                &first.interleaver,
+                first.channel_name(),
+            ) == (
+                &run.payload,
+                &run.coder,
+                &run.interleaver,
+                run.channel_name(),
+            )
+        };
+        match trials.iter_mut().find(same) {
+            Some(runs) => runs.push(run),
+            None => trials.push(vec![run]),
+        }
+    }
+    let mut table = Table::new();
+    table.add_row(Row::from_iter(vec![
+        "Payload",
+        "Coder",
+        "Interleaver",
+        "Channel",
+        "Trials",
+        "Residual Errors",
+        "Residual Bit Error Rate",
+        "E2E Time",
+    ]));
+    for runs in trials {
+        let estimate = |figure: fn(&Analytics) -> f64| {
+            Estimate::new(&runs.iter().map(|run| figure(run)).collect::<Vec<_>>())
+        };
+        let errors = estimate(|run| run.residual_bit_errors as f64);
+        let rate = estimate(|run| {
+            run.residual_bit_errors as f64 / (run.input_byte_count as f64 * 8.0).max(1.0)
+        });
+        let time = estimate(|run| run.end_to_end_time.as_micros() as f64 / 1000f64);
+        table.add_row(Row::from_iter(vec![
+            runs[0].payload.clone(),
+            runs[0].coder.clone(),
+            runs[0].interleaver.clone(),
+            runs[0].channel_name(),
+            runs.len().to_string(),
+            interval(errors, |value| format!("{:.1}", value)),
+            interval(rate, |value| format!("{:.3e}", value)),
+            interval(time, |value| format!("{:.3} ms", value)),
+        ]));
+    }
+    render(table, format)
+}
+
+/// Writes the report of [`significance_report`] to the file, or to standard
+/// output if there is none.
+pub fn write_significance_report(
+    analytics: &[Analytics],
+    format: ReportFormat,
+    file: Option<&Path>,
+) -> Result<()> {
+    let report = significance_report(analytics, format)?;
+    out(file)?.write_all(report.as_bytes())?;
+    Ok(())
+}
+
+/// A row per two coders run over the same channel with the same seeds, and
+/// so the same errors, telling whether one left significantly fewer
+/// residual errors: a paired t test over the trials both ran, or over the
+/// blocks of the payload with a single trial.
+pub fn significance_report(analytics: &[Analytics], format: ReportFormat) -> Result<String> {
+    // the runs over every channel with one payload, by coder.
+    let mut channels: Vec<Vec<Vec<&Analytics>>> = vec![];
+    for run in analytics {
+        let same_channel = |coders: &&mut Vec<Vec<&Analytics>>| {
+            let first = coders[0][0];
+            (&first.payload, first.channel_name()) == (&run.payload, run.channel_name())
+        };
+        let Some(coders) = channels.iter_mut().find(same_channel) else {
+            channels.push(vec![vec![run]]);
+            continue;
+        };
+        let same_coder = |runs: &&mut Vec<&Analytics>| {
+            (&runs[0].coder, &runs[0].interleaver) == (&run.coder, &run.interleaver)
+        };
+        match coders.iter_mut().find(same_coder) {
+            Some(runs) => runs.push(run),
+            None => coders.push(vec![run]),
+        }
+    }
+    let mut table = Table::new();
+    table.add_row(Row::from_iter(vec![
+        "Payload",
+        "Channel",
+        "Coder A",
+        "Coder B",
+        "Paired Over",
+        "Residual Errors A - B",
+        "t",
+        "Significant",
+    ]));
+    for coders in &channels {
+        for (index, a) in coders.iter().enumerate() {
+            for b in &coders[index + 1..] {
+                let pairs: Vec<_> = a
+                    .iter()
+                    .filter_map(|a| {
+                        let b = b.iter().find(|b| b.channel.seed() == a.channel.seed())?;
+                        Some((a, b))
+                    })
+                    .collect();
+                let (differences, over): (Vec<f64>, _) = match &pairs[..] {
+                    [] => continue,
+                    [(a, b)] => (
+                        a.residual_blocks
+                            .iter()
+                            .zip(&b.residual_blocks)
+                            .map(|(a, b)| *a as f64 - *b as f64)
+                            .collect(),
+                        "blocks",
+                    ),
+                    pairs => (
+                        pairs
+                            .iter()
+                            .map(|(a, b)| {
+                                a.residual_bit_errors as f64 - b.residual_bit_errors as f64
+                            })
+                            .collect(),
+                        "trials",
+                    ),
+                };
+                let Some(test) = PairedTest::new(&differences) else {
+                    continue;
+                };
+                let (a, b) = (a[0], b[0]);
+                table.add_row(Row::from_iter(vec![
+                    a.payload.clone(),
+                    a.channel_name(),
+                    coder_name(a),
+                    coder_name(b),
+                    format!("{} {}", differences.len(), over),
+                    interval(test.difference, |value| format!("{:.1}", value)),
+                    format!("{:.2}", test.t),
+                    match (test.significant, test.t > 0.0) {
+                        (false, _) => "no".to_string(),
+                        (true, true) => format!("yes, fewer with {}", coder_name(b)),
+                        (true, false) => format!("yes, fewer with {}", coder_name(a)),
+                    },
+                ]));
+            }
+        }
+    }
+    render(table, format)
+}
+
+// the coder with the interleaver in front of it, if any.
+fn coder_name(analytics: &Analytics) -> String {
+    match analytics.interleaver.as_str() {
+        "None" => analytics.coder.clone(),
+        interleaver => format!("{}, {}", analytics.coder, interleaver),
+    }
+}
+
+// the mean and the half width of the confidence interval around it.
+fn interval(estimate: Estimate, format: impl Fn(f64) -> String) -> String {
+    match estimate.margin {
+        Some(margin) => format!("{} ± {}", format(estimate.mean), format(margin)),
+        None => format(estimate.mean),
+    }
+}
+
+fn out(file: Option<&Path>) -> Result<Box<dyn Write>> {
+    Ok(match file {
+        Some(path) => Box::new(std::fs::File::create(path)?),
+        None => Box::new(std::io::stdout()),
+    })
+}
+
+// the table in the format, its cells as they are.
+fn render(table: Table, format: ReportFormat) -> Result<String> {
+    let mut out = vec![];
+    match format {
+        ReportFormat::Table => {
+            formatted(table).print(&mut out)?;
+        }
+        ReportFormat::Csv => {
+            table.to_csv(&mut out)?.flush()?;
+        }
+        // an object per row, the cells under the names of their columns.
+        ReportFormat::Json => {
+            let mut rows = table.row_iter();
+            let columns: Vec<String> = match rows.next() {
+                Some(header) => header.iter().map(|cell| cell.get_content()).collect(),
+                None => vec![],
+            };
+            let rows: Vec<serde_json::Map<String, serde_json::Value>> = rows
+                .map(|row| {
+                    columns
+                        .iter()
+                        .cloned()
+                        .zip(row.iter().map(|cell| cell.get_content().into()))
+                        .collect()
+                })
+                .collect();
+            writeln!(out, "{}", serde_json::to_string_pretty(&rows)?)?;
+        }
+        ReportFormat::Html => out.write_all(html::page(&table, &[]).as_bytes())?,
+        ReportFormat::Markdown => {
+            for (index, row) in table.row_iter().enumerate() {
+                let cells: Vec<String> = row
+                    .iter()
+                    .map(|cell| {
+                        // a cell has to stay on its line.
+                        cell.get_content().replace('|', "\\|").replace('\n', "<br>")
+                    })
+                    .collect();
+                writeln!(out, "| {} |", cells.join(" | "))?;
+                // right aligned as in the table.
+                if index == 0 {
+                    writeln!(out, "|{}", " --: |".repeat(cells.len()))?;
+                }
+            }
+        }
+    }
+    Ok(String::from_utf8(out)?)
+}
+
+fn table(analytics: &[Analytics]) -> Table {
+    formatted(raw_table_from_data(analytics))
+}
+
+fn formatted(mut table: Table) -> Table {
     use prettytable::format;
     let format = format::FormatBuilder::new()
         .column_separator('|')
@@ -54,32 +1301,123 @@ pub fn report(analytics: &[Analytics]) {
         )
         .padding(1, 1)
         .build();
-    let mut table = raw_table_from_data(analytics);
     table.set_format(format);
     right_align(&mut table);
-
-    table.printstd();
+    table
 }
 
 fn raw_table_from_data(analytics: &[Analytics]) -> Table {
     let locale = &Locale::en;
+    // a column per metric any run recorded, in the order they first come.
+    let mut metrics: Vec<String> = vec![];
+    for (name, _) in analytics.iter().flat_map(|analytics| &analytics.metrics) {
+        if !metrics.contains(name) {
+            metrics.push(name.clone());
+        }
+    }
     let mut table = Table::new();
-    table.add_row(Row::from_iter(vec![
+    let mut header = vec![
+        "Coder",
+        "Interleaver",
+        "Payload",
         "E2E Time",
+        "Effective Throughput",
+        "First Byte Latency",
+        "Busy Time",
+        "Encode / Channel / Decode Time",
+        "Encode / Decode MB/s",
+        "Peak Memory",
+        "Allocations / MB",
         "Input Bits",
         "Channel Bits",
         "Overhead Ratio",
+        "Code Rate",
         "Channel",
+        "Seed",
         "Channel Errors",
         "Residual Errors",
+        "Missing / Extra Bytes",
         "Residual Error Ratio",
-    ]));
-    analytics.into_iter().for_each(|analytics| {
-        table.add_row(Row::from_iter(vec![
+        "Coding Gain",
+        "Residual BER / Theory",
+        "Capacity / Rate of Limit",
+        "Burst Length Mean / P95 / Max",
+        "Error-Free Gap Mean / CV / Max",
+        "Residual Errors by Bit of Byte / Codeword",
+        "Media Damage",
+        "Audio SNR",
+        "Corrected / Detected / Passed",
+        "Miscorrected",
+        "Detected / Silent Errors",
+        "Codeword Error Rate",
+        "Frame Error Rate",
+        "Undetected Frame Errors",
+        "Lost / Recovered Packets",
+        "Invalid Symbols / Disparity Errors",
+        "Compression Ratio / Corrupted Blocks",
+        "Rejected Frames",
+        "Feedback Messages / Corrupted",
+        "Retransmissions / Throughput",
+        "Transmissions / Frame",
+        "Bandwidth Saved",
+        "Rate Switches",
+        "Header",
+        "Queue Depth Max / Mean / Full",
+        "Build",
+    ];
+    header.extend(metrics.iter().map(String::as_str));
+    table.add_row(Row::from_iter(header));
+    let runs = analytics;
+    analytics.iter().for_each(|analytics| {
+        let mut row = vec![
+            analytics.coder.clone(),
+            analytics.interleaver.clone(),
+            analytics.payload.clone(),
             format!(
                 "{:.3} ms",
                 analytics.end_to_end_time.as_micros() as f64 / 1000f64
             ),
+            // payload bits delivered per second of the whole run.
+            format!(
+                "{:.3} Mbit/s",
+                (analytics.input_byte_count * 8) as f64
+                    / analytics.end_to_end_time.as_secs_f64()
+                    / 1e6
+            ),
+            // from the first payload byte going in to the first decoded byte
+            // coming out.
+            analytics.latency.map_or("-".to_string(), |latency| {
+                format!("{:.3} ms", latency.as_micros() as f64 / 1000f64)
+            }),
+            busy_time(analytics),
+            analytics.stage_time.map_or("-".to_string(), |time| {
+                let ms = |time: Duration| time.as_micros() as f64 / 1000f64;
+                format!(
+                    "{:.3} ms / {:.3} ms / {:.3} ms",
+                    ms(time.encode),
+                    ms(time.channel),
+                    ms(time.decode)
+                )
+            }),
+            analytics
+                .throughput
+                .map_or("-".to_string(), |(encode, decode)| {
+                    format!("{:.3} / {:.3}", encode, decode)
+                }),
+            // the memory of other runs at the same time would count too.
+            analytics.peak_memory.map_or("-".to_string(), |peak| {
+                format!("{:.3} MiB", peak as f64 / (1 << 20) as f64)
+            }),
+            // per megabyte of the payload, every stage and the channel
+            // included, none for an empty payload.
+            analytics
+                .allocations
+                .filter(|_| analytics.input_byte_count > 0)
+                .map_or("-".to_string(), |allocations| {
+                    ((allocations as f64 / (analytics.input_byte_count as f64 / 1e6)).round()
+                        as u64)
+                        .to_formatted_string(locale)
+                }),
             format!(
                 "{} bit",
                 (analytics.input_byte_count * 8).to_formatted_string(locale)
@@ -89,15 +1427,17 @@ fn raw_table_from_data(analytics: &[Analytics]) -> Table {
                 (analytics.channel_byte_count * 8).to_formatted_string(locale)
             ),
             format!(
-                "{}%",
+                "{:.3}%",
                 ((analytics.channel_byte_count as f64 / analytics.input_byte_count as f64) - 1.0)
                     * 100.0
             ),
+            // input bits per channel bit, puncturing and retransmissions included.
             format!(
-                "h: {:.2}, tau: {:.2}",
-                analytics.channel.get_h(),
-                analytics.channel.get_tau()
+                "{:.3}",
+                analytics.input_byte_count as f64 / analytics.channel_byte_count as f64
             ),
+            analytics.channel_name(),
+            analytics.channel.seed().to_string(),
             format!(
                 "{}",
                 analytics.channel_bit_errors.to_formatted_string(locale)
@@ -106,16 +1446,340 @@ fn raw_table_from_data(analytics: &[Analytics]) -> Table {
                 "{}",
                 analytics.residual_bit_errors.to_formatted_string(locale)
             ),
+            // a decoded payload of another length than the original.
+            match (analytics.missing_bytes, analytics.extra_bytes) {
+                (0, 0) => "-".to_string(),
+                (missing, extra) => format!(
+                    "{} / {}",
+                    missing.to_formatted_string(locale),
+                    extra.to_formatted_string(locale)
+                ),
+            },
             format!(
                 "{:.3}%",
                 (analytics.residual_bit_errors as f64 / analytics.channel_bit_errors as f64)
                     * 100.0
             ),
-        ]));
+            match analytics.coding_gain(runs) {
+                Some((gain, true)) => format!("> {:.2} dB", gain),
+                Some((gain, false)) => format!("{:.2} dB", gain),
+                None => "-".to_string(),
+            },
+            format!(
+                "{:.2e} / {}",
+                analytics.residual_bit_errors as f64
+                    / (analytics.input_byte_count as f64 * 8.0).max(1.0),
+                analytics
+                    .theoretical_bit_error_rate()
+                    .map_or("-".to_string(), |rate| format!("{:.2e}", rate))
+            ),
+            // how close the code rate comes to the highest one at its
+            // residual error rate.
+            analytics
+                .shannon_limit()
+                .map_or("-".to_string(), |(capacity, limit)| {
+                    format!(
+                        "{:.3} / {:.1}%",
+                        capacity,
+                        analytics.input_byte_count as f64
+                            / analytics.channel_byte_count as f64
+                            / limit
+                            * 100.0
+                    )
+                }),
+            format!(
+                "channel: {}\nresidual: {}",
+                bursts(&analytics.channel_bursts),
+                bursts(&analytics.residual_bursts)
+            ),
+            // a coefficient of variation above one shows errors clustering.
+            format!(
+                "channel: {}\nresidual: {}",
+                gaps(analytics.channel_bursts.gaps(), locale),
+                gaps(analytics.residual_bursts.gaps(), locale)
+            ),
+            bit_positions(analytics),
+            analytics.media.map_or("-".to_string(), media_damage),
+            analytics
+                .audio
+                .map_or("-".to_string(), |audio| audio_quality(audio, locale)),
+            segments(analytics, locale),
+            // out of the codewords the decoder corrected.
+            match (analytics.miscorrected, analytics.segment_stats) {
+                (Some(miscorrected), Some(stats)) => format!(
+                    "{} ({:.3}%)",
+                    miscorrected.to_formatted_string(locale),
+                    miscorrected as f64 / stats.corrected.max(1) as f64 * 100.0
+                ),
+                _ => "-".to_string(),
+            },
+            detected_errors(analytics, locale),
+            analytics
+                .codeword_error_rate
+                .map_or("-".to_string(), |rate| format!("{:.3}%", rate * 100.0)),
+            analytics
+                .frame_error_rate
+                .map_or("-".to_string(), |rate| format!("{:.3}%", rate * 100.0)),
+            analytics
+                .undetected_frame_errors
+                .map_or("-".to_string(), |errors| errors.to_formatted_string(locale)),
+            analytics
+                .packets
+                .map_or("-".to_string(), |(lost, rate, recovered)| {
+                    format!(
+                        "{} ({:.3}%) / {}",
+                        lost.to_formatted_string(locale),
+                        rate * 100.0,
+                        recovered.to_formatted_string(locale)
+                    )
+                }),
+            analytics.line.map_or("-".to_string(), |line| {
+                format!(
+                    "{} ({:.3}%) / {}",
+                    line.invalid.to_formatted_string(locale),
+                    line.invalid as f64 / line.symbols as f64 * 100.0,
+                    line.disparity_errors.to_formatted_string(locale)
+                )
+            }),
+            // input bytes per compressed byte, then blocks lost to residual errors.
+            analytics
+                .compression
+                .map_or("-".to_string(), |compression| {
+                    format!(
+                        "{:.3} / {} of {}",
+                        compression.input_bytes as f64 / compression.compressed_bytes as f64,
+                        compression.corrupted.to_formatted_string(locale),
+                        compression.blocks.to_formatted_string(locale)
+                    )
+                }),
+            analytics.cipher.map_or("-".to_string(), |cipher| {
+                format!(
+                    "{} of {}",
+                    cipher.rejected.to_formatted_string(locale),
+                    cipher.frames.to_formatted_string(locale)
+                )
+            }),
+            analytics.feedback.map_or("-".to_string(), |feedback| {
+                format!(
+                    "{} / {}",
+                    feedback.messages.to_formatted_string(locale),
+                    feedback.corrupted.to_formatted_string(locale)
+                )
+            }),
+            // payload bits per bit sent, retransmissions included.
+            analytics.arq.map_or("-".to_string(), |arq| {
+                format!(
+                    "{} / {:.3}%",
+                    arq.retransmissions.to_formatted_string(locale),
+                    analytics.input_byte_count as f64 / analytics.channel_byte_count as f64 * 100.0
+                )
+            }),
+            analytics.arq.map_or("-".to_string(), |arq| {
+                format!(
+                    "{:.3}",
+                    (arq.frames + arq.retransmissions) as f64 / arq.frames as f64
+                )
+            }),
+            analytics
+                .bandwidth_saved
+                .map_or("-".to_string(), |saved| format!("{:.3}%", saved * 100.0)),
+            rate_switches(analytics, locale),
+            match analytics.header_valid {
+                Some(true) => "valid",
+                Some(false) => "invalid",
+                None => "-",
+            }
+            .to_string(),
+            queues(analytics),
+            analytics.build.to_string(),
+        ];
+        row.extend(metrics.iter().map(|name| {
+            analytics
+                .metrics
+                .iter()
+                .find(|(metric, _)| metric == name)
+                .map_or("-".to_string(), |(_, value)| value.to_string())
+        }));
+        table.add_row(Row::from_iter(row));
     });
     table
 }
 
+// one line per stage, stages working at the same time overlapping.
+fn busy_time(analytics: &Analytics) -> String {
+    if analytics.busy_time.is_empty() {
+        return "-".to_string();
+    }
+    analytics
+        .busy_time
+        .iter()
+        .map(|(stage, busy)| format!("{}: {:.3} ms", stage, busy.as_micros() as f64 / 1000f64))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// one line per queue, named after the stage filling it.
+fn queues(analytics: &Analytics) -> String {
+    if analytics.queues.is_empty() {
+        return "-".to_string();
+    }
+    analytics
+        .queues
+        .iter()
+        .map(|(stage, queue)| {
+            format!(
+                "{}: {} / {:.1} / {}",
+                stage,
+                queue.max_depth,
+                queue.mean_depth(),
+                queue.full
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// one line per layer for coders made of several layers.
+fn bursts(bursts: &Bursts) -> String {
+    match (bursts.mean(), bursts.percentile(0.95), bursts.max()) {
+        (Some(mean), Some(p95), Some(max)) => format!("{:.2} / {} / {} bit", mean, p95, max),
+        _ => "-".to_string(),
+    }
+}
+
+fn gaps(gaps: &Gaps, locale: &Locale) -> String {
+    match (gaps.mean(), gaps.coefficient_of_variation(), gaps.max()) {
+        (Some(mean), Some(cv), Some(max)) => format!(
+            "{} / {:.2} / {} bit",
+            (mean.round() as u64).to_formatted_string(locale),
+            cv,
+            max.to_formatted_string(locale)
+        ),
+        _ => "-".to_string(),
+    }
+}
+
+// the errors on every bit of a byte, and of codewords short enough for a
+// line, most significant bit first. a bit standing out points at a coder
+// handling that position wrong.
+fn bit_positions(analytics: &Analytics) -> String {
+    if analytics.residual_bit_errors == 0 {
+        return "-".to_string();
+    }
+    let mut lines = vec![format!("byte: {}", histogram(&analytics.byte_positions))];
+    if let Some(positions) = analytics
+        .codeword_positions
+        .as_ref()
+        .filter(|positions| positions.len() <= 16)
+    {
+        lines.push(format!("codeword: {}", histogram(positions)));
+    }
+    lines.join("\n")
+}
+
+// the seconds of the media hit and the samples in them.
+fn media_damage(damage: MediaDamage) -> String {
+    let mut lines = vec![format!(
+        "{} of {} s hit",
+        damage.seconds_hit, damage.seconds
+    )];
+    if damage.frames > 0 || damage.audio_samples > 0 {
+        lines.push(format!(
+            "{} frames ({} key), {} audio samples",
+            damage.frames, damage.key_frames, damage.audio_samples
+        ));
+    }
+    if damage.container {
+        lines.push("container hit".to_string());
+    }
+    lines.join("\n")
+}
+
+// the noise in dB below the signal and the samples it is in.
+fn audio_quality(quality: AudioQuality, locale: &Locale) -> String {
+    match quality.snr {
+        Some(snr) => format!(
+            "{:.1} dB\n{} of {} samples hit",
+            snr,
+            quality.samples_hit.to_formatted_string(locale),
+            quality.samples.to_formatted_string(locale)
+        ),
+        None => "no noise".to_string(),
+    }
+}
+
+fn histogram(counts: &[u64]) -> String {
+    counts
+        .iter()
+        .map(|count| count.to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// errors the codewords and frames owned up to against those that went
+// through unnoticed, a line each.
+fn detected_errors(analytics: &Analytics, locale: &Locale) -> String {
+    let mut lines = vec![];
+    if let (Some(stats), Some(silent)) = (analytics.segment_stats, analytics.silent_codewords()) {
+        lines.push(format!(
+            "codewords: {} / {}",
+            stats.uncorrectable.to_formatted_string(locale),
+            silent.to_formatted_string(locale)
+        ));
+    }
+    if let (Some(detected), Some(undetected)) = (
+        analytics.detected_frame_errors,
+        analytics.undetected_frame_errors,
+    ) {
+        lines.push(format!(
+            "frames: {} / {}",
+            detected.to_formatted_string(locale),
+            undetected.to_formatted_string(locale)
+        ));
+    }
+    match lines.is_empty() {
+        true => "-".to_string(),
+        false => lines.join("\n"),
+    }
+}
+
+fn segments(analytics: &Analytics, locale: &Locale) -> String {
+    let format = |stats: &SegmentStats| {
+        format!(
+            "{} / {} / {}",
+            stats.corrected.to_formatted_string(locale),
+            stats.uncorrectable.to_formatted_string(locale),
+            stats.passed.to_formatted_string(locale)
+        )
+    };
+    match (&analytics.segment_stats, analytics.layer_stats.as_slice()) {
+        (_, layers) if layers.len() > 1 => layers
+            .iter()
+            .map(|(layer, stats)| format!("{}: {}", layer, format(stats)))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        (Some(stats), _) => format(stats),
+        (None, _) => "-".to_string(),
+    }
+}
+
+// number of switches, then the frames sent with every coder, one per line.
+fn rate_switches(analytics: &Analytics, locale: &Locale) -> String {
+    match &analytics.rates {
+        Some(rates) => {
+            let mut lines = vec![format!(
+                "{} switches",
+                rates.switches.len().to_formatted_string(locale)
+            )];
+            lines.extend(rates.frames.iter().map(|(coder, frames)| {
+                format!("{}: {} frames", coder, frames.to_formatted_string(locale))
+            }));
+            lines.join("\n")
+        }
+        None => "-".to_string(),
+    }
+}
+
 fn right_align(table: &mut Table) {
     for row in table.row_iter_mut() {
         for cell in row.iter_mut() {
@@ -123,3 +1787,399 @@ fn right_align(table: &mut Table) {
         }
     }
 }
+
+// times in machine readable reports, in seconds.
+pub(crate) mod seconds {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+    use std::time::Duration;
+
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(duration.as_secs_f64())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        from_seconds(f64::deserialize(deserializer)?)
+    }
+
+    fn from_seconds<E: Error>(seconds: f64) -> Result<Duration, E> {
+        Duration::try_from_secs_f64(seconds).map_err(E::custom)
+    }
+
+    pub mod option {
+        use super::*;
+
+        pub fn serialize<S: Serializer>(
+            duration: &Option<Duration>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            match duration {
+                Some(duration) => serializer.serialize_some(&duration.as_secs_f64()),
+                None => serializer.serialize_none(),
+            }
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Option<Duration>, D::Error> {
+            Option::<f64>::deserialize(deserializer)?
+                .map(from_seconds)
+                .transpose()
+        }
+    }
+
+    // the time of every stage by its name.
+    pub mod stages {
+        use super::*;
+        use serde::de::{MapAccess, Visitor};
+        use std::fmt;
+
+        pub fn serialize<S: Serializer>(
+            stages: &[(String, Duration)],
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            serializer.collect_map(
+                stages
+                    .iter()
+                    .map(|(stage, duration)| (stage, duration.as_secs_f64())),
+            )
+        }
+
+        // in the order of the map, the order the stages ran in.
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Vec<(String, Duration)>, D::Error> {
+            struct Stages;
+
+            impl<'de> Visitor<'de> for Stages {
+                type Value = Vec<(String, Duration)>;
+
+                fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                    formatter.write_str("seconds by stage")
+                }
+
+                fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                    let mut stages = vec![];
+                    while let Some((stage, seconds)) = map.next_entry::<String, f64>()? {
+                        stages.push((stage, from_seconds(seconds)?));
+                    }
+                    Ok(stages)
+                }
+            }
+
+            deserializer.deserialize_map(Stages)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        coder::CodewordStats,
+        interleaver::NoInterleaver,
+        metrics,
+        pipeline::{set_files, Files, FILES_LOCK},
+    };
+    use async_std::task;
+    use std::{path::PathBuf, sync::Arc};
+
+    // a name every format has to escape.
+    const NAME: &str = "a|b <c> \"d\", e";
+
+    // passes the data on in codewords of a byte, claiming to have corrected
+    // the codewords 85 and 100 and given up on 86.
+    struct Bytes;
+
+    impl Coder for Bytes {
+        fn name(&self) -> String {
+            NAME.to_string()
+        }
+
+        fn encode(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+            metrics::set("test metric", 2.5);
+            Ok(data.to_vec())
+        }
+
+        fn decode(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+            Ok(data.to_vec())
+        }
+
+        fn codeword_bits(&self) -> Option<usize> {
+            Some(8)
+        }
+
+        fn codeword_stats(&self) -> Option<CodewordStats> {
+            Some(CodewordStats {
+                corrected: vec![85, 100],
+                uncorrectable: vec![86],
+            })
+        }
+
+        fn segment_stats(&self) -> Option<SegmentStats> {
+            Some(SegmentStats {
+                corrected: 2,
+                uncorrectable: 1,
+                passed: 136,
+            })
+        }
+    }
+
+    fn memory(bytes: &[u8]) -> Source {
+        Source::Memory {
+            name: PathBuf::from(NAME),
+            bytes: Arc::from(bytes),
+        }
+    }
+
+    // a run sending the payload, its decoded payload taken to be `decoded`.
+    fn run(payload: &[u8], decoded: &[u8]) -> Analytics {
+        let _lock = FILES_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        set_files(Files::in_memory(NAME, payload.to_vec()));
+        task::block_on(async {
+            let mut channel = Channel::new(0.0, 0.0).with_seed(1);
+            let run_metrics = pipeline::run(&mut Bytes, &NoInterleaver, &mut channel)
+                .await
+                .unwrap();
+            analyze_sources(&channel, run_metrics, &memory(payload), &memory(decoded))
+                .await
+                .unwrap()
+        })
+    }
+
+    // bits 0 and 7 of byte 10 and bit 7 of byte 11 wrong, the last two
+    // bytes missing.
+    fn damaged() -> Analytics {
+        let payload: Vec<u8> = (0..64).collect();
+        let mut decoded = payload[..62].to_vec();
+        decoded[10] ^= 0x81;
+        decoded[11] ^= 0x01;
+        run(&payload, &decoded)
+    }
+
+    // the cells of the row of the run under the names of their columns.
+    fn cells(analytics: &Analytics) -> Vec<(String, String)> {
+        let table = raw_table_from_data(std::slice::from_ref(analytics));
+        let mut rows = table.row_iter().map(|row| {
+            row.iter()
+                .map(|cell| cell.get_content())
+                .collect::<Vec<_>>()
+        });
+        let header = rows.next().unwrap();
+        header.into_iter().zip(rows.next().unwrap()).collect()
+    }
+
+    fn cell(analytics: &Analytics, column: &str) -> String {
+        cells(analytics)
+            .into_iter()
+            .find(|(name, _)| name == column)
+            .unwrap()
+            .1
+    }
+
+    #[test]
+    fn residual_errors_count_every_bit_of_missing_and_extra_bytes() {
+        let analytics = damaged();
+        assert_eq!(analytics.missing_bytes(), 2);
+        assert_eq!(analytics.extra_bytes(), 0);
+        assert_eq!(analytics.residual_bit_errors(), 3 + 16);
+        assert_eq!(cell(&analytics, "Residual Errors"), "19");
+        assert_eq!(cell(&analytics, "Missing / Extra Bytes"), "2 / 0");
+        // two bytes to a block, the missing ones in the last.
+        let mut blocks = vec![0; RESIDUAL_BLOCKS];
+        blocks[5] = 3;
+        blocks[31] = 16;
+        assert_eq!(analytics.residual_blocks(), blocks);
+
+        let payload: Vec<u8> = (0..64).collect();
+        let mut decoded = payload.clone();
+        decoded.extend([0; 3]);
+        let analytics = run(&payload, &decoded);
+        assert_eq!(analytics.missing_bytes(), 0);
+        assert_eq!(analytics.extra_bytes(), 3);
+        assert_eq!(analytics.residual_bit_errors(), 24);
+        assert_eq!(analytics.residual_blocks()[31], 24);
+        assert_eq!(cell(&analytics, "Missing / Extra Bytes"), "0 / 3");
+
+        let analytics = run(&payload, &payload);
+        assert_eq!(analytics.residual_bit_errors(), 0);
+        assert_eq!(cell(&analytics, "Missing / Extra Bytes"), "-");
+        assert_eq!(
+            cell(&analytics, "Residual Errors by Bit of Byte / Codeword"),
+            "-"
+        );
+    }
+
+    #[test]
+    fn residual_errors_are_counted_by_bit_and_burst() {
+        let analytics = damaged();
+        assert_eq!(analytics.byte_positions(), [3, 2, 2, 2, 2, 2, 2, 4]);
+        assert_eq!(
+            cell(&analytics, "Residual Errors by Bit of Byte / Codeword"),
+            "byte: 3 2 2 2 2 2 2 4\ncodeword: 3 2 2 2 2 2 2 4"
+        );
+        // the bits 80, 87 and 95 alone, then the 16 missing ones.
+        let bursts = analytics.residual_bursts();
+        assert_eq!(bursts.count(), 4);
+        assert_eq!(bursts.mean(), Some(19.0 / 4.0));
+        assert_eq!(bursts.max(), Some(16));
+        assert_eq!(bursts.percentile(0.5), Some(1));
+        assert_eq!(bursts.gaps().count(), 3);
+        assert_eq!(bursts.gaps().max(), Some(400));
+        assert_eq!(
+            cell(&analytics, "Burst Length Mean / P95 / Max"),
+            "channel: -\nresidual: 4.75 / 16 / 16 bit"
+        );
+    }
+
+    #[test]
+    fn codewords_are_held_against_what_the_decoder_claimed() {
+        // the payload starts at codeword 75, after the stream header, so
+        // the bytes 10, 11, 62 and 63 are the codewords 85, 86, 137 and 138.
+        let analytics = damaged();
+        assert_eq!(analytics.codeword_error_rate(), Some(4.0 / 64.0));
+        assert_eq!(cell(&analytics, "Codeword Error Rate"), "6.250%");
+        // 85 corrected to the wrong value, 86 given up on, the missing ones
+        // passed unnoticed.
+        assert_eq!(analytics.miscorrected(), Some(1));
+        assert_eq!(analytics.undetected_codewords(), Some(2));
+        assert_eq!(cell(&analytics, "Miscorrected"), "1 (50.000%)");
+        assert_eq!(
+            cell(&analytics, "Detected / Silent Errors"),
+            "codewords: 1 / 3"
+        );
+    }
+
+    #[test]
+    fn gaps_between_the_bursts_are_measured() {
+        // 6, 7 and 400 bits between the bursts.
+        let analytics = damaged();
+        let gaps = analytics.residual_bursts().gaps();
+        assert_eq!(gaps.mean(), Some(413.0 / 3.0));
+        let cv = gaps.coefficient_of_variation().unwrap();
+        assert!((cv - 1.347).abs() < 1e-3, "{}", cv);
+        assert_eq!(
+            cell(&analytics, "Error-Free Gap Mean / CV / Max"),
+            "channel: -\nresidual: 138 / 1.35 / 400 bit"
+        );
+    }
+
+    #[test]
+    fn coding_gain_is_held_against_the_uncoded_run() {
+        // error rates of 1e-2 and 1e-5 need 4.32 and 9.59 dB, the coded
+        // run spending 3.01 dB on sending twice the bits.
+        let mut baseline = damaged();
+        baseline.coder = Uncoded.name();
+        baseline.input_byte_count = 100_000;
+        baseline.channel_byte_count = 100_000;
+        baseline.residual_bit_errors = 8_000;
+        let mut coded = damaged();
+        coded.input_byte_count = 100_000;
+        coded.channel_byte_count = 200_000;
+        coded.residual_bit_errors = 8;
+        let runs = [baseline, coded];
+        let (gain, bound) = runs[1].coding_gain(&runs).unwrap();
+        assert!((gain - 2.254).abs() < 0.01, "{}", gain);
+        assert!(!bound);
+        assert_eq!(runs[0].coding_gain(&runs), None);
+        assert_eq!(runs[1].coding_gain(&runs[1..]), None);
+
+        // no errors at all count as a single one, the gain a lower bound.
+        let mut runs = runs;
+        runs[1].residual_bit_errors = 0;
+        let (zero, bound) = runs[1].coding_gain(&runs).unwrap();
+        assert!(bound);
+        assert!(zero > gain);
+        let report = report(&runs, ReportFormat::Csv).unwrap();
+        assert!(report.contains(&format!(",> {:.2} dB,", zero)));
+    }
+
+    #[test]
+    fn compare_puts_the_runs_of_both_reports_side_by_side() {
+        let directory = tempfile::tempdir().unwrap();
+        let (before, after) = (
+            directory.path().join("before.json"),
+            directory.path().join("after.json"),
+        );
+        let payload: Vec<u8> = (0..64).collect();
+        std::fs::write(&before, to_json(&[damaged()]).unwrap()).unwrap();
+        let mut other = run(&payload, &payload);
+        other.interleaver = "other".to_string();
+        std::fs::write(&after, to_json(&[run(&payload, &payload), other]).unwrap()).unwrap();
+        let table = compare::table(&before, &after).unwrap();
+        let rows: Vec<Vec<String>> = table
+            .row_iter()
+            .map(|row| row.iter().map(|cell| cell.get_content()).collect())
+            .collect();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[1][..2], [NAME, "None"]);
+        assert_eq!(rows[1][4], "19 -> 0");
+        assert_eq!(rows[2][1], "other");
+        assert_eq!(rows[2][4..], ["after only"; 3]);
+
+        std::fs::write(&after, "[{").unwrap();
+        let error = compare::table(&before, &after).unwrap_err();
+        assert!(error.to_string().contains("is no JSON report"));
+    }
+
+    #[test]
+    fn recorded_metrics_get_a_column_of_their_own() {
+        let analytics = damaged();
+        assert_eq!(analytics.metrics(), [("test metric".to_string(), 2.5)]);
+        let cells = cells(&analytics);
+        assert_eq!(cells.last().unwrap(), &("test metric".into(), "2.5".into()));
+    }
+
+    #[test]
+    fn csv_report_quotes_cells_with_separators() {
+        let report = report(&[damaged()], ReportFormat::Csv).unwrap();
+        assert!(report.starts_with("Coder,Interleaver,Payload,E2E Time,"));
+        assert!(report.contains("\"a|b <c> \"\"d\"\", e\""));
+        let mut reader = csv::Reader::from_reader(report.as_bytes());
+        let header = reader.headers().unwrap().clone();
+        let rows: Vec<csv::StringRecord> = reader.records().map(Result::unwrap).collect();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].len(), header.len());
+        assert_eq!(&rows[0][0], NAME);
+        assert_eq!(&header[header.len() - 1], "test metric");
+    }
+
+    #[test]
+    fn json_report_keeps_every_field() {
+        let report = report(&[damaged()], ReportFormat::Json).unwrap();
+        let runs: serde_json::Value = serde_json::from_str(&report).unwrap();
+        let run = &runs.as_array().unwrap()[0];
+        assert_eq!(run["coder"], NAME);
+        assert_eq!(run["residual_bit_errors"], 19);
+        assert_eq!(run["missing_bytes"], 2);
+        assert_eq!(run["extra_bytes"], 0);
+        assert_eq!(run["miscorrected"], 1);
+        assert_eq!(run["metrics"][0][0], "test metric");
+    }
+
+    #[test]
+    fn markdown_report_keeps_every_row_on_its_line() {
+        let report = report(&[damaged()], ReportFormat::Markdown).unwrap();
+        let lines: Vec<&str> = report.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("| Coder | Interleaver | Payload | E2E Time |"));
+        assert!(lines[0].ends_with(" | test metric |"));
+        let columns = lines[0].matches(" | ").count() + 1;
+        assert_eq!(lines[1], format!("|{}", " --: |".repeat(columns)));
+        assert!(lines[2].starts_with("| a\\|b <c> \"d\", e | None | a\\|b <c> \"d\", e |"));
+        assert!(lines[2].contains("channel: -<br>residual: 4.75 / 16 / 16 bit"));
+        assert!(lines[2].ends_with(" | 2.5 |"));
+    }
+
+    #[test]
+    fn html_report_escapes_the_cells() {
+        let report = report(&[damaged()], ReportFormat::Html).unwrap();
+        assert!(report.starts_with("<!DOCTYPE html>"));
+        assert!(report.contains("<tr><th>Coder</th><th>Interleaver</th><th>Payload</th>"));
+        assert!(report.contains("<th>test metric</th></tr>"));
+        assert!(report.contains("<tr><td>a|b &lt;c&gt; &quot;d&quot;, e</td>"));
+        assert!(report.contains("<td>channel: -<br>residual: 4.75 / 16 / 16 bit</td>"));
+        assert!(!report.contains("<c>"));
+        assert!(report.contains("<h2>Residual Bit Error Rate</h2>"));
+        assert!(report.contains("<h2>Overhead</h2>"));
+    }
+}
@@ -0,0 +1,134 @@
+use color_eyre::eyre::{Result, WrapErr};
+use prettytable::{Cell, Row, Table};
+use serde::Deserialize;
+use std::path::Path;
+
+use crate::channel::Model;
+
+// how much slower a run may get before it counts as a regression, times
+// being noisy.
+const SLOWER: f64 = 0.1;
+
+// the fields of a run in a JSON report the comparison looks at.
+#[derive(Deserialize)]
+struct Run {
+    coder: String,
+    interleaver: String,
+    payload: String,
+    residual_bit_errors: u64,
+    input_byte_count: u64,
+    channel_byte_count: u64,
+    end_to_end_time: f64,
+    channel: Channel,
+}
+
+#[derive(Deserialize)]
+struct Channel {
+    #[serde(flatten)]
+    model: Model,
+    soft_output: bool,
+    erasures: bool,
+}
+
+impl Run {
+    fn key(&self) -> [String; 4] {
+        let channel = &self.channel;
+        let kind = if channel.erasures {
+            ", erasures"
+        } else if channel.soft_output {
+            ", soft"
+        } else {
+            ""
+        };
+        [
+            self.coder.clone(),
+            self.interleaver.clone(),
+            self.payload.clone(),
+            format!("{}{}", channel.model, kind),
+        ]
+    }
+
+    fn overhead(&self) -> f64 {
+        self.channel_byte_count as f64 / (self.input_byte_count as f64).max(1.0) - 1.0
+    }
+}
+
+/// A row per run of either report, the runs of both side by side.
+pub(super) fn table(before: &Path, after: &Path) -> Result<Table> {
+    let before = load(before)?;
+    let after = load(after)?;
+    let mut table = Table::new();
+    table.add_row(Row::from_iter(vec![
+        "Coder",
+        "Interleaver",
+        "Payload",
+        "Channel",
+        "Residual Errors",
+        "Overhead",
+        "E2E Time",
+    ]));
+    let mut keys: Vec<[String; 4]> = vec![];
+    for run in before.iter().chain(&after) {
+        if !keys.contains(&run.key()) {
+            keys.push(run.key());
+        }
+    }
+    for key in keys {
+        let before = before.iter().find(|run| run.key() == key);
+        let after = after.iter().find(|run| run.key() == key);
+        let mut row = Row::from_iter(key.iter());
+        match (before, after) {
+            (Some(before), Some(after)) => {
+                row.add_cell(delta(
+                    before.residual_bit_errors.to_string(),
+                    after.residual_bit_errors.to_string(),
+                    after.residual_bit_errors as f64 - before.residual_bit_errors as f64,
+                    0.0,
+                ));
+                row.add_cell(delta(
+                    format!("{:.1}%", before.overhead() * 100.0),
+                    format!("{:.1}%", after.overhead() * 100.0),
+                    after.overhead() - before.overhead(),
+                    0.0,
+                ));
+                row.add_cell(delta(
+                    format!("{:.3} s", before.end_to_end_time),
+                    format!("{:.3} s", after.end_to_end_time),
+                    after.end_to_end_time / before.end_to_end_time - 1.0,
+                    SLOWER,
+                ));
+            }
+            // a run only one of the reports has.
+            (before, _) => {
+                let side = match before {
+                    Some(_) => "before only",
+                    None => "after only",
+                };
+                for _ in 0..3 {
+                    row.add_cell(Cell::new(side));
+                }
+            }
+        }
+        table.add_row(row);
+    }
+    Ok(table)
+}
+
+fn load(path: &Path) -> Result<Vec<Run>> {
+    let json = std::fs::read_to_string(path)
+        .wrap_err_with(|| format!("cannot read the report {}", path.display()))?;
+    serde_json::from_str(&json).wrap_err_with(|| format!("{} is no JSON report", path.display()))
+}
+
+// before and after, red if it got worse by more than the tolerance, green if
+// it got better by more.
+fn delta(before: String, after: String, change: f64, tolerance: f64) -> Cell {
+    let cell = Cell::new(&format!("{} -> {}", before, after));
+    if change > tolerance {
+        cell.style_spec("Fr")
+    } else if change < -tolerance {
+        cell.style_spec("Fg")
+    } else {
+        cell
+    }
+}
@@ -0,0 +1,119 @@
+use prettytable::Table;
+use std::fmt::Write;
+
+use super::Analytics;
+
+// room for the name of a run left of its bar, the bars and their value.
+const LABEL_WIDTH: usize = 300;
+const BAR_WIDTH: usize = 480;
+const VALUE_WIDTH: usize = 80;
+const BAR_HEIGHT: usize = 18;
+// the bit error rate of the shortest bar, the scale being logarithmic. runs
+// without residual errors get no bar.
+const LOWEST_ERROR_RATE: f64 = 1e-8;
+
+const STYLE: &str = "body { font-family: sans-serif; margin: 2em; }
+table { border-collapse: collapse; font-size: 0.8em; }
+th, td { border: 1px solid #ccc; padding: 0.2em 0.5em; text-align: right; vertical-align: top; }
+th { background: #eee; }
+svg text { font-size: 12px; }";
+
+/// A page of its own with the charts above the table, no files besides it.
+pub(super) fn page(table: &Table, charts: &[String]) -> String {
+    let mut page = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>mmcp report</title>\n<style>\n{}\n</style>\n</head>\n<body>\n",
+        STYLE
+    );
+    for chart in charts {
+        page.push_str(chart);
+    }
+    page.push_str("<h2>Runs</h2>\n<table>\n");
+    for (index, row) in table.row_iter().enumerate() {
+        let tag = if index == 0 { "th" } else { "td" };
+        page.push_str("<tr>");
+        for cell in row.iter() {
+            let content = escape(&cell.get_content()).replace('\n', "<br>");
+            write!(page, "<{tag}>{}</{tag}>", content).unwrap();
+        }
+        page.push_str("</tr>\n");
+    }
+    page.push_str("</table>\n</body>\n</html>\n");
+    page
+}
+
+/// The residual bit errors per payload bit of every run, on a logarithmic
+/// scale.
+pub(super) fn error_rate_chart(analytics: &[Analytics]) -> String {
+    let bars = analytics.iter().map(|run| {
+        let rate = run.residual_bit_errors as f64 / (run.input_byte_count as f64 * 8.0).max(1.0);
+        let share = match rate > 0.0 {
+            true => (rate / LOWEST_ERROR_RATE).log10() / -LOWEST_ERROR_RATE.log10(),
+            false => 0.0,
+        };
+        (label(run), share, format!("{:.2e}", rate))
+    });
+    chart("Residual Bit Error Rate", bars.collect())
+}
+
+/// The channel bits sent on top of the payload bits by every run, relative
+/// to the payload.
+pub(super) fn overhead_chart(analytics: &[Analytics]) -> String {
+    let overheads: Vec<f64> = analytics
+        .iter()
+        .map(|run| run.channel_byte_count as f64 / (run.input_byte_count as f64).max(1.0) - 1.0)
+        .collect();
+    let most = overheads.iter().copied().fold(0.0, f64::max);
+    let bars = analytics.iter().zip(overheads).map(|(run, overhead)| {
+        let share = match most > 0.0 {
+            true => overhead / most,
+            false => 0.0,
+        };
+        (label(run), share, format!("{:.1}%", overhead * 100.0))
+    });
+    chart("Overhead", bars.collect())
+}
+
+// a horizontal bar per run, the share of the full width and the value shown.
+fn chart(title: &str, bars: Vec<(String, f64, String)>) -> String {
+    let width = LABEL_WIDTH + BAR_WIDTH + VALUE_WIDTH;
+    let mut svg = format!(
+        "<h2>{}</h2>\n<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\">\n",
+        title,
+        width,
+        bars.len() * BAR_HEIGHT
+    );
+    for (index, (label, share, value)) in bars.into_iter().enumerate() {
+        let y = index * BAR_HEIGHT;
+        let bar = (share.clamp(0.0, 1.0) * BAR_WIDTH as f64).round() as usize;
+        write!(
+            svg,
+            "<text x=\"{}\" y=\"{}\" text-anchor=\"end\">{}</text>\n\
+             <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"#4a7ebb\"/>\n\
+             <text x=\"{}\" y=\"{}\">{}</text>\n",
+            LABEL_WIDTH - 6,
+            y + BAR_HEIGHT - 5,
+            escape(&label),
+            LABEL_WIDTH,
+            y + 2,
+            bar,
+            BAR_HEIGHT - 4,
+            LABEL_WIDTH + bar + 4,
+            y + BAR_HEIGHT - 5,
+            value
+        )
+        .unwrap();
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn label(run: &Analytics) -> String {
+    format!("{}, {}", run.coder, run.channel_name())
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
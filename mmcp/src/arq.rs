@@ -0,0 +1,586 @@
+use color_eyre::eyre::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    channel::Channel,
+    coder::Coder,
+    feedback::{Duplex, Feedback},
+    framing::crc32,
+    puncturing::PuncturePattern,
+};
+
+const CRC_BYTES: usize = 4;
+// sequence number and payload length in front of every frame.
+const FRAME_HEADER_BYTES: usize = 6;
+const ACK: u8 = 0x06;
+
+/// Sends the payload in CRC-checked frames of `frame_bytes` bytes through the
+/// wrapped coder, one at a time. The receiver acknowledges every intact
+/// frame over the feedback channel, the sender retransmits a frame until it
+/// hears the acknowledgement or gives up after `max_attempts`. A frame given
+/// up on is delivered as last received.
+pub struct StopAndWait {
+    frame_bytes: usize,
+    max_attempts: usize,
+    inner: Box<dyn Coder>,
+    stats: ArqStats,
+}
+
+impl StopAndWait {
+    pub fn new(frame_bytes: usize, max_attempts: usize, inner: Box<dyn Coder>) -> Self {
+        assert!(
+            frame_bytes > 0 && frame_bytes <= u16::MAX as usize,
+            "frames carry between 1 and 65535 bytes"
+        );
+        assert!(max_attempts > 0, "a frame is sent at least once");
+        Self {
+            frame_bytes,
+            max_attempts,
+            inner,
+            stats: ArqStats::default(),
+        }
+    }
+}
+
+impl Duplex for StopAndWait {
+    fn name(&self) -> String {
+        format!("{} + Stop-and-wait/{}", self.inner.name(), self.frame_bytes)
+    }
+
+    fn transfer(
+        &mut self,
+        data: &[u8],
+        forward: &mut Channel,
+        feedback: &mut Feedback,
+    ) -> Result<Vec<u8>> {
+        self.stats = ArqStats::default();
+        let mut delivered = Vec::with_capacity(data.len());
+        // next sequence number the receiver expects.
+        let mut expected = 0;
+        for (seq, payload) in data.chunks(self.frame_bytes).enumerate() {
+            let seq = seq as u32;
+            let encoded = self.inner.encode(&frame(seq, payload))?;
+            self.stats.frames += 1;
+            let mut acknowledged_by_receiver = false;
+            let mut last = vec![];
+            for attempt in 0..self.max_attempts {
+                self.stats.retransmissions += (attempt > 0) as u32;
+                let received = self.inner.decode(&forward.send(&encoded))?;
+                match unframe(&received) {
+                    Some((number, intact)) => {
+                        if number == expected {
+                            delivered.extend_from_slice(intact);
+                            expected += 1;
+                        }
+                        // duplicates of an acknowledged frame are acknowledged again.
+                        if acknowledged(&feedback.send(&ack(number))) == Some(seq) {
+                            acknowledged_by_receiver = true;
+                            break;
+                        }
+                    }
+                    None => last = received,
+                }
+            }
+            self.stats.failed += !acknowledged_by_receiver as u32;
+            // the receiver never got the frame intact, it keeps the last copy.
+            if expected == seq {
+                delivered.extend(received_payload(&last, payload.len()));
+                expected += 1;
+            }
+        }
+        Ok(delivered)
+    }
+
+    fn arq_stats(&self) -> Option<ArqStats> {
+        Some(self.stats)
+    }
+}
+
+/// Sends up to `window` CRC-checked frames before it hears back. The
+/// receiver only takes frames in order and acknowledges each intact frame
+/// with the sequence number it expects next. Acknowledgements reach the
+/// sender once it sent its whole window, the round trip of the link, and it
+/// goes back to the first unacknowledged frame. A frame the window could not
+/// move past in `max_attempts` rounds is given up on and delivered as last
+/// received.
+pub struct GoBackN {
+    frame_bytes: usize,
+    window: usize,
+    max_attempts: usize,
+    inner: Box<dyn Coder>,
+    stats: ArqStats,
+}
+
+impl GoBackN {
+    pub fn new(
+        frame_bytes: usize,
+        window: usize,
+        max_attempts: usize,
+        inner: Box<dyn Coder>,
+    ) -> Self {
+        assert!(
+            frame_bytes > 0 && frame_bytes <= u16::MAX as usize,
+            "frames carry between 1 and 65535 bytes"
+        );
+        assert!(window > 0, "the window holds at least one frame");
+        assert!(max_attempts > 0, "a frame is sent at least once");
+        Self {
+            frame_bytes,
+            window,
+            max_attempts,
+            inner,
+            stats: ArqStats::default(),
+        }
+    }
+}
+
+impl Duplex for GoBackN {
+    fn name(&self) -> String {
+        format!(
+            "{} + Go-back-{}/{}",
+            self.inner.name(),
+            self.window,
+            self.frame_bytes
+        )
+    }
+
+    fn transfer(
+        &mut self,
+        data: &[u8],
+        forward: &mut Channel,
+        feedback: &mut Feedback,
+    ) -> Result<Vec<u8>> {
+        let payloads = data.chunks(self.frame_bytes).collect::<Vec<_>>();
+        let encoded = payloads
+            .iter()
+            .enumerate()
+            .map(|(seq, payload)| self.inner.encode(&frame(seq as u32, payload)))
+            .collect::<Result<Vec<_>>>()?;
+        self.stats = ArqStats {
+            frames: payloads.len() as u32,
+            ..ArqStats::default()
+        };
+        let mut sent = vec![false; payloads.len()];
+        let mut delivered = Vec::with_capacity(data.len());
+        // first unacknowledged frame, rounds it held the window back and the
+        // next frame the receiver expects with its last damaged copy.
+        let (mut base, mut attempts) = (0, 0);
+        let (mut expected, mut last) = (0, vec![]);
+        while base < payloads.len() {
+            let end = (base + self.window).min(payloads.len());
+            let mut acknowledged_up_to = base;
+            for seq in base..end {
+                self.stats.retransmissions += sent[seq] as u32;
+                sent[seq] = true;
+                let received = self.inner.decode(&forward.send(&encoded[seq]))?;
+                match unframe(&received) {
+                    Some((number, intact)) => {
+                        if number as usize == expected {
+                            delivered.extend_from_slice(intact);
+                            expected += 1;
+                            last.clear();
+                        }
+                        if let Some(next) = acknowledged(&feedback.send(&ack(expected as u32))) {
+                            acknowledged_up_to = acknowledged_up_to.max((next as usize).min(end));
+                        }
+                    }
+                    None if seq == expected => last = received,
+                    None => {}
+                }
+            }
+            if acknowledged_up_to > base {
+                (base, attempts) = (acknowledged_up_to, 0);
+                continue;
+            }
+            attempts += 1;
+            if attempts == self.max_attempts {
+                self.stats.failed += 1;
+                if expected == base {
+                    delivered.extend(received_payload(&last, payloads[base].len()));
+                    expected += 1;
+                    last.clear();
+                }
+                (base, attempts) = (base + 1, 0);
+            }
+        }
+        Ok(delivered)
+    }
+
+    fn arq_stats(&self) -> Option<ArqStats> {
+        Some(self.stats)
+    }
+}
+
+/// Sends up to `window` CRC-checked frames before it hears back, like
+/// [`GoBackN`], but the receiver buffers intact frames ahead of a missing
+/// one and acknowledges each frame on its own, so the sender only repeats
+/// the frames that did not arrive. A frame sent `max_attempts` times without
+/// acknowledgement is given up on and delivered as last received.
+pub struct SelectiveRepeat {
+    frame_bytes: usize,
+    window: usize,
+    max_attempts: usize,
+    inner: Box<dyn Coder>,
+    stats: ArqStats,
+}
+
+impl SelectiveRepeat {
+    pub fn new(
+        frame_bytes: usize,
+        window: usize,
+        max_attempts: usize,
+        inner: Box<dyn Coder>,
+    ) -> Self {
+        assert!(
+            frame_bytes > 0 && frame_bytes <= u16::MAX as usize,
+            "frames carry between 1 and 65535 bytes"
+        );
+        assert!(window > 0, "the window holds at least one frame");
+        assert!(max_attempts > 0, "a frame is sent at least once");
+        Self {
+            frame_bytes,
+            window,
+            max_attempts,
+            inner,
+            stats: ArqStats::default(),
+        }
+    }
+}
+
+impl Duplex for SelectiveRepeat {
+    fn name(&self) -> String {
+        format!(
+            "{} + Selective-repeat-{}/{}",
+            self.inner.name(),
+            self.window,
+            self.frame_bytes
+        )
+    }
+
+    fn transfer(
+        &mut self,
+        data: &[u8],
+        forward: &mut Channel,
+        feedback: &mut Feedback,
+    ) -> Result<Vec<u8>> {
+        let payloads = data.chunks(self.frame_bytes).collect::<Vec<_>>();
+        let encoded = payloads
+            .iter()
+            .enumerate()
+            .map(|(seq, payload)| self.inner.encode(&frame(seq as u32, payload)))
+            .collect::<Result<Vec<_>>>()?;
+        self.stats = ArqStats {
+            frames: payloads.len() as u32,
+            ..ArqStats::default()
+        };
+        // sender side: sends per frame and whether it is done with it.
+        let mut sent = vec![0; payloads.len()];
+        let mut done = vec![false; payloads.len()];
+        // receiver side: the buffered frames and last damaged copies.
+        let mut buffer: Vec<Option<Vec<u8>>> = vec![None; payloads.len()];
+        let mut damaged = vec![vec![]; payloads.len()];
+        let mut delivered = Vec::with_capacity(data.len());
+        let (mut base, mut expected) = (0, 0);
+        while base < payloads.len() {
+            let end = (base + self.window).min(payloads.len());
+            for seq in base..end {
+                if done[seq] {
+                    continue;
+                }
+                self.stats.retransmissions += (sent[seq] > 0) as u32;
+                sent[seq] += 1;
+                let received = self.inner.decode(&forward.send(&encoded[seq]))?;
+                match unframe(&received) {
+                    Some((number, intact)) => {
+                        let number = number as usize;
+                        // frames beyond the receive window are dropped unacknowledged.
+                        if number >= expected + self.window || number >= payloads.len() {
+                            continue;
+                        }
+                        if number >= expected {
+                            buffer[number] = Some(intact.to_vec());
+                        }
+                        if acknowledged(&feedback.send(&ack(number as u32))) == Some(seq as u32) {
+                            done[seq] = true;
+                        }
+                    }
+                    None => damaged[seq] = received,
+                }
+                if !done[seq] && sent[seq] == self.max_attempts {
+                    self.stats.failed += 1;
+                    done[seq] = true;
+                }
+            }
+            // the receiver hands on every frame in order it has, or gave up on.
+            while expected < payloads.len() && (buffer[expected].is_some() || done[expected]) {
+                match buffer[expected].take() {
+                    Some(payload) => delivered.extend(payload),
+                    None => delivered.extend(received_payload(
+                        &damaged[expected],
+                        payloads[expected].len(),
+                    )),
+                }
+                damaged[expected].clear();
+                expected += 1;
+            }
+            while base < payloads.len() && done[base] {
+                base += 1;
+            }
+        }
+        Ok(delivered)
+    }
+
+    fn arq_stats(&self) -> Option<ArqStats> {
+        Some(self.stats)
+    }
+}
+
+/// Type-II hybrid ARQ: every CRC-checked frame is encoded by the wrapped
+/// coder and sent one increment of the puncture pattern at a time. On a
+/// failed frame the sender adds the next increment instead of repeating the
+/// frame, the receiver combines the ratios of everything received so far
+/// and decodes again. Once every increment was sent they are repeated, up to
+/// `max_transmissions` in all, and a frame still failing is delivered as
+/// decoded last.
+pub struct HybridArq {
+    frame_bytes: usize,
+    pattern: PuncturePattern,
+    max_transmissions: usize,
+    inner: Box<dyn Coder>,
+    stats: ArqStats,
+}
+
+impl HybridArq {
+    pub fn new(
+        frame_bytes: usize,
+        pattern: PuncturePattern,
+        max_transmissions: usize,
+        inner: Box<dyn Coder>,
+    ) -> Self {
+        assert!(
+            frame_bytes > 0 && frame_bytes <= u16::MAX as usize,
+            "frames carry between 1 and 65535 bytes"
+        );
+        assert!(max_transmissions > 0, "a frame is sent at least once");
+        Self {
+            frame_bytes,
+            pattern,
+            max_transmissions,
+            inner,
+            stats: ArqStats::default(),
+        }
+    }
+}
+
+impl Duplex for HybridArq {
+    fn name(&self) -> String {
+        format!(
+            "{} + HARQ-IR {}x/{}",
+            self.inner.name(),
+            self.pattern.increments(),
+            self.frame_bytes
+        )
+    }
+
+    fn transfer(
+        &mut self,
+        data: &[u8],
+        forward: &mut Channel,
+        feedback: &mut Feedback,
+    ) -> Result<Vec<u8>> {
+        self.stats = ArqStats::default();
+        let mut delivered = Vec::with_capacity(data.len());
+        let mut expected = 0;
+        for (seq, payload) in data.chunks(self.frame_bytes).enumerate() {
+            let seq = seq as u32;
+            let encoded = self.inner.encode(&frame(seq, payload))?;
+            self.stats.frames += 1;
+            let mut llrs = vec![0.0; 8 * encoded.len()];
+            let mut last = vec![];
+            for transmission in 0..self.max_transmissions {
+                self.stats.retransmissions += (transmission > 0) as u32;
+                let increment = transmission % self.pattern.increments();
+                let received = forward.process_soft(&self.pattern.puncture(&encoded, increment));
+                self.pattern.depuncture(&mut llrs, &received, increment);
+                let decoded = self.inner.decode_soft(&llrs)?;
+                match unframe(&decoded) {
+                    Some((number, intact)) => {
+                        if number == expected {
+                            delivered.extend_from_slice(intact);
+                            expected += 1;
+                        }
+                        if acknowledged(&feedback.send(&ack(number))) == Some(seq) {
+                            break;
+                        }
+                    }
+                    None => last = decoded,
+                }
+                self.stats.failed += (transmission + 1 == self.max_transmissions) as u32;
+            }
+            if expected == seq {
+                delivered.extend(received_payload(&last, payload.len()));
+                expected += 1;
+            }
+        }
+        Ok(delivered)
+    }
+
+    fn arq_stats(&self) -> Option<ArqStats> {
+        Some(self.stats)
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+pub struct ArqStats {
+    /// Frames the payload was split into.
+    pub frames: u32,
+    /// Frames, or increments of them, sent after their first transmission.
+    pub retransmissions: u32,
+    /// Frames the sender gave up on.
+    pub failed: u32,
+}
+
+// sequence number, payload length, payload and a CRC-32 over all of them.
+pub(super) fn frame(seq: u32, payload: &[u8]) -> Vec<u8> {
+    let mut frame = seq.to_be_bytes().to_vec();
+    frame.extend_from_slice(&(payload.len() as u16).to_be_bytes());
+    frame.extend_from_slice(payload);
+    frame.extend_from_slice(&crc32(&frame).to_be_bytes());
+    frame
+}
+
+// sequence number and payload of a decoded frame whose CRC matches, bytes
+// the coder appended behind it are ignored.
+pub(super) fn unframe(data: &[u8]) -> Option<(u32, &[u8])> {
+    if data.len() < FRAME_HEADER_BYTES + CRC_BYTES {
+        return None;
+    }
+    let len = u16::from_be_bytes([data[4], data[5]]) as usize;
+    let end = FRAME_HEADER_BYTES + len;
+    if data.len() < end + CRC_BYTES
+        || crc32(&data[..end]).to_be_bytes() != data[end..end + CRC_BYTES]
+    {
+        return None;
+    }
+    let seq = u32::from_be_bytes(data[..4].try_into().unwrap());
+    Some((seq, &data[FRAME_HEADER_BYTES..end]))
+}
+
+// payload of a frame that failed its CRC, cut or padded to the length sent.
+pub(super) fn received_payload(data: &[u8], len: usize) -> Vec<u8> {
+    let mut payload = data
+        .get(FRAME_HEADER_BYTES..)
+        .unwrap_or_default()
+        .iter()
+        .copied()
+        .take(len)
+        .collect::<Vec<_>>();
+    payload.resize(len, 0);
+    payload
+}
+
+// acknowledges a frame by its sequence number, or for go-back-n every frame
+// before the given one.
+fn ack(seq: u32) -> Vec<u8> {
+    let mut ack = vec![ACK];
+    ack.extend_from_slice(&seq.to_be_bytes());
+    ack.extend_from_slice(&crc32(&ack).to_be_bytes());
+    ack
+}
+
+// sequence number of an intact acknowledgement.
+fn acknowledged(message: &[u8]) -> Option<u32> {
+    let (ack, crc) = message.split_at_checked(5)?;
+    (ack[0] == ACK && crc32(ack).to_be_bytes() == crc)
+        .then(|| u32::from_be_bytes(ack[1..].try_into().unwrap()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coder::Uncoded;
+
+    const FRAME_BYTES: usize = 16;
+    const ATTEMPTS: usize = 3;
+
+    fn stages() -> Vec<Box<dyn Duplex>> {
+        vec![
+            Box::new(StopAndWait::new(FRAME_BYTES, ATTEMPTS, Box::new(Uncoded))),
+            Box::new(GoBackN::new(FRAME_BYTES, 4, ATTEMPTS, Box::new(Uncoded))),
+            Box::new(SelectiveRepeat::new(
+                FRAME_BYTES,
+                4,
+                ATTEMPTS,
+                Box::new(Uncoded),
+            )),
+            Box::new(HybridArq::new(
+                FRAME_BYTES,
+                PuncturePattern::new(8, vec![vec![0, 2, 4, 6], vec![1, 3, 5, 7]]),
+                ATTEMPTS,
+                Box::new(Uncoded),
+            )),
+        ]
+    }
+
+    fn payload() -> Vec<u8> {
+        (0..10 * FRAME_BYTES as u32 + 5)
+            .map(|i| (i * 7) as u8)
+            .collect()
+    }
+
+    #[test]
+    fn clean_links_send_every_frame_once_or_once_per_increment() {
+        let data = payload();
+        for mut stage in stages() {
+            let mut forward = Channel::new(0.0, 0.0).with_seed(1);
+            let delivered = stage
+                .transfer(&data, &mut forward, &mut Feedback::perfect())
+                .unwrap();
+            assert_eq!(delivered, data, "{}", stage.name());
+            let stats = stage.arq_stats().unwrap();
+            assert_eq!(stats.frames, 11, "{}", stage.name());
+            // the first increment leaves half the bits of a frame erased,
+            // the second one completes it.
+            let increments = if stage.name().contains("HARQ") { 1 } else { 0 };
+            assert_eq!(stats.retransmissions, 11 * increments, "{}", stage.name());
+            assert_eq!(stats.failed, 0, "{}", stage.name());
+        }
+    }
+
+    #[test]
+    fn frames_are_given_up_on_after_the_last_attempt() {
+        let data = payload();
+        for mut stage in stages() {
+            // every other bit flipped, no frame ever passes its CRC.
+            let mut forward = Channel::gilbert_elliott(0.0, 1.0, 0.5, 0.5).with_seed(1);
+            let delivered = stage
+                .transfer(&data, &mut forward, &mut Feedback::perfect())
+                .unwrap();
+            assert_eq!(delivered.len(), data.len(), "{}", stage.name());
+            let stats = stage.arq_stats().unwrap();
+            assert_eq!(stats.failed, stats.frames, "{}", stage.name());
+            // go-back-n also repeats the frames behind a failing one.
+            if stage.name().contains("Go-back") {
+                assert!(stats.retransmissions >= stats.frames * (ATTEMPTS as u32 - 1));
+            } else {
+                assert_eq!(
+                    stats.retransmissions,
+                    stats.frames * (ATTEMPTS as u32 - 1),
+                    "{}",
+                    stage.name()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn frames_only_unframe_intact() {
+        let framed = frame(7, b"payload");
+        assert_eq!(unframe(&framed), Some((7, &b"payload"[..])));
+        let mut damaged = framed.clone();
+        damaged[8] ^= 1;
+        assert_eq!(unframe(&damaged), None);
+        assert_eq!(received_payload(&damaged, 7)[2], b'y' ^ 1);
+        assert_eq!(acknowledged(&ack(3)), Some(3));
+    }
+}
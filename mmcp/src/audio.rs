@@ -0,0 +1,282 @@
+use serde::{Deserialize, Serialize};
+
+// format tags of the fmt chunk, the extensible one naming the real one in
+// its subformat.
+const PCM: u16 = 1;
+const FLOAT: u16 = 3;
+const EXTENSIBLE: u16 = 0xfffe;
+
+/// The PCM samples of a WAV file, by where they lie in the file. Tells how
+/// loud the noise the residual errors added to the audio is.
+pub struct Wav {
+    // where the data chunk starts and how long it is, whole frames only.
+    data: std::ops::Range<u64>,
+    format: Format,
+    bytes_per_sample: usize,
+}
+
+#[derive(Clone, Copy)]
+enum Format {
+    Integer,
+    Float,
+}
+
+/// How the decoded audio of a run compares to the original.
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+pub struct AudioQuality {
+    /// Signal to noise ratio in dB, the noise being the difference of the
+    /// decoded samples to the original ones. None without any difference.
+    pub snr: Option<f64>,
+    /// Samples decoded wrong or missing, out of all of them.
+    pub samples_hit: u64,
+    pub samples: u64,
+}
+
+impl Wav {
+    /// The samples of the file starting with these bytes, None if it is no
+    /// WAV file of PCM or float samples or its header does not fit in them.
+    pub fn parse(head: &[u8]) -> Option<Self> {
+        if head.get(..4)? != b"RIFF" || head.get(8..12)? != b"WAVE" {
+            return None;
+        }
+        let mut fmt = None;
+        let mut offset = 12;
+        while let Some(header) = head.get(offset..offset + 8) {
+            let size = u32::from_le_bytes(header[4..].try_into().unwrap()) as usize;
+            let start = offset + 8;
+            match &header[..4] {
+                b"fmt " => fmt = Some(head.get(start..start + size)?),
+                b"data" => {
+                    let (format, bytes_per_sample, block) = format(fmt?)?;
+                    let (start, size) = (start as u64, size as u64);
+                    return Some(Self {
+                        data: start..start + size - size % block as u64,
+                        format,
+                        bytes_per_sample,
+                    });
+                }
+                _ => {}
+            }
+            // chunks are padded to an even length.
+            offset = start + size + size % 2;
+        }
+        None
+    }
+
+    // the sample scaled to between -1 and 1.
+    fn value(&self, sample: &[u8]) -> f64 {
+        match (self.format, sample.len()) {
+            (Format::Float, 4) => f32::from_le_bytes(sample.try_into().unwrap()) as f64,
+            (Format::Float, _) => f64::from_le_bytes(sample.try_into().unwrap()),
+            // eight bits are unsigned around 128.
+            (Format::Integer, 1) => (sample[0] as f64 - 128.0) / 128.0,
+            (Format::Integer, bytes) => {
+                // in the most significant bytes, the sign bit on top.
+                let mut value = [0; 8];
+                value[8 - bytes..].copy_from_slice(sample);
+                i64::from_le_bytes(value) as f64 / -(i64::MIN as f64)
+            }
+        }
+    }
+}
+
+/// Compares the samples of a WAV payload with the decoded ones, fed both a
+/// piece at a time from the start of the file.
+pub struct Meter {
+    wav: Wav,
+    // where the bytes held back start in the file.
+    offset: u64,
+    // bytes of a sample not all of which came yet.
+    payload: Vec<u8>,
+    decoded: Vec<u8>,
+    signal: f64,
+    noise: f64,
+    quality: AudioQuality,
+}
+
+impl Meter {
+    pub fn new(wav: Wav) -> Self {
+        Self {
+            wav,
+            offset: 0,
+            payload: vec![],
+            decoded: vec![],
+            signal: 0.0,
+            noise: 0.0,
+            quality: AudioQuality::default(),
+        }
+    }
+
+    /// The next bytes of the payload and those decoded in their place, fewer
+    /// of them once the decoded payload ended.
+    pub fn feed(&mut self, payload: &[u8], decoded: &[u8]) {
+        let bytes = self.wav.bytes_per_sample;
+        self.payload.extend_from_slice(payload);
+        self.decoded.extend_from_slice(decoded);
+        // the bytes before the samples, and those after them.
+        let skip = self.wav.data.start.saturating_sub(self.offset);
+        let mut start = (skip as usize).min(self.payload.len());
+        let mut offset = self.offset + start as u64;
+        while start + bytes <= self.payload.len() && offset + bytes as u64 <= self.wav.data.end {
+            let original = &self.payload[start..start + bytes];
+            let decoded = self.decoded.get(start..start + bytes);
+            let value = self.wav.value(original);
+            self.signal += value * value;
+            self.quality.samples += 1;
+            if decoded != Some(original) {
+                self.quality.samples_hit += 1;
+                let error = value - decoded.map_or(0.0, |decoded| self.wav.value(decoded));
+                // a float decoded into a NaN or infinity counts as full scale.
+                self.noise += if error.is_finite() {
+                    error * error
+                } else {
+                    1.0
+                };
+            }
+            start += bytes;
+            offset += bytes as u64;
+        }
+        if offset >= self.wav.data.end {
+            start = self.payload.len();
+        }
+        self.payload.drain(..start);
+        self.decoded.drain(..start.min(self.decoded.len()));
+        self.offset += start as u64;
+    }
+
+    /// The decoded audio against the original, missing samples silent.
+    pub fn finish(self) -> AudioQuality {
+        AudioQuality {
+            snr: (self.noise > 0.0).then(|| 10.0 * (self.signal / self.noise).log10()),
+            ..self.quality
+        }
+    }
+}
+
+// the format of the samples, their bytes and those of a frame of all
+// channels.
+fn format(fmt: &[u8]) -> Option<(Format, usize, usize)> {
+    let field = |at: usize| Some(u16::from_le_bytes(fmt.get(at..at + 2)?.try_into().ok()?));
+    let tag = match field(0)? {
+        EXTENSIBLE => field(24)?,
+        tag => tag,
+    };
+    let block = field(12)? as usize;
+    let bytes = field(14)?.div_ceil(8) as usize;
+    let format = match (tag, bytes) {
+        (PCM, 1..=4) => Format::Integer,
+        (FLOAT, 4 | 8) => Format::Float,
+        _ => return None,
+    };
+    (block > 0 && block.is_multiple_of(bytes)).then_some((format, bytes, block))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{pipeline::Source, residuals::Residuals};
+    use async_std::task;
+    use std::{path::PathBuf, sync::Arc};
+
+    // a mono WAV file of 16 bit samples with a chunk the parser skips.
+    fn wav(samples: &[i16]) -> Vec<u8> {
+        let data: Vec<u8> = samples
+            .iter()
+            .flat_map(|sample| sample.to_le_bytes())
+            .collect();
+        let mut fmt = vec![];
+        fmt.extend(PCM.to_le_bytes());
+        fmt.extend(1u16.to_le_bytes());
+        fmt.extend(8000u32.to_le_bytes());
+        fmt.extend(16000u32.to_le_bytes());
+        fmt.extend(2u16.to_le_bytes());
+        fmt.extend(16u16.to_le_bytes());
+        let mut wav = b"RIFF\0\0\0\0WAVE".to_vec();
+        for (kind, chunk) in [(b"fmt ", &fmt[..]), (b"LIST", b"odd"), (b"data", &data)] {
+            wav.extend(kind);
+            wav.extend((chunk.len() as u32).to_le_bytes());
+            wav.extend(chunk);
+            if chunk.len() % 2 == 1 {
+                wav.push(0);
+            }
+        }
+        wav
+    }
+
+    // the decoded file fed to a meter in pieces of the size.
+    fn quality(payload: &[u8], decoded: &[u8], piece: usize) -> AudioQuality {
+        let mut meter = Meter::new(Wav::parse(payload).unwrap());
+        for (index, payload) in payload.chunks(piece).enumerate() {
+            let start = (index * piece).min(decoded.len());
+            let end = (start + piece).min(decoded.len());
+            meter.feed(payload, &decoded[start..end]);
+        }
+        meter.finish()
+    }
+
+    #[test]
+    fn only_pcm_and_float_wav_files_parse() {
+        let payload = wav(&[0; 8]);
+        let parsed = Wav::parse(&payload).unwrap();
+        assert_eq!(parsed.data, 56..72);
+        assert_eq!(parsed.bytes_per_sample, 2);
+        assert!(Wav::parse(b"RIFF\0\0\0\0AVI ").is_none());
+        assert!(Wav::parse(&payload[..40]).is_none());
+        // ADPCM has no samples to compare.
+        let mut adpcm = payload.clone();
+        adpcm[20] = 2;
+        assert!(Wav::parse(&adpcm).is_none());
+    }
+
+    #[test]
+    fn samples_round_trip_without_noise() {
+        let payload = wav(&[16384, -16384, 1, 0, 0x7fff, -0x8000, 3, 4]);
+        for piece in [1, 3, 7, payload.len()] {
+            let quality = quality(&payload, &payload, piece);
+            assert_eq!(quality.snr, None);
+            assert_eq!(quality.samples, 8);
+            assert_eq!(quality.samples_hit, 0);
+        }
+    }
+
+    #[test]
+    fn noise_is_the_difference_to_the_original_samples() {
+        // half of full scale, one sample decoded as silence: a signal of 8
+        // times the noise.
+        let payload = wav(&[16384; 8]);
+        let mut decoded = payload.clone();
+        decoded[56..58].fill(0);
+        for piece in [1, 5, payload.len()] {
+            let quality = quality(&payload, &decoded, piece);
+            assert_eq!(quality.samples_hit, 1);
+            assert!((quality.snr.unwrap() - 10.0 * 8f64.log10()).abs() < 1e-9);
+        }
+        // the header hit is no noise, missing samples are silent.
+        let mut decoded = payload.clone();
+        decoded[0] ^= 1;
+        decoded.truncate(payload.len() - 4);
+        let quality = quality(&payload, &decoded, 5);
+        assert_eq!((quality.samples, quality.samples_hit), (8, 2));
+        assert!((quality.snr.unwrap() - 10.0 * 4f64.log10()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn residuals_of_a_wav_payload_measure_the_audio() {
+        let memory = |bytes: &[u8]| Source::Memory {
+            name: PathBuf::from("audio.wav"),
+            bytes: Arc::from(bytes),
+        };
+        let payload = wav(&[16384; 8]);
+        let mut decoded = payload.clone();
+        decoded[57] ^= 0x40;
+        let residuals =
+            task::block_on(Residuals::between(&memory(&payload), &memory(&decoded))).unwrap();
+        let quality = residuals.audio().unwrap();
+        assert_eq!((quality.samples, quality.samples_hit), (8, 1));
+        assert!((quality.snr.unwrap() - 10.0 * 8f64.log10()).abs() < 1e-9);
+
+        let residuals =
+            task::block_on(Residuals::between(&memory(b"text"), &memory(b"text"))).unwrap();
+        assert!(residuals.audio().is_none());
+    }
+}
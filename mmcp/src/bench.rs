@@ -0,0 +1,67 @@
+use color_eyre::eyre::Result;
+use rand::{rngs::StdRng, RngCore, SeedableRng};
+use std::time::{Duration, Instant};
+
+use crate::{
+    analytics::{self, Analytics},
+    channel::Channel,
+    coder::Coder,
+    interleaver::Interleaver,
+    pipeline::{self, Files},
+};
+
+/// Random payloads of several sizes a coder is timed with, one report row
+/// per payload and channel with the throughput next to the errors.
+pub struct Bench {
+    /// Bytes of every payload.
+    pub sizes: Vec<usize>,
+    /// Untimed rounds of encoding and decoding before the timed ones.
+    pub warmup: usize,
+    pub iterations: usize,
+}
+
+impl Bench {
+    /// Encodes and decodes every payload with the coder, then sends it over
+    /// each of the channels. The payloads are drawn from the seed and become
+    /// the payload of the runs, in memory.
+    pub async fn run(
+        &self,
+        coder: &mut dyn Coder,
+        interleaver: &dyn Interleaver,
+        channels: impl Fn() -> Result<Vec<Channel>>,
+        seed: u64,
+    ) -> Result<Vec<Analytics>> {
+        let mut results = vec![];
+        for &size in &self.sizes {
+            let mut payload = vec![0; size];
+            StdRng::seed_from_u64(seed).fill_bytes(&mut payload);
+            for _ in 0..self.warmup {
+                let encoded = coder.encode(&payload)?;
+                coder.decode(&encoded)?;
+            }
+            let iterations = self.iterations.max(1);
+            let mut encoding = Duration::ZERO;
+            let mut decoding = Duration::ZERO;
+            for _ in 0..iterations {
+                let start = Instant::now();
+                let encoded = coder.encode(&payload)?;
+                encoding += start.elapsed();
+                let start = Instant::now();
+                coder.decode(&encoded)?;
+                decoding += start.elapsed();
+            }
+            let megabytes = (size * iterations) as f64 / 1e6;
+            pipeline::set_files(Files::in_memory(format!("random-{}", size), payload));
+            for mut channel in channels()? {
+                let run_metrics = pipeline::run(coder, interleaver, &mut channel).await?;
+                let mut analytics = analytics::analyze(&channel, run_metrics).await?;
+                analytics.set_throughput(
+                    megabytes / encoding.as_secs_f64(),
+                    megabytes / decoding.as_secs_f64(),
+                );
+                results.push(analytics);
+            }
+        }
+        Ok(results)
+    }
+}
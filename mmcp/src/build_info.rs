@@ -0,0 +1,55 @@
+use serde::{Deserialize, Serialize};
+use std::sync::RwLock;
+
+// the binary running the coders, see `set`.
+static BUILD: RwLock<Option<BuildInfo>> = RwLock::new(None);
+
+/// What produced a run: the crate of the binary with the coders, its
+/// version and the git commit it was built from.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BuildInfo {
+    pub crate_name: String,
+    pub version: String,
+    pub commit: String,
+}
+
+impl BuildInfo {
+    pub fn new(crate_name: &str, version: &str) -> Self {
+        Self {
+            crate_name: crate_name.to_string(),
+            version: version.to_string(),
+            commit: env!("MMCP_COMMIT").to_string(),
+        }
+    }
+}
+
+impl std::fmt::Display for BuildInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} ({})", self.crate_name, self.version, self.commit)
+    }
+}
+
+// the library itself until a binary says otherwise.
+impl Default for BuildInfo {
+    fn default() -> Self {
+        Self::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+    }
+}
+
+/// The build the runs of the process are reported with from now on,
+/// usually `set(build_info!())` first thing in `main`.
+pub fn set(build: BuildInfo) {
+    *BUILD.write().unwrap() = Some(build);
+}
+
+pub fn get() -> BuildInfo {
+    BUILD.read().unwrap().clone().unwrap_or_default()
+}
+
+/// The [`BuildInfo`] of the crate the macro is used in.
+#[macro_export]
+macro_rules! build_info {
+    () => {
+        $crate::build_info::BuildInfo::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+    };
+}
@@ -0,0 +1,132 @@
+use serde::{Deserialize, Serialize};
+
+/// The bursts of consecutive bit errors in a stream, counted by their
+/// length, and the error-free gaps between them, fed the positions of the
+/// errors in order. How long bursts get tells how deep an interleaver has
+/// to spread them.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Bursts {
+    // bursts by their length in bits, the first entry those of a single bit.
+    lengths: Vec<u64>,
+    gaps: Gaps,
+    // first and last position of the burst going on, counted once closed.
+    #[serde(skip)]
+    current: Option<(u64, u64)>,
+}
+
+impl Bursts {
+    /// Adds the error at the bit position, which comes after every error
+    /// added before.
+    pub fn error(&mut self, position: u64) {
+        match self.current {
+            Some((first, last)) if position == last + 1 => self.current = Some((first, position)),
+            Some((_, last)) => {
+                self.close();
+                self.gaps.add(position - last - 1);
+                self.current = Some((position, position));
+            }
+            None => self.current = Some((position, position)),
+        }
+    }
+
+    /// Counts the burst going on, no further errors follow.
+    pub fn finish(&mut self) {
+        self.close();
+    }
+
+    fn close(&mut self) {
+        if let Some((first, last)) = self.current.take() {
+            let length = (last - first + 1) as usize;
+            if self.lengths.len() < length {
+                self.lengths.resize(length, 0);
+            }
+            self.lengths[length - 1] += 1;
+        }
+    }
+
+    pub fn count(&self) -> u64 {
+        self.lengths.iter().sum()
+    }
+
+    /// The mean length in bits, none without errors.
+    pub fn mean(&self) -> Option<f64> {
+        let errors: u64 = (1..)
+            .zip(&self.lengths)
+            .map(|(length, count)| length * count)
+            .sum();
+        match self.count() {
+            0 => None,
+            count => Some(errors as f64 / count as f64),
+        }
+    }
+
+    /// The length in bits no longer than which `share` of the bursts are.
+    pub fn percentile(&self, share: f64) -> Option<u64> {
+        let wanted = (share * self.count() as f64).ceil().max(1.0) as u64;
+        let mut bursts = 0;
+        (1..).zip(&self.lengths).find_map(|(length, count)| {
+            bursts += count;
+            (bursts >= wanted).then_some(length)
+        })
+    }
+
+    /// The error-free gaps between the bursts.
+    pub fn gaps(&self) -> &Gaps {
+        &self.gaps
+    }
+
+    pub fn max(&self) -> Option<u64> {
+        self.lengths
+            .iter()
+            .rposition(|&count| count > 0)
+            .map(|index| index as u64 + 1)
+    }
+}
+
+/// Lengths of the runs of correct bits between two errors. Errors striking
+/// independently of each other leave gaps about as spread as they are long,
+/// a coefficient of variation of one. Errors clustering leave more.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Gaps {
+    count: u64,
+    sum: f64,
+    squares: f64,
+    max: u64,
+    // gaps by the power of two their length falls below, the first entry
+    // those of a single bit, the next those of two or three.
+    histogram: Vec<u64>,
+}
+
+impl Gaps {
+    fn add(&mut self, length: u64) {
+        self.count += 1;
+        self.sum += length as f64;
+        self.squares += (length as f64).powi(2);
+        self.max = self.max.max(length);
+        let bin = length.ilog2() as usize;
+        if self.histogram.len() <= bin {
+            self.histogram.resize(bin + 1, 0);
+        }
+        self.histogram[bin] += 1;
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// The mean length in bits, none without two errors apart.
+    pub fn mean(&self) -> Option<f64> {
+        (self.count > 0).then(|| self.sum / self.count as f64)
+    }
+
+    /// The standard deviation of the lengths over their mean.
+    pub fn coefficient_of_variation(&self) -> Option<f64> {
+        let mean = self.mean()?;
+        let variance = (self.squares / self.count as f64 - mean * mean).max(0.0);
+        (mean > 0.0).then(|| variance.sqrt() / mean)
+    }
+
+    pub fn max(&self) -> Option<u64> {
+        (self.count > 0).then_some(self.max)
+    }
+}
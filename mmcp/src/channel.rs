@@ -1,8 +1,16 @@
 use async_std::prelude::*;
-use color_eyre::eyre::Result;
-use rand::prelude::*;
+use color_eyre::eyre::{eyre, Result};
+use rand::{prelude::*, rngs::StdRng};
+use serde::{Deserialize, Serialize};
+use std::{f64::consts::TAU, path::Path, str::FromStr};
 
-#[derive(Clone, Copy)]
+use crate::bursts::Bursts;
+
+// reliability of bits that are never flipped, larger ratios are clamped.
+const RELIABLE_LLR: f32 = 16.0;
+const TRACE_HEADER: &str = "# mmcp error trace: bit positions hit by the channel";
+
+#[derive(Debug, Clone, Copy)]
 enum State {
     Good,
     Bad,
@@ -17,80 +25,688 @@ impl State {
     }
 }
 
+/// How the channel corrupts the bits it carries.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(tag = "model", rename_all = "kebab-case")]
+pub enum Model {
+    /// Every bit the state switches from good to bad with probability `p`
+    /// and back with probability `r`, so bursts last 1 / r bits on average.
+    /// Bits flip with probability `good_error` or `h` in the two states.
+    GilbertElliott {
+        p: f64,
+        r: f64,
+        good_error: f64,
+        h: f64,
+    },
+    /// Bits are sent as BPSK symbols +1 / -1 with additive white Gaussian
+    /// noise, `eb_n0_db` is the energy per information bit over the noise
+    /// density.
+    Awgn { eb_n0_db: f64 },
+    /// The stream is cut into packets of `packet_bytes` bytes and every
+    /// packet is dropped with probability `loss`, the rest arrive intact.
+    PacketLoss { packet_bytes: usize, loss: f64 },
+    /// Before every bit a random bit is inserted with probability
+    /// `insertion`, and the bit itself is deleted with probability
+    /// `deletion`, which shifts everything received after it.
+    InsertionDeletion { insertion: f64, deletion: f64 },
+    /// Flips exactly the bits of a trace captured from another run.
+    Replay { errors: usize },
+    /// Moves through the states of a [`MarkovChain`] bit by bit.
+    Markov { states: usize, mean_error: f64 },
+    /// The first `fade` bits of every `period` bits flip with probability
+    /// `h`, the rest arrive intact.
+    PeriodicFade { period: u64, fade: u64, h: f64 },
+}
+
+impl std::fmt::Display for Model {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            // the original symmetric model.
+            Model::GilbertElliott {
+                p,
+                r,
+                good_error,
+                h,
+            } if p == r && good_error == 0.0 => {
+                write!(f, "h: {}, tau: {}", probability(h), probability(p))
+            }
+            Model::GilbertElliott {
+                p,
+                r,
+                good_error,
+                h,
+            } => write!(
+                f,
+                "GE p: {}, r: {} ({:.0} bit bursts), good: {}, bad: {:.2}",
+                p,
+                r,
+                1.0 / r,
+                good_error,
+                h
+            ),
+            Model::Awgn { eb_n0_db } => write!(f, "AWGN Eb/N0: {:.1} dB", eb_n0_db),
+            Model::PacketLoss { packet_bytes, loss } => {
+                write!(f, "{} B packets, loss: {:.1}%", packet_bytes, loss * 100.0)
+            }
+            Model::InsertionDeletion {
+                insertion,
+                deletion,
+            } => write!(f, "insertion: {:e}, deletion: {:e}", insertion, deletion),
+            Model::Replay { errors } => write!(f, "replay of {} errors", errors),
+            Model::Markov { states, mean_error } => write!(
+                f,
+                "Markov, {} states, mean error: {}",
+                states,
+                probability(mean_error)
+            ),
+            Model::PeriodicFade { period, fade, h } => {
+                write!(f, "fade {} of {} bits, h: {}", fade, period, probability(h))
+            }
+        }
+    }
+}
+
 pub struct Channel {
+    model: Model,
+    seed: u64,
+    rng: StdRng,
     state: State,
-    h: f64,
-    tau: f64,
-    channel_bit_errors: u32,
+    // bytes sent so far and whether the current packet is dropped.
+    sent_bytes: usize,
+    sent_bits: u64,
+    // sorted bit positions to flip when replaying and the next one due.
+    trace: Vec<u64>,
+    trace_position: usize,
+    chain: Option<MarkovChain>,
+    chain_state: usize,
+    captured: Option<Vec<u64>>,
+    dropping: bool,
+    // bits received towards the next byte and their ratios.
+    received: (u8, [f32; 8]),
+    received_bits: usize,
+    code_rate: f64,
+    soft_output: bool,
+    erasures: bool,
+    channel_bit_errors: u64,
+    // of the bits flipped, lost and shifted bits left out.
+    bursts: Bursts,
 }
 
 impl Channel {
+    // the original symmetric model: the state switches with probability tau
+    // either way and only the bad state flips bits.
     pub fn new(h: f64, tau: f64) -> Self {
+        Self::gilbert_elliott(tau, tau, 0.0, h)
+    }
+
+    pub fn gilbert_elliott(p: f64, r: f64, good_error: f64, h: f64) -> Self {
+        Self::with_model(Model::GilbertElliott {
+            p,
+            r,
+            good_error,
+            h,
+        })
+    }
+
+    pub fn awgn(eb_n0_db: f64) -> Self {
+        Self::with_model(Model::Awgn { eb_n0_db })
+    }
+
+    pub fn insertion_deletion(insertion: f64, deletion: f64) -> Self {
+        Self::with_model(Model::InsertionDeletion {
+            insertion,
+            deletion,
+        })
+    }
+
+    /// Replays a trace written by [`Channel::save_trace`].
+    pub fn replay(path: impl AsRef<Path>) -> Result<Self> {
+        Self::import(path, TraceFormat::Positions)
+    }
+
+    /// Flips the bits of an error pattern from a file, for traces captured on
+    /// real links or by other simulators. Positions count from the first bit
+    /// sent, in `to_bits` order.
+    pub fn import(path: impl AsRef<Path>, format: TraceFormat) -> Result<Self> {
+        let mut trace = match format {
+            TraceFormat::Positions => positions(&std::fs::read_to_string(path)?)?,
+            TraceFormat::Bitmap => std::fs::read(path)?
+                .iter()
+                .enumerate()
+                .flat_map(|(index, &byte)| {
+                    (0..8)
+                        .filter(move |bit| (byte >> bit) & 1 == 1)
+                        .map(move |bit| 8 * index as u64 + bit)
+                })
+                .collect(),
+        };
+        trace.sort_unstable();
+        trace.dedup();
+        let mut channel = Self::with_model(Model::Replay {
+            errors: trace.len(),
+        });
+        channel.trace = trace;
+        Ok(channel)
+    }
+
+    /// Starts in the first state of the chain.
+    pub fn markov(chain: MarkovChain) -> Self {
+        let mut channel = Self::with_model(Model::Markov {
+            states: chain.error.len(),
+            mean_error: chain.mean_error(),
+        });
+        channel.chain = Some(chain);
+        channel
+    }
+
+    pub fn periodic_fade(period: u64, fade: u64, h: f64) -> Self {
+        assert!(period > 0, "fades need a period");
+        Self::with_model(Model::PeriodicFade { period, fade, h })
+    }
+
+    pub fn packet_loss(packet_bytes: usize, loss: f64) -> Self {
+        assert!(packet_bytes > 0, "packets must carry at least one byte");
+        Self::with_model(Model::PacketLoss { packet_bytes, loss })
+    }
+
+    fn with_model(model: Model) -> Self {
+        let seed = rand::random();
         Self {
+            model,
+            seed,
+            rng: StdRng::seed_from_u64(seed),
             state: State::Good,
-            h,
-            tau,
+            sent_bytes: 0,
+            sent_bits: 0,
+            trace: vec![],
+            trace_position: 0,
+            chain: None,
+            chain_state: 0,
+            captured: None,
+            dropping: false,
+            received: (0, [0.0; 8]),
+            received_bits: 0,
+            code_rate: 1.0,
+            soft_output: false,
+            erasures: false,
             channel_bit_errors: 0,
+            bursts: Bursts::default(),
         }
     }
 
-    pub(super) fn channel_bit_errors(&self) -> u32 {
+    /// Replaces the random seed, the same seed gives the same errors on the
+    /// same stream.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self.rng = StdRng::seed_from_u64(seed);
+        self
+    }
+
+    /// Records the position of every bit the channel gets wrong, see
+    /// [`Channel::save_trace`].
+    pub fn with_trace_capture(mut self) -> Self {
+        self.captured = Some(vec![]);
+        self
+    }
+
+    /// Writes the captured positions in the format [`Channel::replay`] reads.
+    pub fn save_trace(&self, path: impl AsRef<Path>) -> Result<()> {
+        let captured = self
+            .captured
+            .as_ref()
+            .ok_or_else(|| eyre!("trace capture is not enabled for this channel"))?;
+        let mut trace = format!("{}\n# {}\n", TRACE_HEADER, self.model);
+        for position in captured {
+            trace.push_str(&position.to_string());
+            trace.push('\n');
+        }
+        std::fs::write(path, trace)?;
+        Ok(())
+    }
+
+    /// Makes the receiver report a log likelihood ratio per bit instead of
+    /// hard bits, see [`Channel::process_soft`].
+    pub fn with_soft_output(mut self) -> Self {
+        self.soft_output = true;
+        self
+    }
+
+    /// Marks the bits the channel gets wrong as erased instead of flipping
+    /// them. Erased bits reach the decoder as ratios of zero, so the channel
+    /// reports soft output; hard decisions read them as zeros.
+    pub fn with_erasures(mut self) -> Self {
+        self.erasures = true;
+        self.soft_output = true;
+        self
+    }
+
+    /// Whether every byte sent arrives as a byte, so the receiver can split
+    /// the stream where the sender did. Lost packets and inserted or deleted
+    /// bits shift everything after them.
+    pub fn keeps_length(&self) -> bool {
+        !matches!(
+            self.model,
+            Model::PacketLoss { .. } | Model::InsertionDeletion { .. }
+        )
+    }
+
+    pub fn soft_output(&self) -> bool {
+        self.soft_output
+    }
+
+    /// Information bits per channel bit, which scales the symbol energy of
+    /// AWGN channels. Uncoded until the pipeline knows the coder's rate.
+    pub fn set_code_rate(&mut self, code_rate: f64) {
+        self.code_rate = code_rate;
+    }
+
+    pub(super) fn channel_bit_errors(&self) -> u64 {
         self.channel_bit_errors
     }
 
+    /// The bursts of the bits flipped so far.
+    pub(super) fn bursts(&self) -> Bursts {
+        let mut bursts = self.bursts.clone();
+        bursts.finish();
+        bursts
+    }
+
+    pub(super) fn sent_bytes(&self) -> usize {
+        self.sent_bytes
+    }
+
     pub fn channel_information(&self) -> ChannelInformation {
         ChannelInformation {
-            h: self.h,
-            tau: self.tau,
+            model: self.model,
+            seed: self.seed,
+            soft_output: self.soft_output,
+            erasures: self.erasures,
         }
     }
 
-    pub async fn process<'a, S: 'a>(&'a mut self, stream: S) -> Result<impl Stream<Item = u8> + 'a>
+    pub async fn process<'a, S>(&'a mut self, stream: S) -> Result<impl Stream<Item = u8> + 'a>
     where
-        S: Stream<Item = u8>,
+        S: Stream<Item = u8> + 'a,
     {
-        let transition = rand::distributions::Uniform::new(0.0, 1.0);
-        let error = rand::distributions::Uniform::new(0.0, 1.0);
-
-        Ok(stream.map(move |byte| {
-            let mut rng = rand::thread_rng();
-            let ge_bits = (0..8)
-                .into_iter()
-                .map(|_| {
-                    if rng.sample(transition) < self.tau {
-                        self.state = self.state.transition();
-                    }
-                    match self.state {
-                        State::Good => 0,
-                        State::Bad => {
-                            if rng.sample(error) < self.h {
-                                self.channel_bit_errors += 1;
-                                0b01
-                            } else {
-                                0
-                            }
-                        }
-                    }
-                })
-                .collect::<Vec<_>>();
-            let ge_byte = super::to_byte(&ge_bits);
-            byte ^ ge_byte
+        Ok(stream.flat_map(move |byte| {
+            let received = self.transmit(byte);
+            async_std::stream::from_iter(received.into_iter().flatten().map(|(byte, _)| byte))
         }))
     }
+
+    /// Like [`Channel::process`] for a stream of chunks, every chunk sent
+    /// with [`Channel::send`].
+    pub async fn process_chunks<'a, S>(
+        &'a mut self,
+        stream: S,
+    ) -> Result<impl Stream<Item = Vec<u8>> + 'a>
+    where
+        S: Stream<Item = Vec<u8>> + 'a,
+    {
+        Ok(stream.map(move |chunk| self.send(&chunk)))
+    }
+
+    /// Sends the bytes and returns what the receiver decides, for stages
+    /// driving the channel themselves one frame at a time.
+    pub fn send(&mut self, data: &[u8]) -> Vec<u8> {
+        data.iter()
+            .flat_map(|&byte| self.transmit(byte))
+            .flatten()
+            .map(|(byte, _)| byte)
+            .collect()
+    }
+
+    /// Sends the bytes and returns a log likelihood ratio log P(0) / P(1) per
+    /// bit, in `to_bits` order. On Gilbert-Elliott channels the receiver is
+    /// assumed to know the state a bit was sent in, as it would from the
+    /// signal quality: bits of the bad state get ln((1 - h) / h), which turns
+    /// negative for h above 1/2, and bits of the good state the same ratio of
+    /// `good_error`. AWGN channels report 2y / sigma^2 of the received symbol.
+    /// Erased bits get 0, every other bit of an erasure channel is certain.
+    /// Dropped packets are left out, bits of a trailing partial byte after
+    /// insertions or deletions as well.
+    pub fn process_soft(&mut self, data: &[u8]) -> Vec<f32> {
+        data.iter()
+            .flat_map(|&byte| self.transmit(byte))
+            .flatten()
+            .flat_map(|(_, llrs)| llrs)
+            .collect()
+    }
+
+    // sends the bits of a byte, returns the bytes they complete at the
+    // receiver with erased bits read as zeros and the ratio of every bit in
+    // to_bits order. that is a single byte unless bits are inserted, dropped
+    // or deleted.
+    fn transmit(&mut self, byte: u8) -> [Option<(u8, [f32; 8])>; 2] {
+        let mut completed = [None, None];
+        let mut count = 0;
+        let index = self.sent_bytes;
+        self.sent_bytes += 1;
+        if let Model::PacketLoss { packet_bytes, loss } = self.model {
+            if index.is_multiple_of(packet_bytes) {
+                self.dropping = self.rng.gen::<f64>() < loss;
+            }
+            if self.dropping {
+                self.channel_bit_errors += 8;
+                return completed;
+            }
+        }
+        for i in 0..8 {
+            let bit = (byte >> i) & 1;
+            if let Model::InsertionDeletion {
+                insertion,
+                deletion,
+            } = self.model
+            {
+                if self.rng.gen::<f64>() < insertion {
+                    let inserted = self.rng.gen::<bool>() as u8;
+                    self.channel_bit_errors += 1;
+                    if let Some(byte) = self.receive(inserted, certain(inserted)) {
+                        completed[count] = Some(byte);
+                        count += 1;
+                    }
+                }
+                if self.rng.gen::<f64>() < deletion {
+                    self.channel_bit_errors += 1;
+                    continue;
+                }
+            }
+            let (hit, ratio) = self.send_bit(bit);
+            if hit {
+                self.bursts.error(self.sent_bits);
+            }
+            if let (true, Some(captured)) = (hit, self.captured.as_mut()) {
+                captured.push(self.sent_bits);
+            }
+            self.sent_bits += 1;
+            self.channel_bit_errors += hit as u64;
+            let (bit, llr) = match (hit, self.erasures) {
+                (true, true) => (0, 0.0),
+                (_, true) => (bit ^ hit as u8, certain(bit ^ hit as u8)),
+                (_, false) => (bit ^ hit as u8, ratio),
+            };
+            if let Some(byte) = self.receive(bit, llr) {
+                completed[count] = Some(byte);
+                count += 1;
+            }
+        }
+        completed
+    }
+
+    // appends a received bit, returns the byte it completes.
+    fn receive(&mut self, bit: u8, llr: f32) -> Option<(u8, [f32; 8])> {
+        self.received.0 |= bit << self.received_bits;
+        self.received.1[self.received_bits] = llr;
+        self.received_bits += 1;
+        if self.received_bits < 8 {
+            return None;
+        }
+        self.received_bits = 0;
+        Some(std::mem::replace(&mut self.received, (0, [0.0; 8])))
+    }
+
+    // sends a single bit, returns whether its hard decision is wrong and the
+    // ratio of what was received.
+    fn send_bit(&mut self, bit: u8) -> (bool, f32) {
+        match self.model {
+            Model::GilbertElliott {
+                p,
+                r,
+                good_error,
+                h,
+            } => {
+                let switch = match self.state {
+                    State::Good => p,
+                    State::Bad => r,
+                };
+                if self.rng.gen::<f64>() < switch {
+                    self.state = self.state.transition();
+                }
+                let error = match self.state {
+                    State::Good => good_error,
+                    State::Bad => h,
+                };
+                let hit = error > 0.0 && self.rng.gen::<f64>() < error;
+                (hit, signed(reliability(error), bit ^ hit as u8))
+            }
+            Model::Awgn { eb_n0_db } => {
+                let es_n0 = self.code_rate * 10f64.powf(eb_n0_db / 10.0);
+                let sigma = (1.0 / (2.0 * es_n0)).sqrt();
+                let symbol = if bit == 0 { 1.0 } else { -1.0 };
+                let y = symbol + sigma * gaussian(&mut self.rng);
+                let llr = (2.0 * y / (sigma * sigma)) as f32;
+                (
+                    (y < 0.0) != (bit == 1),
+                    llr.clamp(-RELIABLE_LLR, RELIABLE_LLR),
+                )
+            }
+            // whole packets and single bits are dropped before bits are sent.
+            Model::PacketLoss { .. } | Model::InsertionDeletion { .. } => (false, certain(bit)),
+            Model::Replay { .. } => {
+                let hit = self.trace.get(self.trace_position) == Some(&self.sent_bits);
+                self.trace_position += hit as usize;
+                (hit, certain(bit ^ hit as u8))
+            }
+            Model::Markov { .. } => {
+                let chain = self
+                    .chain
+                    .as_ref()
+                    .expect("markov channels carry their chain");
+                self.chain_state = chain.next(self.chain_state, &mut self.rng);
+                let error = chain.error[self.chain_state];
+                let hit = error > 0.0 && self.rng.gen::<f64>() < error;
+                (hit, signed(reliability(error), bit ^ hit as u8))
+            }
+            Model::PeriodicFade { period, fade, h } => {
+                let error = if self.sent_bits % period < fade {
+                    h
+                } else {
+                    0.0
+                };
+                let hit = error > 0.0 && self.rng.gen::<f64>() < error;
+                (hit, signed(reliability(error), bit ^ hit as u8))
+            }
+        }
+    }
+}
+
+/// States a channel moves through bit by bit, each flipping the bits sent in
+/// it with its own probability.
+#[derive(Debug, Clone)]
+pub struct MarkovChain {
+    error: Vec<f64>,
+    // transitions[i][j] is the probability of moving from state i to j.
+    transitions: Vec<Vec<f64>>,
+}
+
+impl MarkovChain {
+    pub fn new(error: Vec<f64>, transitions: Vec<Vec<f64>>) -> Self {
+        assert!(
+            !error.is_empty() && transitions.len() == error.len(),
+            "a Markov chain needs a row of transitions per state"
+        );
+        for row in &transitions {
+            assert!(
+                row.len() == error.len() && (row.iter().sum::<f64>() - 1.0).abs() < 1e-9,
+                "transitions out of a state must cover every state and sum to 1"
+            );
+        }
+        Self { error, transitions }
+    }
+
+    /// Bit error probability in the long run.
+    pub fn mean_error(&self) -> f64 {
+        // power iteration towards the stationary distribution.
+        let states = self.error.len();
+        let mut share = vec![1.0 / states as f64; states];
+        for _ in 0..10_000 {
+            let mut next = vec![0.0; states];
+            for (from, row) in self.transitions.iter().enumerate() {
+                for (to, probability) in row.iter().enumerate() {
+                    next[to] += share[from] * probability;
+                }
+            }
+            share = next;
+        }
+        share.iter().zip(&self.error).map(|(s, e)| s * e).sum()
+    }
+
+    fn next(&self, state: usize, rng: &mut impl Rng) -> usize {
+        let mut draw = rng.gen::<f64>();
+        for (next, &probability) in self.transitions[state].iter().enumerate() {
+            if draw < probability {
+                return next;
+            }
+            draw -= probability;
+        }
+        self.error.len() - 1
+    }
+}
+
+// three significant digits, but at least two decimals.
+fn probability(value: f64) -> String {
+    let decimals = if value > 0.0 {
+        (2.0 - value.log10().floor()).clamp(2.0, 12.0) as usize
+    } else {
+        2
+    };
+    let mut formatted = format!("{:.*}", decimals, value);
+    while formatted.ends_with('0') && formatted.len() - formatted.find('.').unwrap() > 3 {
+        formatted.pop();
+    }
+    formatted
+}
+
+// log likelihood ratio of a bit received without any doubt.
+fn certain(bit: u8) -> f32 {
+    signed(RELIABLE_LLR, bit)
+}
+
+// log likelihood ratio of a received bit with the given reliability.
+fn signed(reliability: f32, bit: u8) -> f32 {
+    if bit == 0 {
+        reliability
+    } else {
+        -reliability
+    }
+}
+
+// log likelihood ratio of a received bit flipped with the given probability.
+fn reliability(error: f64) -> f32 {
+    (((1.0 - error) / error).ln() as f32).clamp(-RELIABLE_LLR, RELIABLE_LLR)
+}
+
+// standard normal sample by the Box-Muller transform.
+fn gaussian(rng: &mut impl Rng) -> f64 {
+    let u = 1.0 - rng.gen::<f64>();
+    let v = rng.gen::<f64>();
+    (-2.0 * u.ln()).sqrt() * (TAU * v).cos()
+}
+
+/// How an error pattern is stored in a file.
+#[derive(Debug, Clone, Copy)]
+pub enum TraceFormat {
+    /// Positions of the bits in error separated by commas or whitespace, so
+    /// CSV files and one position per line both work. Lines starting with `#`
+    /// are comments, a first line that is not a number is taken as a header.
+    Positions,
+    /// Raw bytes where every set bit marks a bit in error, bit i of byte j
+    /// being bit 8 * j + i sent.
+    Bitmap,
+}
+
+impl FromStr for TraceFormat {
+    type Err = color_eyre::Report;
+
+    fn from_str(format: &str) -> Result<Self> {
+        match format {
+            "positions" | "csv" => Ok(Self::Positions),
+            "bitmap" => Ok(Self::Bitmap),
+            _ => Err(eyre!(
+                "unknown trace format {}, expected positions, csv or bitmap",
+                format
+            )),
+        }
+    }
+}
+
+fn positions(trace: &str) -> Result<Vec<u64>> {
+    let mut positions = vec![];
+    let lines = trace
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'));
+    for (index, line) in lines.enumerate() {
+        let fields = line
+            .split(|c: char| c == ',' || c == ';' || c.is_whitespace())
+            .filter(|field| !field.is_empty())
+            .map(str::parse::<u64>)
+            .collect::<Result<Vec<_>, _>>();
+        match fields {
+            Ok(fields) => positions.extend(fields),
+            Err(_) if index == 0 => continue,
+            Err(error) => return Err(eyre!("invalid bit position in {:?}: {}", line, error)),
+        }
+    }
+    Ok(positions)
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct ChannelInformation {
-    h: f64,
-    tau: f64,
+    #[serde(flatten)]
+    model: Model,
+    seed: u64,
+    soft_output: bool,
+    erasures: bool,
 }
 
 impl ChannelInformation {
+    pub fn model(&self) -> Model {
+        self.model
+    }
+
+    /// Bit error probability in the bad state, or of the faded bits. NaN for
+    /// models without one.
     pub fn get_h(&self) -> f64 {
-        self.h
+        match self.model {
+            Model::GilbertElliott { h, .. } | Model::PeriodicFade { h, .. } => h,
+            _ => f64::NAN,
+        }
     }
 
+    /// Probability of a switch from the good to the bad state, the tau of
+    /// channels built with `Channel::new`. NaN for models without one.
     pub fn get_tau(&self) -> f64 {
-        self.tau
+        match self.model {
+            Model::GilbertElliott { p, .. } => p,
+            _ => f64::NAN,
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn soft_output(&self) -> bool {
+        self.soft_output
+    }
+
+    pub fn erasures(&self) -> bool {
+        self.erasures
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn information_keeps_h_and_tau() {
+        let information = Channel::new(0.1, 0.05).channel_information();
+        assert_eq!((information.get_h(), information.get_tau()), (0.1, 0.05));
+        let information = Channel::periodic_fade(100, 10, 0.3).channel_information();
+        assert_eq!(information.get_h(), 0.3);
+        assert!(information.get_tau().is_nan());
+        assert!(Channel::awgn(3.0).channel_information().get_h().is_nan());
     }
 }
@@ -0,0 +1,189 @@
+use color_eyre::eyre::Result;
+use serde::{Deserialize, Serialize};
+
+/// A coder that can be selected at pipeline construction time and driven by
+/// [`crate::pipeline::run`].
+pub trait Coder {
+    /// Name shown in the report.
+    fn name(&self) -> String;
+
+    fn encode(&mut self, data: &[u8]) -> Result<Vec<u8>>;
+
+    fn decode(&mut self, data: &[u8]) -> Result<Vec<u8>>;
+
+    /// Decodes from a log likelihood ratio per received bit, in
+    /// [`crate::to_bits`] order. Coders without a soft decoder decide every
+    /// bit on its own and decode the resulting bytes.
+    fn decode_soft(&mut self, llrs: &[f32]) -> Result<Vec<u8>> {
+        self.decode(&crate::hard_decisions(llrs))
+    }
+
+    /// Bytes coded independently of the bytes around them and what they
+    /// encode to, for coders able to code a payload block by block.
+    /// [`crate::pipeline::run`] then streams the payload in whole blocks, only
+    /// the last one of which may be shorter, calling `encode` and `decode`
+    /// once per batch of blocks.
+    fn block(&self) -> Option<(usize, usize)> {
+        None
+    }
+
+    /// Leading info bits of the last codeword treated as zero when encoding
+    /// `data_bytes` bytes, for block codes shortening their last codeword
+    /// instead of padding it.
+    fn shortening(&self, _data_bytes: usize) -> u32 {
+        0
+    }
+
+    /// Decodes a batch `encode` made of `data_bytes` bytes. Block codes work
+    /// out the [`Coder::shortening`] of the batch from it instead of
+    /// remembering the last one encoded, as [`crate::pipeline::run`] encodes
+    /// later batches before decoding the earlier ones.
+    fn decode_batch(&mut self, data: &[u8], _data_bytes: usize) -> Result<Vec<u8>> {
+        self.decode(data)
+    }
+
+    /// Payload bits every codeword carries, the payload filling the
+    /// codewords in order, for block codes. The report counts the codewords
+    /// decoded wrong from it.
+    fn codeword_bits(&self) -> Option<usize> {
+        None
+    }
+
+    /// Which codewords the last `decode` call corrected or gave up on, if
+    /// the coder tracks it. The report holds them against the payload for
+    /// the codewords corrected to the wrong value.
+    fn codeword_stats(&self) -> Option<CodewordStats> {
+        None
+    }
+
+    /// Outcome of the last `decode` call per segment, if the coder tracks it.
+    fn segment_stats(&self) -> Option<SegmentStats> {
+        None
+    }
+
+    /// Segment outcomes broken out per layer, outermost layer first.
+    fn layer_stats(&self) -> Vec<(String, SegmentStats)> {
+        self.segment_stats()
+            .map(|stats| vec![(self.name(), stats)])
+            .unwrap_or_default()
+    }
+
+    /// CRC outcome of every frame of the last `decode` call, if the coder frames its payload.
+    fn frame_stats(&self) -> Option<FrameStats> {
+        None
+    }
+
+    /// Which packets of the last `decode` call went missing, if the coder packetizes its stream.
+    fn packet_stats(&self) -> Option<PacketStats> {
+        None
+    }
+
+    /// Code group errors of the last `decode` call, if the coder line codes its stream.
+    fn line_stats(&self) -> Option<LineStats> {
+        None
+    }
+
+    /// Compressed size and damaged blocks of the last `decode` call, if the coder compresses its payload.
+    fn compression_stats(&self) -> Option<CompressionStats> {
+        None
+    }
+
+    /// Frames rejected by the last `decode` call, if the coder encrypts its payload.
+    fn cipher_stats(&self) -> Option<CipherStats> {
+        None
+    }
+}
+
+/// Sends the data as it is, for protocols relying on retransmissions alone.
+pub struct Uncoded;
+
+impl Coder for Uncoded {
+    fn name(&self) -> String {
+        "Uncoded".to_string()
+    }
+
+    fn block(&self) -> Option<(usize, usize)> {
+        Some((1, 1))
+    }
+
+    fn encode(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+        Ok(data.to_vec())
+    }
+
+    fn decode(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+        Ok(data.to_vec())
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+pub struct SegmentStats {
+    pub corrected: u64,
+    pub uncorrectable: u64,
+    pub passed: u64,
+}
+
+impl std::ops::AddAssign for SegmentStats {
+    fn add_assign(&mut self, other: Self) {
+        self.corrected += other.corrected;
+        self.uncorrectable += other.uncorrectable;
+        self.passed += other.passed;
+    }
+}
+
+/// Codewords by their index in the decoded stream, those in neither list
+/// passed as received.
+#[derive(Debug, Default, Clone)]
+pub struct CodewordStats {
+    pub corrected: Vec<usize>,
+    pub uncorrectable: Vec<usize>,
+}
+
+impl CodewordStats {
+    /// Adds the codewords of a later `decode` call, which started `offset`
+    /// codewords into the stream.
+    pub fn append(&mut self, other: Self, offset: usize) {
+        self.corrected
+            .extend(other.corrected.into_iter().map(|index| offset + index));
+        self.uncorrectable
+            .extend(other.uncorrectable.into_iter().map(|index| offset + index));
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct FrameStats {
+    /// Payload bytes per frame.
+    pub frame_size: usize,
+    /// Whether the CRC of each received frame matched, in frame order.
+    pub passed: Vec<bool>,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct PacketStats {
+    /// Whether each packet was lost on the channel, in sequence order.
+    pub lost: Vec<bool>,
+}
+
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+pub struct LineStats {
+    pub symbols: u32,
+    /// Received code groups the line code does not have.
+    pub invalid: u32,
+    /// Valid code groups received against the running disparity.
+    pub disparity_errors: u32,
+}
+
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+pub struct CompressionStats {
+    pub input_bytes: u64,
+    pub compressed_bytes: u64,
+    pub blocks: u64,
+    /// Blocks that failed to decompress to their original length and checksum.
+    pub corrupted: u64,
+}
+
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+pub struct CipherStats {
+    pub frames: u32,
+    /// Frames whose authentication tag did not match.
+    pub rejected: u32,
+}
@@ -0,0 +1,167 @@
+use color_eyre::eyre::Result;
+use flate2::{read::ZlibDecoder, write::ZlibEncoder, Compression};
+use std::io::{Read, Write};
+
+use crate::coder::{
+    CipherStats, Coder, CompressionStats, FrameStats, LineStats, PacketStats, SegmentStats,
+};
+
+// uncompressed and compressed length in front of every block.
+const BLOCK_HEADER_BYTES: usize = 8;
+
+/// Compresses the payload with zlib in independent blocks of `block_bytes`
+/// bytes before the wrapped coder and decompresses after it. A residual
+/// error in a block usually costs the whole block, which the receiver
+/// notices by its checksum and delivers as far as it decompressed, filled
+/// up with zeros. An error in the lengths in front of a block loses every
+/// block from there on.
+pub struct Compressed {
+    block_bytes: usize,
+    inner: Box<dyn Coder>,
+    stats: CompressionStats,
+}
+
+impl Compressed {
+    pub fn new(block_bytes: usize, inner: Box<dyn Coder>) -> Self {
+        assert!(
+            block_bytes > 0 && block_bytes <= u32::MAX as usize,
+            "blocks carry between 1 and 2^32 - 1 bytes"
+        );
+        Self {
+            block_bytes,
+            inner,
+            stats: CompressionStats::default(),
+        }
+    }
+}
+
+impl Coder for Compressed {
+    fn name(&self) -> String {
+        format!("{} + zlib/{}", self.inner.name(), self.block_bytes)
+    }
+
+    fn encode(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+        let mut compressed = vec![];
+        for block in data.chunks(self.block_bytes) {
+            let mut encoder = ZlibEncoder::new(vec![], Compression::default());
+            encoder.write_all(block)?;
+            let deflated = encoder.finish()?;
+            compressed.extend_from_slice(&(block.len() as u32).to_be_bytes());
+            compressed.extend_from_slice(&(deflated.len() as u32).to_be_bytes());
+            compressed.extend_from_slice(&deflated);
+        }
+        self.stats.input_bytes = data.len() as u64;
+        self.stats.compressed_bytes = compressed.len() as u64;
+        self.inner.encode(&compressed)
+    }
+
+    fn decode(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+        let compressed = self.inner.decode(data)?;
+        self.stats.blocks = 0;
+        self.stats.corrupted = 0;
+        let mut decompressed = Vec::with_capacity(self.stats.input_bytes as usize);
+        let mut rest = compressed.as_slice();
+        while rest.len() >= BLOCK_HEADER_BYTES {
+            let len = u32::from_be_bytes(rest[..4].try_into().unwrap()) as usize;
+            let deflated_len = u32::from_be_bytes(rest[4..8].try_into().unwrap()) as usize;
+            // a damaged length leaves nothing to find the next block by.
+            if len == 0 || len > self.block_bytes {
+                self.stats.blocks += 1;
+                self.stats.corrupted += 1;
+                break;
+            }
+            let deflated = &rest[BLOCK_HEADER_BYTES..];
+            let deflated = &deflated[..deflated_len.min(deflated.len())];
+            let mut block = Vec::with_capacity(len);
+            let intact = ZlibDecoder::new(deflated)
+                .read_to_end(&mut block)
+                .is_ok_and(|_| block.len() == len);
+            self.stats.blocks += 1;
+            self.stats.corrupted += !intact as u64;
+            block.resize(len, 0);
+            decompressed.extend(block);
+            rest = &rest[BLOCK_HEADER_BYTES + deflated.len()..];
+        }
+        Ok(decompressed)
+    }
+
+    fn segment_stats(&self) -> Option<SegmentStats> {
+        self.inner.segment_stats()
+    }
+
+    fn layer_stats(&self) -> Vec<(String, SegmentStats)> {
+        self.inner.layer_stats()
+    }
+
+    fn frame_stats(&self) -> Option<FrameStats> {
+        self.inner.frame_stats()
+    }
+
+    fn packet_stats(&self) -> Option<PacketStats> {
+        self.inner.packet_stats()
+    }
+
+    fn line_stats(&self) -> Option<LineStats> {
+        self.inner.line_stats()
+    }
+
+    fn compression_stats(&self) -> Option<CompressionStats> {
+        Some(self.stats)
+    }
+
+    fn cipher_stats(&self) -> Option<CipherStats> {
+        self.inner.cipher_stats()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coder::Uncoded;
+
+    // text-like bytes that compress, over several blocks.
+    fn payload() -> Vec<u8> {
+        b"the quick brown fox jumps over the lazy dog. "
+            .iter()
+            .copied()
+            .cycle()
+            .take(1000)
+            .collect()
+    }
+
+    #[test]
+    fn blocks_round_trip_smaller() {
+        let mut coder = Compressed::new(256, Box::new(Uncoded));
+        let data = payload();
+        let encoded = coder.encode(&data).unwrap();
+        assert!(encoded.len() < data.len());
+        assert_eq!(coder.decode(&encoded).unwrap(), data);
+        let stats = coder.compression_stats().unwrap();
+        assert_eq!(stats.input_bytes, 1000);
+        assert_eq!(stats.compressed_bytes, encoded.len() as u64);
+        assert_eq!((stats.blocks, stats.corrupted), (4, 0));
+    }
+
+    #[test]
+    fn empty_input_stays_empty() {
+        let mut coder = Compressed::new(256, Box::new(Uncoded));
+        let encoded = coder.encode(&[]).unwrap();
+        assert!(encoded.is_empty());
+        assert!(coder.decode(&encoded).unwrap().is_empty());
+        assert_eq!(coder.compression_stats().unwrap().blocks, 0);
+    }
+
+    #[test]
+    fn a_damaged_block_costs_only_itself() {
+        let mut coder = Compressed::new(256, Box::new(Uncoded));
+        let data = payload();
+        let mut encoded = coder.encode(&data).unwrap();
+        // inside the deflated bytes of the first block.
+        encoded[BLOCK_HEADER_BYTES + 4] ^= 0x10;
+        let decoded = coder.decode(&encoded).unwrap();
+        assert_eq!(decoded.len(), data.len());
+        assert_eq!(decoded[256..], data[256..]);
+        let stats = coder.compression_stats().unwrap();
+        assert_eq!((stats.blocks, stats.corrupted), (4, 1));
+    }
+}
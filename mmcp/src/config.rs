@@ -0,0 +1,434 @@
+use color_eyre::eyre::{eyre, Result, WrapErr};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::{
+    analytics::ReportFormat,
+    channel::{Channel, MarkovChain},
+    experiment::Experiment,
+    interleaver::{
+        BlockInterleaver, ByteInterleaver, ConvolutionalInterleaver, HelicalInterleaver,
+        Interleaver, NoInterleaver, SeededInterleaver,
+    },
+    pipeline::Files,
+};
+
+/// Read from the working directory unless another file is given.
+pub const CONFIG_FILE: &str = "mmcp.toml";
+
+/// Settings of a run, read from a TOML file. Every key is optional and falls
+/// back to what the binaries run without a file.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// File sent through the pipeline.
+    pub payload: PathBuf,
+    /// Where the decoded payload is written, with the number of the run and
+    /// the channel added to the file name.
+    pub output: PathBuf,
+    /// Seed of every channel unless one is given on the command line.
+    pub seed: Option<u64>,
+    /// Registry names of the coders to run instead of the fixed ones.
+    pub coders: Vec<String>,
+    /// Between the selected coders and the channel.
+    pub interleaver: InterleaverConfig,
+    pub channels: Vec<ChannelConfig>,
+    pub report: ReportConfig,
+    /// Limits the coded runs have to keep, or else the binary fails.
+    pub thresholds: Thresholds,
+    /// Named variations of the settings above, run with `--experiment`.
+    pub experiments: Vec<ExperimentConfig>,
+    /// Where the manifest of every experiment run is written.
+    pub manifest_dir: PathBuf,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        let files = Files::default();
+        Self {
+            payload: files.payload,
+            output: files.output,
+            seed: None,
+            coders: vec![],
+            interleaver: InterleaverConfig::None,
+            channels: default_channels(),
+            report: ReportConfig::default(),
+            thresholds: Thresholds::default(),
+            experiments: vec![],
+            manifest_dir: PathBuf::from("manifests"),
+        }
+    }
+}
+
+impl Config {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .wrap_err_with(|| format!("failed to read {}", path.display()))?;
+        let config: Self = toml::from_str(&text)
+            .wrap_err_with(|| format!("invalid configuration {}", path.display()))?;
+        config
+            .validate()
+            .wrap_err_with(|| format!("invalid configuration {}", path.display()))?;
+        Ok(config)
+    }
+
+    /// Fails naming the first key whose value no channel or interleaver can
+    /// be built from, which [`ChannelConfig::build`] and
+    /// [`InterleaverConfig::build`] would panic on.
+    pub fn validate(&self) -> Result<()> {
+        validate_channels(&self.channels, "channels")?;
+        self.interleaver
+            .validate()
+            .wrap_err("invalid interleaver")?;
+        for (index, experiment) in self.experiments.iter().enumerate() {
+            if let Some(channels) = &experiment.channels {
+                validate_channels(channels, &format!("experiments[{index}].channels"))?;
+            }
+            if let Some(interleaver) = experiment.interleaver {
+                interleaver
+                    .validate()
+                    .wrap_err_with(|| format!("invalid experiments[{index}].interleaver"))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// [`CONFIG_FILE`] if there is one, the defaults otherwise.
+    pub fn find() -> Result<Self> {
+        if Path::new(CONFIG_FILE).exists() {
+            Self::load(CONFIG_FILE)
+        } else {
+            Ok(Self::default())
+        }
+    }
+
+    pub fn channels(&self) -> Vec<Channel> {
+        self.channels.iter().map(ChannelConfig::build).collect()
+    }
+
+    /// The experiment of this name, its unset keys taken from the top level.
+    pub fn experiment(&self, name: &str) -> Result<Experiment> {
+        let experiment = self
+            .experiments
+            .iter()
+            .find(|experiment| experiment.name == name)
+            .ok_or_else(|| {
+                eyre!(
+                    "no experiment named {:?}, choose one of {}",
+                    name,
+                    self.experiments
+                        .iter()
+                        .map(|experiment| experiment.name.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            })?
+            .clone();
+        Ok(Experiment {
+            name: experiment.name,
+            payload: experiment.payload.unwrap_or_else(|| self.payload.clone()),
+            coders: experiment.coders.unwrap_or_else(|| self.coders.clone()),
+            interleaver: experiment.interleaver.unwrap_or(self.interleaver),
+            channels: experiment.channels.unwrap_or_else(|| self.channels.clone()),
+        })
+    }
+
+    /// Runs the experiment instead of the top level settings.
+    pub fn apply(&mut self, experiment: &Experiment) {
+        self.payload = experiment.payload.clone();
+        self.coders = experiment.coders.clone();
+        self.interleaver = experiment.interleaver;
+        self.channels = experiment.channels.clone();
+    }
+}
+
+/// The `[[channels]]` tables of a TOML file, without the other keys of a
+/// configuration.
+pub fn load_channels(path: impl AsRef<Path>) -> Result<Vec<ChannelConfig>> {
+    #[derive(Deserialize)]
+    #[serde(deny_unknown_fields)]
+    struct Channels {
+        channels: Vec<ChannelConfig>,
+    }
+
+    let path = path.as_ref();
+    let text = std::fs::read_to_string(path)
+        .wrap_err_with(|| format!("failed to read {}", path.display()))?;
+    let channels: Channels =
+        toml::from_str(&text).wrap_err_with(|| format!("invalid channels {}", path.display()))?;
+    validate_channels(&channels.channels, "channels")
+        .wrap_err_with(|| format!("invalid channels {}", path.display()))?;
+    Ok(channels.channels)
+}
+
+fn validate_channels(channels: &[ChannelConfig], key: &str) -> Result<()> {
+    for (index, channel) in channels.iter().enumerate() {
+        channel
+            .validate()
+            .wrap_err_with(|| format!("invalid {key}[{index}]"))?;
+    }
+    Ok(())
+}
+
+/// An entry of [`Config::experiments`].
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExperimentConfig {
+    pub name: String,
+    pub payload: Option<PathBuf>,
+    pub coders: Option<Vec<String>>,
+    pub interleaver: Option<InterleaverConfig>,
+    pub channels: Option<Vec<ChannelConfig>>,
+}
+
+/// A channel model and its parameters, as in [`crate::channel::Model`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "model", rename_all = "kebab-case", deny_unknown_fields)]
+pub enum ChannelConfig {
+    /// The original symmetric Gilbert-Elliott model, see [`Channel::new`].
+    Symmetric {
+        h: f64,
+        tau: f64,
+    },
+    GilbertElliott {
+        p: f64,
+        r: f64,
+        good_error: f64,
+        h: f64,
+    },
+    Awgn {
+        eb_n0_db: f64,
+    },
+    PacketLoss {
+        packet_bytes: usize,
+        loss: f64,
+    },
+    InsertionDeletion {
+        insertion: f64,
+        deletion: f64,
+    },
+    Markov {
+        error: Vec<f64>,
+        transitions: Vec<Vec<f64>>,
+    },
+    PeriodicFade {
+        period: u64,
+        fade: u64,
+        h: f64,
+    },
+}
+
+impl ChannelConfig {
+    /// Fails naming the key [`ChannelConfig::build`] can't build a channel
+    /// from.
+    pub fn validate(&self) -> Result<()> {
+        match self {
+            Self::PacketLoss {
+                packet_bytes: 0, ..
+            } => Err(eyre!("packet_bytes must be at least 1")),
+            Self::PeriodicFade { period: 0, .. } => Err(eyre!("period must be at least 1")),
+            Self::Markov { error, transitions } => {
+                if error.is_empty() {
+                    return Err(eyre!("error needs the probability of at least one state"));
+                }
+                if transitions.len() != error.len() {
+                    return Err(eyre!(
+                        "transitions needs a row per state of error, {} instead of {}",
+                        transitions.len(),
+                        error.len()
+                    ));
+                }
+                for (index, row) in transitions.iter().enumerate() {
+                    if row.len() != error.len() || (row.iter().sum::<f64>() - 1.0).abs() >= 1e-9 {
+                        return Err(eyre!(
+                            "transitions[{index}] must cover every state and sum to 1"
+                        ));
+                    }
+                }
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn build(&self) -> Channel {
+        match self.clone() {
+            Self::Symmetric { h, tau } => Channel::new(h, tau),
+            Self::GilbertElliott {
+                p,
+                r,
+                good_error,
+                h,
+            } => Channel::gilbert_elliott(p, r, good_error, h),
+            Self::Awgn { eb_n0_db } => Channel::awgn(eb_n0_db),
+            Self::PacketLoss { packet_bytes, loss } => Channel::packet_loss(packet_bytes, loss),
+            Self::InsertionDeletion {
+                insertion,
+                deletion,
+            } => Channel::insertion_deletion(insertion, deletion),
+            Self::Markov { error, transitions } => {
+                Channel::markov(MarkovChain::new(error, transitions))
+            }
+            Self::PeriodicFade { period, fade, h } => Channel::periodic_fade(period, fade, h),
+        }
+    }
+}
+
+/// The interleavers of [`crate::interleaver`].
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case", deny_unknown_fields)]
+pub enum InterleaverConfig {
+    None,
+    Block,
+    Convolutional { branches: usize, delay: usize },
+    ByteBlock { rows: usize, columns: usize },
+    Helical { rows: usize, columns: usize },
+    Seeded { seed: u64, block_bytes: usize },
+}
+
+impl InterleaverConfig {
+    /// Fails naming the key [`InterleaverConfig::build`] can't build an
+    /// interleaver from.
+    pub fn validate(&self) -> Result<()> {
+        match *self {
+            Self::Convolutional { branches: 0, .. } => Err(eyre!("branches must be at least 1")),
+            Self::ByteBlock { rows: 0, .. } | Self::Helical { rows: 0, .. } => {
+                Err(eyre!("rows must be at least 1"))
+            }
+            Self::ByteBlock { columns: 0, .. } | Self::Helical { columns: 0, .. } => {
+                Err(eyre!("columns must be at least 1"))
+            }
+            Self::Seeded { block_bytes: 0, .. } => Err(eyre!("block_bytes must be at least 1")),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn build(&self) -> Box<dyn Interleaver> {
+        match *self {
+            Self::None => Box::new(NoInterleaver),
+            Self::Block => Box::new(BlockInterleaver),
+            Self::Convolutional { branches, delay } => {
+                Box::new(ConvolutionalInterleaver::new(branches, delay))
+            }
+            Self::ByteBlock { rows, columns } => Box::new(ByteInterleaver::new(rows, columns)),
+            Self::Helical { rows, columns } => Box::new(HelicalInterleaver::new(rows, columns)),
+            Self::Seeded { seed, block_bytes } => {
+                Box::new(SeededInterleaver::new(seed, block_bytes))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ReportConfig {
+    pub format: ReportFormat,
+    /// Written to this file instead of standard output.
+    pub file: Option<PathBuf>,
+}
+
+/// The `[thresholds]` table, unset limits not checked.
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Thresholds {
+    /// Residual bit errors per channel bit error.
+    pub max_residual_error_ratio: Option<f64>,
+    /// Channel bits on top of the payload bits, per payload bit.
+    pub max_overhead: Option<f64>,
+    pub max_e2e_ms: Option<f64>,
+}
+
+// the channels every binary ran before there was a configuration.
+fn default_channels() -> Vec<ChannelConfig> {
+    vec![
+        ChannelConfig::Symmetric { h: 0.01, tau: 0.01 },
+        ChannelConfig::Symmetric { h: 0.1, tau: 0.05 },
+        ChannelConfig::Symmetric { h: 0.75, tau: 0.1 },
+        ChannelConfig::Symmetric { h: 0.5, tau: 0.5 },
+        ChannelConfig::Symmetric { h: 0.5, tau: 0.75 },
+        ChannelConfig::Symmetric { h: 0.75, tau: 0.90 },
+        ChannelConfig::GilbertElliott {
+            p: 0.001,
+            r: 0.1,
+            good_error: 0.0001,
+            h: 0.5,
+        },
+        ChannelConfig::GilbertElliott {
+            p: 0.0005,
+            r: 0.02,
+            good_error: 0.0,
+            h: 0.3,
+        },
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the whole chain of the error of a configuration, outermost first.
+    fn error(text: &str) -> String {
+        let config: Config = toml::from_str(text).unwrap();
+        format!("{:#}", config.validate().unwrap_err())
+    }
+
+    #[test]
+    fn the_defaults_are_valid() {
+        Config::default().validate().unwrap();
+        toml::from_str::<Config>(include_str!("../../mmcp.example.toml"))
+            .unwrap()
+            .validate()
+            .unwrap();
+    }
+
+    #[test]
+    fn channels_no_channel_can_be_built_from_are_rejected() {
+        let rejected = error(
+            "[[channels]]\nmodel = \"awgn\"\neb_n0_db = 3.0\n\
+             [[channels]]\nmodel = \"periodic-fade\"\nperiod = 0\nfade = 1\nh = 0.5\n",
+        );
+        assert!(rejected.contains("channels[1]"), "{rejected}");
+        assert!(rejected.contains("period"), "{rejected}");
+
+        let rejected =
+            error("[[channels]]\nmodel = \"packet-loss\"\npacket_bytes = 0\nloss = 0.1\n");
+        assert!(rejected.contains("packet_bytes"), "{rejected}");
+
+        let rejected = error(
+            "[[channels]]\nmodel = \"markov\"\nerror = [0.0, 0.5]\n\
+             transitions = [[0.9, 0.1], [0.5, 0.4]]\n",
+        );
+        assert!(rejected.contains("transitions[1]"), "{rejected}");
+        let rejected =
+            error("[[channels]]\nmodel = \"markov\"\nerror = [0.0, 0.5]\ntransitions = [[1.0]]\n");
+        assert!(rejected.contains("transitions"), "{rejected}");
+    }
+
+    #[test]
+    fn interleavers_no_interleaver_can_be_built_from_are_rejected() {
+        for (table, key) in [
+            (
+                "type = \"convolutional\"\nbranches = 0\ndelay = 2",
+                "branches",
+            ),
+            ("type = \"byte-block\"\nrows = 0\ncolumns = 4", "rows"),
+            ("type = \"helical\"\nrows = 4\ncolumns = 0", "columns"),
+            (
+                "type = \"seeded\"\nseed = 1\nblock_bytes = 0",
+                "block_bytes",
+            ),
+        ] {
+            let rejected = error(&format!("[interleaver]\n{table}\n"));
+            assert!(rejected.contains(key), "{rejected}");
+        }
+        let rejected = error(
+            "[[experiments]]\nname = \"fine\"\n\
+             interleaver = { type = \"seeded\", seed = 1, block_bytes = 0 }\n",
+        );
+        assert!(
+            rejected.contains("experiments[0].interleaver"),
+            "{rejected}"
+        );
+    }
+}
@@ -0,0 +1,225 @@
+use aes_gcm::{
+    aead::{Aead, KeyInit},
+    aes::Aes128,
+    Aes128Gcm,
+};
+use color_eyre::eyre::{eyre, Result};
+use ctr::cipher::{KeyIvInit, StreamCipher};
+
+use crate::coder::{
+    CipherStats, Coder, CompressionStats, FrameStats, LineStats, PacketStats, SegmentStats,
+};
+
+// the pipeline measures error propagation, not secrecy, so both ends share
+// a fixed key.
+const KEY: [u8; 16] = *b"mmcp pipeline 16";
+const TAG_BYTES: usize = 16;
+
+type Aes128Ctr = ctr::Ctr128BE<Aes128>;
+
+#[derive(Debug, Clone, Copy)]
+pub enum Cipher {
+    /// Authenticated: a frame with a single residual error fails its tag and
+    /// is rejected as a whole.
+    AesGcm,
+    /// Unauthenticated stream cipher: a residual error flips the same bit of
+    /// the plaintext and nothing else.
+    AesCtr,
+}
+
+/// Encrypts the payload in frames of `frame_bytes` bytes, each under a nonce
+/// made of its frame number, before the wrapped coder and decrypts after it.
+/// Rejected frames are delivered as zeros.
+pub struct Encrypted {
+    cipher: Cipher,
+    frame_bytes: usize,
+    inner: Box<dyn Coder>,
+    stats: CipherStats,
+}
+
+impl Encrypted {
+    pub fn new(cipher: Cipher, frame_bytes: usize, inner: Box<dyn Coder>) -> Self {
+        assert!(frame_bytes > 0, "frames must carry at least one byte");
+        Self {
+            cipher,
+            frame_bytes,
+            inner,
+            stats: CipherStats::default(),
+        }
+    }
+
+    // bytes a frame of `frame_bytes` plaintext bytes takes on the wire.
+    fn sealed_bytes(&self) -> usize {
+        match self.cipher {
+            Cipher::AesGcm => self.frame_bytes + TAG_BYTES,
+            Cipher::AesCtr => self.frame_bytes,
+        }
+    }
+}
+
+impl Coder for Encrypted {
+    fn name(&self) -> String {
+        let cipher = match self.cipher {
+            Cipher::AesGcm => "AES-GCM",
+            Cipher::AesCtr => "AES-CTR",
+        };
+        format!("{} + {}/{}", self.inner.name(), cipher, self.frame_bytes)
+    }
+
+    fn encode(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+        let mut sealed =
+            Vec::with_capacity(data.len().div_ceil(self.frame_bytes) * self.sealed_bytes());
+        for (number, frame) in data.chunks(self.frame_bytes).enumerate() {
+            match self.cipher {
+                Cipher::AesGcm => sealed.extend(
+                    Aes128Gcm::new(&KEY.into())
+                        .encrypt(&nonce(number).into(), frame)
+                        .map_err(|_| eyre!("frame {} could not be encrypted", number))?,
+                ),
+                Cipher::AesCtr => {
+                    let mut frame = frame.to_vec();
+                    keystream(number).apply_keystream(&mut frame);
+                    sealed.extend(frame);
+                }
+            }
+        }
+        self.inner.encode(&sealed)
+    }
+
+    fn decode(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+        let sealed = self.inner.decode(data)?;
+        self.stats = CipherStats::default();
+        let mut payload = Vec::with_capacity(sealed.len());
+        for (number, frame) in sealed.chunks(self.sealed_bytes()).enumerate() {
+            if matches!(self.cipher, Cipher::AesGcm) && frame.len() <= TAG_BYTES {
+                break;
+            }
+            self.stats.frames += 1;
+            match self.cipher {
+                Cipher::AesGcm => {
+                    match Aes128Gcm::new(&KEY.into()).decrypt(&nonce(number).into(), frame) {
+                        Ok(plaintext) => payload.extend(plaintext),
+                        Err(_) => {
+                            self.stats.rejected += 1;
+                            payload.resize(payload.len() + frame.len() - TAG_BYTES, 0);
+                        }
+                    }
+                }
+                Cipher::AesCtr => {
+                    let mut frame = frame.to_vec();
+                    keystream(number).apply_keystream(&mut frame);
+                    payload.extend(frame);
+                }
+            }
+        }
+        Ok(payload)
+    }
+
+    fn segment_stats(&self) -> Option<SegmentStats> {
+        self.inner.segment_stats()
+    }
+
+    fn layer_stats(&self) -> Vec<(String, SegmentStats)> {
+        self.inner.layer_stats()
+    }
+
+    fn frame_stats(&self) -> Option<FrameStats> {
+        self.inner.frame_stats()
+    }
+
+    fn packet_stats(&self) -> Option<PacketStats> {
+        self.inner.packet_stats()
+    }
+
+    fn line_stats(&self) -> Option<LineStats> {
+        self.inner.line_stats()
+    }
+
+    fn compression_stats(&self) -> Option<CompressionStats> {
+        self.inner.compression_stats()
+    }
+
+    fn cipher_stats(&self) -> Option<CipherStats> {
+        Some(self.stats)
+    }
+}
+
+// 96 bit nonce of a frame, its number in the low bytes.
+fn nonce(number: usize) -> [u8; 12] {
+    let mut nonce = [0; 12];
+    nonce[4..].copy_from_slice(&(number as u64).to_be_bytes());
+    nonce
+}
+
+// counter mode keystream of a frame, starting from its nonce.
+fn keystream(number: usize) -> Aes128Ctr {
+    let mut iv = [0; 16];
+    iv[..12].copy_from_slice(&nonce(number));
+    Aes128Ctr::new(&KEY.into(), &iv.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coder::Uncoded;
+
+    const FRAME_BYTES: usize = 32;
+
+    fn payload() -> Vec<u8> {
+        (0..3 * FRAME_BYTES as u8 + 5).collect()
+    }
+
+    #[test]
+    fn both_ciphers_round_trip() {
+        for (cipher, sealed) in [(Cipher::AesGcm, TAG_BYTES), (Cipher::AesCtr, 0)] {
+            let mut coder = Encrypted::new(cipher, FRAME_BYTES, Box::new(Uncoded));
+            let data = payload();
+            let encoded = coder.encode(&data).unwrap();
+            assert_eq!(encoded.len(), data.len() + 4 * sealed);
+            assert_ne!(encoded[..data.len()], data[..]);
+            assert_eq!(coder.decode(&encoded).unwrap(), data);
+            let stats = coder.cipher_stats().unwrap();
+            assert_eq!((stats.frames, stats.rejected), (4, 0));
+        }
+    }
+
+    #[test]
+    fn empty_input_stays_empty() {
+        for cipher in [Cipher::AesGcm, Cipher::AesCtr] {
+            let mut coder = Encrypted::new(cipher, FRAME_BYTES, Box::new(Uncoded));
+            let encoded = coder.encode(&[]).unwrap();
+            assert!(encoded.is_empty());
+            assert!(coder.decode(&encoded).unwrap().is_empty());
+            assert_eq!(coder.cipher_stats().unwrap().frames, 0);
+        }
+    }
+
+    #[test]
+    fn gcm_rejects_a_tampered_frame_as_a_whole() {
+        let mut coder = Encrypted::new(Cipher::AesGcm, FRAME_BYTES, Box::new(Uncoded));
+        let data = payload();
+        let mut encoded = coder.encode(&data).unwrap();
+        // a bit of the second frame.
+        encoded[FRAME_BYTES + TAG_BYTES + 3] ^= 0x04;
+        let decoded = coder.decode(&encoded).unwrap();
+        assert_eq!(decoded.len(), data.len());
+        assert!(decoded[FRAME_BYTES..2 * FRAME_BYTES]
+            .iter()
+            .all(|&byte| byte == 0));
+        assert_eq!(decoded[..FRAME_BYTES], data[..FRAME_BYTES]);
+        assert_eq!(decoded[2 * FRAME_BYTES..], data[2 * FRAME_BYTES..]);
+        assert_eq!(coder.cipher_stats().unwrap().rejected, 1);
+    }
+
+    #[test]
+    fn ctr_passes_a_tampered_bit_on_unnoticed() {
+        let mut coder = Encrypted::new(Cipher::AesCtr, FRAME_BYTES, Box::new(Uncoded));
+        let data = payload();
+        let mut encoded = coder.encode(&data).unwrap();
+        encoded[FRAME_BYTES + 3] ^= 0x04;
+        let mut expected = data.clone();
+        expected[FRAME_BYTES + 3] ^= 0x04;
+        assert_eq!(coder.decode(&encoded).unwrap(), expected);
+        assert_eq!(coder.cipher_stats().unwrap().rejected, 0);
+    }
+}
@@ -0,0 +1,107 @@
+use color_eyre::eyre::{Result, WrapErr};
+use plotters::prelude::*;
+use std::{
+    path::{Path, PathBuf},
+    sync::RwLock,
+};
+
+use crate::residuals::Residuals;
+
+// pixels across the heatmap. every pixel covers as many payload bits as it
+// takes to fit the payload into about a square.
+const WIDTH: usize = 512;
+
+// where the maps go, none unless asked for, settled with the options.
+static DIRECTORY: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+/// Writes the error map of every run analyzed from now on into the
+/// directory, or of none.
+pub fn set_directory(directory: Option<PathBuf>) {
+    *DIRECTORY.write().unwrap() = directory;
+}
+
+/// Writes the payload bits a run decoded wrong into the directory if there
+/// is one: `errors-<label>.bin` has a bit per payload bit, the payload xored
+/// with what was decoded, every bit missing from the decoded payload set.
+/// `errors-<label>.png` shows the share of the bits in error as a heatmap,
+/// a pixel per run of bits, row by row from the top left.
+pub fn save(label: &str, residuals: &Residuals) -> Result<()> {
+    let Some(directory) = DIRECTORY.read().unwrap().clone() else {
+        return Ok(());
+    };
+    std::fs::create_dir_all(&directory)?;
+    let length = residuals.payload_length() as usize;
+    let mut map = vec![0; length];
+    for (index, difference) in residuals.differences() {
+        map[index as usize] = difference;
+    }
+    map[length - residuals.missing_bytes() as usize..].fill(0xff);
+    let path = directory.join(format!("errors-{}.bin", label));
+    std::fs::write(&path, &map)
+        .wrap_err_with(|| format!("cannot write the error map {}", path.display()))?;
+    heatmap(&map, &directory.join(format!("errors-{}.png", label)))
+}
+
+fn heatmap(map: &[u8], path: &Path) -> Result<()> {
+    let bits = map.len() * 8;
+    let per_pixel = bits.div_ceil(WIDTH * WIDTH).max(1);
+    let pixels = bits.div_ceil(per_pixel);
+    let height = pixels.div_ceil(WIDTH).max(1);
+    let root = BitMapBackend::new(path, (WIDTH as u32, height as u32)).into_drawing_area();
+    root.fill(&WHITE)?;
+    for pixel in 0..pixels {
+        let errors = (pixel * per_pixel..((pixel + 1) * per_pixel).min(bits))
+            .filter(|&bit| map[bit / 8] & 0x80 >> (bit % 8) != 0)
+            .count();
+        if errors == 0 {
+            continue;
+        }
+        // the square root keeps a single error among many bits visible.
+        let share = (errors as f64 / per_pixel as f64).sqrt();
+        let fade = (255.0 * (1.0 - share)).round() as u8;
+        root.draw_pixel(
+            ((pixel % WIDTH) as i32, (pixel / WIDTH) as i32),
+            &RGBColor(255, fade, fade),
+        )?;
+    }
+    root.present()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipeline::{Source, FILES_LOCK};
+    use async_std::task;
+    use std::sync::Arc;
+
+    fn memory(bytes: &[u8]) -> Source {
+        Source::Memory {
+            name: PathBuf::from("memory"),
+            bytes: Arc::from(bytes),
+        }
+    }
+
+    #[test]
+    fn map_has_the_bits_decoded_wrong_and_the_missing_ones() {
+        // every run analyzed meanwhile would write its map here too.
+        let _lock = FILES_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let directory = tempfile::tempdir().unwrap();
+        let payload = [0u8; 8];
+        let mut decoded = [0u8; 6];
+        decoded[1] = 0x81;
+        let residuals =
+            task::block_on(Residuals::between(&memory(&payload), &memory(&decoded))).unwrap();
+
+        save("none", &residuals).unwrap();
+        assert!(!directory.path().join("errors-none.bin").exists());
+
+        set_directory(Some(directory.path().to_path_buf()));
+        let saved = save("test", &residuals);
+        set_directory(None);
+        saved.unwrap();
+        let map = std::fs::read(directory.path().join("errors-test.bin")).unwrap();
+        assert_eq!(map, [0, 0x81, 0, 0, 0, 0, 0xff, 0xff]);
+        assert!(directory.path().join("errors-test.png").exists());
+    }
+}
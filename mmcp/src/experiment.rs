@@ -0,0 +1,80 @@
+use color_eyre::eyre::{Result, WrapErr};
+use serde::{Deserialize, Serialize};
+use std::{
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    analytics::{Analytics, Summary},
+    config::{ChannelConfig, InterleaverConfig},
+};
+
+/// A named bundle of payload, coders and channels, defined in the
+/// configuration and run with `--experiment`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Experiment {
+    pub name: String,
+    pub payload: PathBuf,
+    pub coders: Vec<String>,
+    pub interleaver: InterleaverConfig,
+    pub channels: Vec<ChannelConfig>,
+}
+
+/// What an experiment run was and what it gave, written next to the others
+/// of its experiment. `--manifest` repeats the run from it, seed included.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Manifest {
+    /// Seconds since the Unix epoch the run finished at.
+    pub finished: u64,
+    #[serde(with = "decimal")]
+    pub seed: u64,
+    pub experiment: Experiment,
+    pub results: Vec<Summary>,
+}
+
+impl Manifest {
+    pub fn new(experiment: &Experiment, seed: u64, results: &[Analytics]) -> Self {
+        Self {
+            finished: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |time| time.as_secs()),
+            seed,
+            experiment: experiment.clone(),
+            results: results.iter().map(Analytics::summary).collect(),
+        }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .wrap_err_with(|| format!("failed to read {}", path.display()))?;
+        toml::from_str(&text).wrap_err_with(|| format!("invalid manifest {}", path.display()))
+    }
+
+    /// Writes the manifest into the directory as `<name>-<finished>.toml`, so
+    /// earlier runs of the experiment are kept, and returns its path.
+    pub fn save(&self, directory: impl AsRef<Path>) -> Result<PathBuf> {
+        let directory = directory.as_ref();
+        std::fs::create_dir_all(directory)?;
+        let path = directory.join(format!("{}-{}.toml", self.experiment.name, self.finished));
+        std::fs::write(&path, toml::to_string(self)?)
+            .wrap_err_with(|| format!("failed to write {}", path.display()))?;
+        Ok(path)
+    }
+}
+
+// TOML integers end at i64::MAX, seeds go up to u64::MAX.
+mod decimal {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&value.to_string())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(D::Error::custom)
+    }
+}
@@ -0,0 +1,73 @@
+use color_eyre::eyre::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::{adaptive::RateStats, arq::ArqStats, channel::Channel};
+
+/// Reverse direction of a duplex link, carrying acknowledgements from the
+/// decoder side back to the encoder side over a channel of its own.
+pub struct Feedback {
+    channel: Channel,
+    stats: FeedbackStats,
+}
+
+impl Feedback {
+    pub fn new(channel: Channel) -> Self {
+        Self {
+            channel,
+            stats: FeedbackStats::default(),
+        }
+    }
+
+    /// A reverse channel that delivers every message intact.
+    pub fn perfect() -> Self {
+        Self::new(Channel::new(0.0, 0.0))
+    }
+
+    /// Sends a message to the encoder side, returns what arrives there.
+    pub fn send(&mut self, message: &[u8]) -> Vec<u8> {
+        let received = self.channel.send(message);
+        self.stats.messages += 1;
+        self.stats.bytes += message.len() as u32;
+        self.stats.corrupted += (received != message) as u32;
+        received
+    }
+
+    pub fn stats(&self) -> FeedbackStats {
+        self.stats
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+pub struct FeedbackStats {
+    pub messages: u32,
+    pub bytes: u32,
+    /// Messages that reached the encoder side altered.
+    pub corrupted: u32,
+}
+
+/// A stage that drives the forward channel itself, so its receiver can
+/// answer over the feedback channel before the sender goes on. Driven by
+/// [`crate::pipeline::run_duplex`].
+pub trait Duplex {
+    /// Name shown in the report.
+    fn name(&self) -> String;
+
+    /// Delivers the data to the decoder side and returns what it ends up
+    /// with.
+    fn transfer(
+        &mut self,
+        data: &[u8],
+        forward: &mut Channel,
+        feedback: &mut Feedback,
+    ) -> Result<Vec<u8>>;
+
+    /// Retransmissions of the last `transfer` call, if the stage repeats frames.
+    fn arq_stats(&self) -> Option<ArqStats> {
+        None
+    }
+
+    /// Coder switches of the last `transfer` call, if the stage adapts its rate.
+    fn rate_stats(&self) -> Option<RateStats> {
+        None
+    }
+}
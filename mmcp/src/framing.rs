@@ -0,0 +1,492 @@
+use color_eyre::eyre::Result;
+
+use crate::coder::{Coder, FrameStats, PacketStats, SegmentStats};
+
+const CRC_BYTES: usize = 4;
+const STREAM_MAGIC: [u8; 4] = *b"MMCP";
+const STREAM_VERSION: u8 = 3;
+// magic, version, code, interleaver depth, payload length and shortening.
+const HEADER_BYTES: usize = 25;
+const HEADER_COPIES: usize = 3;
+// sequence number and length of the packetized stream.
+const PACKET_HEADER_BYTES: usize = 8;
+// CCSDS attached sync marker in front of every synchronized frame.
+const SYNC_WORD: [u8; 4] = [0x1A, 0xCF, 0xFC, 0x1D];
+// sync word bits that may differ from the marker for a match.
+const SYNC_TOLERANCE: usize = 3;
+// bits around the expected start of a frame searched before the lock is lost.
+const SYNC_WINDOW: usize = 16;
+const CRC32_TABLE: [u32; 256] = crc32_table();
+
+// reflected CRC-32 (IEEE 802.3) lookup table.
+const fn crc32_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+pub fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0u32, |crc, &byte| {
+        (crc >> 8) ^ CRC32_TABLE[((crc ^ byte as u32) & 0xFF) as usize]
+    })
+}
+
+/// Describes an encoded stream so the receiver can check it decodes what it
+/// thinks it does. The header travels in front of the payload through the
+/// coder, three times over, and is majority voted on receipt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreamHeader {
+    pub version: u8,
+    // CRC-32 of the coder name.
+    pub code: u32,
+    pub interleaver_depth: u32,
+    pub payload_length: u64,
+    // zero info bits of the coder's last codeword.
+    pub shortening: u32,
+}
+
+impl StreamHeader {
+    /// Header of a payload of `payload_length` bytes, the shortening of the
+    /// coder is taken for the framed stream it encodes.
+    pub fn new(coder: &dyn Coder, interleaver_depth: usize, payload_length: usize) -> Self {
+        Self::named(&coder.name(), interleaver_depth, payload_length)
+            .shortened(coder.shortening(Self::framed_bytes(payload_length)))
+    }
+
+    /// Header of a stream sent by a stage other than a single coder, which
+    /// does not shorten.
+    pub fn named(name: &str, interleaver_depth: usize, payload_length: usize) -> Self {
+        Self {
+            version: STREAM_VERSION,
+            code: crc32(name.as_bytes()),
+            interleaver_depth: interleaver_depth as u32,
+            payload_length: payload_length as u64,
+            shortening: 0,
+        }
+    }
+
+    fn shortened(self, shortening: u32) -> Self {
+        Self { shortening, ..self }
+    }
+
+    /// Length of a framed payload of `payload_length` bytes.
+    pub fn framed_bytes(payload_length: usize) -> usize {
+        HEADER_BYTES * HEADER_COPIES + payload_length
+    }
+
+    fn to_bytes(self) -> Vec<u8> {
+        let mut bytes = STREAM_MAGIC.to_vec();
+        bytes.push(self.version);
+        bytes.extend_from_slice(&self.code.to_be_bytes());
+        bytes.extend_from_slice(&self.interleaver_depth.to_be_bytes());
+        bytes.extend_from_slice(&self.payload_length.to_be_bytes());
+        bytes.extend_from_slice(&self.shortening.to_be_bytes());
+        bytes
+    }
+
+    pub fn frame(&self, payload: &[u8]) -> Vec<u8> {
+        let mut framed = self.to_bytes().repeat(HEADER_COPIES);
+        framed.extend_from_slice(payload);
+        framed
+    }
+
+    /// Splits off the header, which is `None` if magic or version do not match.
+    pub fn unframe(data: &[u8]) -> (Option<Self>, &[u8]) {
+        let Some((copies, payload)) = data.split_at_checked(HEADER_BYTES * HEADER_COPIES) else {
+            return (None, data);
+        };
+        let bytes = (0..HEADER_BYTES)
+            .map(|i| {
+                let (a, b, c) = (
+                    copies[i],
+                    copies[HEADER_BYTES + i],
+                    copies[2 * HEADER_BYTES + i],
+                );
+                (a & b) | (a & c) | (b & c)
+            })
+            .collect::<Vec<_>>();
+        if bytes[..4] != STREAM_MAGIC || bytes[4] != STREAM_VERSION {
+            return (None, payload);
+        }
+        let field = |i: usize| u32::from_be_bytes(bytes[i..i + 4].try_into().unwrap());
+        let header = Self {
+            version: bytes[4],
+            code: field(5),
+            interleaver_depth: field(9),
+            payload_length: u64::from_be_bytes(bytes[13..21].try_into().unwrap()),
+            shortening: field(21),
+        };
+        (Some(header), payload)
+    }
+
+    /// Whether a received header describes the same stream as this one, the
+    /// payload length is only known to the sender.
+    pub fn matches(&self, received: &Self) -> bool {
+        self.version == received.version
+            && self.code == received.code
+            && self.interleaver_depth == received.interleaver_depth
+            && self.shortening == received.shortening
+    }
+}
+
+/// Splits the payload into frames of `frame_size` bytes, appends a CRC-32 to
+/// every frame and hands the result to the wrapped coder.
+pub struct CrcFraming {
+    frame_size: usize,
+    inner: Box<dyn Coder>,
+    stats: FrameStats,
+}
+
+impl CrcFraming {
+    pub fn new(frame_size: usize, inner: Box<dyn Coder>) -> Self {
+        assert!(frame_size > 0, "frames must carry at least one byte");
+        Self {
+            frame_size,
+            inner,
+            stats: FrameStats::default(),
+        }
+    }
+}
+
+impl Coder for CrcFraming {
+    fn name(&self) -> String {
+        format!("{} + CRC-32/{}", self.inner.name(), self.frame_size)
+    }
+
+    fn encode(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+        let mut framed = Vec::with_capacity(data.len() / self.frame_size * CRC_BYTES + data.len());
+        for frame in data.chunks(self.frame_size) {
+            framed.extend_from_slice(frame);
+            framed.extend_from_slice(&crc32(frame).to_be_bytes());
+        }
+        self.inner.encode(&framed)
+    }
+
+    fn decode(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+        let framed = self.inner.decode(data)?;
+        self.stats = FrameStats {
+            frame_size: self.frame_size,
+            passed: vec![],
+        };
+        let mut payload = Vec::with_capacity(framed.len());
+        for frame in framed.chunks(self.frame_size + CRC_BYTES) {
+            if frame.len() <= CRC_BYTES {
+                break;
+            }
+            let (frame, crc) = frame.split_at(frame.len() - CRC_BYTES);
+            self.stats.passed.push(crc32(frame).to_be_bytes() == crc);
+            payload.extend_from_slice(frame);
+        }
+        Ok(payload)
+    }
+
+    fn segment_stats(&self) -> Option<SegmentStats> {
+        self.inner.segment_stats()
+    }
+
+    fn layer_stats(&self) -> Vec<(String, SegmentStats)> {
+        self.inner.layer_stats()
+    }
+
+    fn frame_stats(&self) -> Option<FrameStats> {
+        Some(self.stats.clone())
+    }
+}
+
+/// Splits the stream of the wrapped coder into packets of `packet_bytes`
+/// bytes, each led by its sequence number and the length of the stream.
+/// Packets missing on receipt reach the wrapped coder as erased bits.
+pub struct Packetizer {
+    packet_bytes: usize,
+    inner: Box<dyn Coder>,
+    stats: PacketStats,
+}
+
+impl Packetizer {
+    pub fn new(packet_bytes: usize, inner: Box<dyn Coder>) -> Self {
+        assert!(
+            packet_bytes > PACKET_HEADER_BYTES,
+            "packets must carry at least one byte of payload"
+        );
+        Self {
+            packet_bytes,
+            inner,
+            stats: PacketStats::default(),
+        }
+    }
+
+    fn payload_bytes(&self) -> usize {
+        self.packet_bytes - PACKET_HEADER_BYTES
+    }
+}
+
+impl Coder for Packetizer {
+    fn name(&self) -> String {
+        format!("{} in {} B packets", self.inner.name(), self.packet_bytes)
+    }
+
+    fn encode(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+        let encoded = self.inner.encode(data)?;
+        let mut packets = vec![];
+        for (sequence, payload) in encoded.chunks(self.payload_bytes()).enumerate() {
+            packets.extend_from_slice(&(sequence as u32).to_be_bytes());
+            packets.extend_from_slice(&(encoded.len() as u32).to_be_bytes());
+            packets.extend_from_slice(payload);
+            packets.resize(packets.len().next_multiple_of(self.packet_bytes), 0);
+        }
+        Ok(packets)
+    }
+
+    fn decode(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+        self.decode_soft(&certain_llrs(data))
+    }
+
+    fn decode_soft(&mut self, llrs: &[f32]) -> Result<Vec<u8>> {
+        let packets = llrs.chunks_exact(8 * self.packet_bytes).collect::<Vec<_>>();
+        let (stream, lost) = reassemble(&packets, self.payload_bytes());
+        self.stats = PacketStats { lost };
+        self.inner.decode_soft(&stream)
+    }
+
+    fn segment_stats(&self) -> Option<SegmentStats> {
+        self.inner.segment_stats()
+    }
+
+    fn layer_stats(&self) -> Vec<(String, SegmentStats)> {
+        self.inner.layer_stats()
+    }
+
+    fn frame_stats(&self) -> Option<FrameStats> {
+        self.inner.frame_stats()
+    }
+
+    fn packet_stats(&self) -> Option<PacketStats> {
+        Some(self.stats.clone())
+    }
+}
+
+/// Like [`Packetizer`] with a sync word in front of every frame of
+/// `frame_bytes` payload bytes. The receiver finds the frames by correlating
+/// with the sync word, so it regains alignment after bits were inserted or
+/// deleted. Frames it misses reach the wrapped coder as erased bits.
+pub struct FrameSync {
+    frame_bytes: usize,
+    inner: Box<dyn Coder>,
+    stats: PacketStats,
+}
+
+impl FrameSync {
+    pub fn new(frame_bytes: usize, inner: Box<dyn Coder>) -> Self {
+        assert!(frame_bytes > 0, "frames must carry at least one byte");
+        Self {
+            frame_bytes,
+            inner,
+            stats: PacketStats::default(),
+        }
+    }
+
+    fn frame_bits(&self) -> usize {
+        8 * (SYNC_WORD.len() + PACKET_HEADER_BYTES + self.frame_bytes)
+    }
+}
+
+impl Coder for FrameSync {
+    fn name(&self) -> String {
+        format!("{} + sync/{}", self.inner.name(), self.frame_bytes)
+    }
+
+    fn encode(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+        let encoded = self.inner.encode(data)?;
+        let mut frames = vec![];
+        for (sequence, payload) in encoded.chunks(self.frame_bytes).enumerate() {
+            frames.extend_from_slice(&SYNC_WORD);
+            frames.extend_from_slice(&(sequence as u32).to_be_bytes());
+            frames.extend_from_slice(&(encoded.len() as u32).to_be_bytes());
+            frames.extend_from_slice(payload);
+            frames.resize(frames.len().next_multiple_of(self.frame_bits() / 8), 0);
+        }
+        Ok(frames)
+    }
+
+    fn decode(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+        self.decode_soft(&certain_llrs(data))
+    }
+
+    fn decode_soft(&mut self, llrs: &[f32]) -> Result<Vec<u8>> {
+        let bits = llrs.iter().map(|&llr| llr < 0.0).collect::<Vec<_>>();
+        let sync = SYNC_WORD
+            .iter()
+            .flat_map(|&byte| crate::to_bits(byte))
+            .map(|bit| bit == 1)
+            .collect::<Vec<_>>();
+        let frame_bits = self.frame_bits();
+        let matches = |offset: usize| {
+            bits[offset..offset + sync.len()]
+                .iter()
+                .zip(&sync)
+                .filter(|(bit, sync)| bit != sync)
+                .count()
+                <= SYNC_TOLERANCE
+        };
+
+        // look around where the next frame should start, nearest first, and
+        // scan ahead for the sync word once the lock is lost.
+        let mut frames = vec![];
+        let mut expected = 0;
+        while expected + frame_bits <= bits.len() {
+            let last = bits.len() - frame_bits;
+            let mut window = (expected.saturating_sub(SYNC_WINDOW)
+                ..=(expected + SYNC_WINDOW).min(last))
+                .collect::<Vec<_>>();
+            window.sort_by_key(|&offset| offset.abs_diff(expected));
+            let Some(offset) = window
+                .into_iter()
+                .chain((expected + SYNC_WINDOW + 1)..=last)
+                .find(|&offset| matches(offset))
+            else {
+                break;
+            };
+            frames.push(&llrs[offset + sync.len()..offset + frame_bits]);
+            expected = offset + frame_bits;
+        }
+        let (stream, lost) = reassemble(&frames, self.frame_bytes);
+        self.stats = PacketStats { lost };
+        self.inner.decode_soft(&stream)
+    }
+
+    fn segment_stats(&self) -> Option<SegmentStats> {
+        self.inner.segment_stats()
+    }
+
+    fn layer_stats(&self) -> Vec<(String, SegmentStats)> {
+        self.inner.layer_stats()
+    }
+
+    fn frame_stats(&self) -> Option<FrameStats> {
+        self.inner.frame_stats()
+    }
+
+    // frames lost to desynchronization.
+    fn packet_stats(&self) -> Option<PacketStats> {
+        Some(self.stats.clone())
+    }
+}
+
+// ratios of bytes received without any doubt.
+pub(super) fn certain_llrs(data: &[u8]) -> Vec<f32> {
+    data.iter()
+        .flat_map(|&byte| crate::to_bits(byte))
+        .map(|bit| if bit == 0 { 1.0 } else { -1.0 })
+        .collect()
+}
+
+// puts the payloads of received packets, each led by its sequence number and
+// the stream length, back in order. missing packets are left as erased bits.
+fn reassemble(packets: &[&[f32]], payload_bytes: usize) -> (Vec<f32>, Vec<bool>) {
+    let header_bits = 8 * PACKET_HEADER_BYTES;
+    let mut lengths = vec![];
+    let mut received = vec![];
+    for packet in packets {
+        let header = crate::hard_decisions(&packet[..header_bits]);
+        let sequence = u32::from_be_bytes(header[..4].try_into().unwrap()) as usize;
+        lengths.push(u32::from_be_bytes(header[4..].try_into().unwrap()) as usize);
+        received.push((sequence, &packet[header_bits..]));
+    }
+    // the stream length travels in every packet, the median outvotes the
+    // corrupted ones.
+    lengths.sort_unstable();
+    let length = lengths.get(lengths.len() / 2).copied().unwrap_or(0);
+    let count = length.div_ceil(payload_bytes);
+    let mut stream = vec![0.0; 8 * count * payload_bytes];
+    let mut lost = vec![true; count];
+    for (sequence, payload) in received {
+        if sequence < count && lost[sequence] {
+            let start = 8 * sequence * payload_bytes;
+            stream[start..start + payload.len()].copy_from_slice(payload);
+            lost[sequence] = false;
+        }
+    }
+    stream.truncate(8 * length);
+    (stream, lost)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header() -> StreamHeader {
+        StreamHeader::named("Hamming(7,4)", 8, 123_456).shortened(3)
+    }
+
+    #[test]
+    fn header_round_trips() {
+        let framed = header().frame(b"payload");
+        assert_eq!(framed.len(), StreamHeader::framed_bytes(7));
+        let (received, payload) = StreamHeader::unframe(&framed);
+        assert_eq!(received, Some(header()));
+        assert_eq!(payload, b"payload");
+    }
+
+    #[test]
+    fn majority_vote_outvotes_one_corrupted_copy() {
+        let mut framed = header().frame(b"payload");
+        // every bit of one copy.
+        framed[..HEADER_BYTES]
+            .iter_mut()
+            .for_each(|byte| *byte ^= 0xff);
+        let (received, payload) = StreamHeader::unframe(&framed);
+        assert_eq!(received, Some(header()));
+        assert_eq!(payload, b"payload");
+        // or another bit of the same byte in each.
+        let mut framed = header().frame(b"payload");
+        framed[13] ^= 0x01;
+        framed[HEADER_BYTES + 13] ^= 0x02;
+        framed[2 * HEADER_BYTES + 13] ^= 0x80;
+        assert_eq!(StreamHeader::unframe(&framed).0, Some(header()));
+    }
+
+    #[test]
+    fn two_corrupted_copies_outvote_the_third() {
+        let mut framed = header().frame(b"");
+        // the same bit of the payload length in two copies.
+        framed[13 + 7] ^= 0x01;
+        framed[HEADER_BYTES + 13 + 7] ^= 0x01;
+        let (received, _) = StreamHeader::unframe(&framed);
+        let received = received.unwrap();
+        assert_eq!(received.payload_length, header().payload_length ^ 1);
+        assert!(header().matches(&received));
+    }
+
+    #[test]
+    fn broken_magic_or_short_stream_has_no_header() {
+        let mut framed = header().frame(b"payload");
+        for copy in 0..2 {
+            framed[copy * HEADER_BYTES] ^= 0xff;
+        }
+        assert_eq!(StreamHeader::unframe(&framed).0, None);
+        let short = &header().frame(b"")[..HEADER_BYTES * HEADER_COPIES - 1];
+        assert_eq!(StreamHeader::unframe(short), (None, short));
+    }
+
+    #[test]
+    fn headers_of_other_coders_do_not_match() {
+        let other = StreamHeader::named("SECDED(8,4)", 8, 123_456);
+        assert!(!header().matches(&other));
+        // the payload length is no part of the match.
+        assert!(header().matches(&StreamHeader::named("Hamming(7,4)", 8, 1).shortened(3)));
+    }
+}
@@ -0,0 +1,126 @@
+use color_eyre::eyre::{Result, WrapErr};
+use prettytable::{Row, Table};
+use rusqlite::{params, Connection};
+use std::{
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::analytics::Analytics;
+
+/// A SQLite database every run is added to, with the time it was recorded,
+/// for the trend of a coder over many invocations.
+pub struct History {
+    connection: Connection,
+}
+
+impl History {
+    /// Opens the database, creating it if there is none.
+    pub fn open(path: &Path) -> Result<Self> {
+        let connection = Connection::open(path)
+            .wrap_err_with(|| format!("cannot open the history {}", path.display()))?;
+        connection.execute_batch(
+            "CREATE TABLE IF NOT EXISTS runs (
+                id INTEGER PRIMARY KEY,
+                -- seconds since the Unix epoch.
+                recorded INTEGER NOT NULL,
+                coder TEXT NOT NULL,
+                interleaver TEXT NOT NULL,
+                payload TEXT NOT NULL,
+                channel TEXT NOT NULL,
+                -- the model and its parameters, seed included, as JSON.
+                channel_parameters TEXT NOT NULL,
+                input_bits INTEGER NOT NULL,
+                channel_bits INTEGER NOT NULL,
+                channel_errors INTEGER NOT NULL,
+                residual_errors INTEGER NOT NULL,
+                end_to_end_ms REAL NOT NULL,
+                -- the whole run as in the JSON report.
+                analytics TEXT NOT NULL
+            );",
+        )?;
+        Ok(Self { connection })
+    }
+
+    /// Adds the runs, all recorded now.
+    pub fn record(&mut self, analytics: &[Analytics]) -> Result<()> {
+        let recorded = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_secs());
+        let transaction = self.connection.transaction()?;
+        for run in analytics {
+            let summary = run.summary();
+            let json = serde_json::to_value(run)?;
+            transaction.execute(
+                "INSERT INTO runs (recorded, coder, interleaver, payload, channel,
+                    channel_parameters, input_bits, channel_bits, channel_errors,
+                    residual_errors, end_to_end_ms, analytics)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+                params![
+                    recorded,
+                    summary.coder,
+                    summary.interleaver,
+                    summary.payload,
+                    summary.channel,
+                    json["channel"].to_string(),
+                    summary.input_bits,
+                    summary.channel_bits,
+                    summary.channel_errors,
+                    summary.residual_errors,
+                    summary.end_to_end_ms,
+                    json.to_string(),
+                ],
+            )?;
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    /// A row per coder, interleaver, payload and channel with how often it
+    /// ran and its first and last run against each other.
+    pub fn trends(&self) -> Result<Table> {
+        let mut table = Table::new();
+        table.add_row(Row::from_iter(vec![
+            "Coder",
+            "Interleaver",
+            "Payload",
+            "Channel",
+            "Runs",
+            "First / Last Run",
+            "Residual Error Ratio",
+            "E2E Time",
+        ]));
+        let mut statement = self.connection.prepare(
+            "SELECT coder, interleaver, payload, channel, count(*),
+                datetime(min(recorded), 'unixepoch'), datetime(max(recorded), 'unixepoch'),
+                first_ratio, last_ratio, first_ms, last_ms
+            FROM (SELECT *,
+                first_value(ratio) OVER run AS first_ratio,
+                last_value(ratio) OVER run AS last_ratio,
+                first_value(end_to_end_ms) OVER run AS first_ms,
+                last_value(end_to_end_ms) OVER run AS last_ms
+                FROM (SELECT *, 1.0 * residual_errors / max(channel_errors, 1) AS ratio FROM runs)
+                WINDOW run AS (PARTITION BY coder, interleaver, payload, channel ORDER BY id
+                    ROWS BETWEEN UNBOUNDED PRECEDING AND UNBOUNDED FOLLOWING))
+            GROUP BY coder, interleaver, payload, channel
+            ORDER BY min(id)",
+        )?;
+        let mut rows = statement.query([])?;
+        while let Some(row) = rows.next()? {
+            let text = |index| row.get::<_, String>(index);
+            let (first_ratio, last_ratio): (f64, f64) = (row.get(7)?, row.get(8)?);
+            let (first_ms, last_ms): (f64, f64) = (row.get(9)?, row.get(10)?);
+            table.add_row(Row::from_iter(vec![
+                text(0)?,
+                text(1)?,
+                text(2)?,
+                text(3)?,
+                row.get::<_, u64>(4)?.to_string(),
+                format!("{} / {}", text(5)?, text(6)?),
+                format!("{:.3}% -> {:.3}%", first_ratio * 100.0, last_ratio * 100.0),
+                format!("{:.3} ms -> {:.3} ms", first_ms, last_ms),
+            ]));
+        }
+        Ok(table)
+    }
+}
@@ -0,0 +1,493 @@
+use crate::rng::SplitMix64;
+
+// seed and payload length of the seeded interleaver, sent three times and
+// majority voted on receipt.
+const SEEDED_HEADER_BYTES: usize = 16;
+const SEEDED_HEADER_COPIES: usize = 3;
+
+/// Reorders the encoded bytes before the channel and restores their order
+/// afterwards, so bursts of channel errors are spread over many codewords.
+pub trait Interleaver {
+    fn name(&self) -> String;
+    /// Number of codeword bytes or bits a burst is spread over.
+    fn depth(&self) -> usize;
+    fn interleave(&self, data: &[u8]) -> Vec<u8>;
+    fn deinterleave(&self, data: &[u8]) -> Vec<u8>;
+    /// Same as `deinterleave` for a log likelihood ratio per bit, eight per
+    /// byte.
+    fn deinterleave_soft(&self, llrs: &[f32]) -> Vec<f32>;
+}
+
+/// Groups the ratios of every byte, so byte level interleavers can move them.
+pub fn byte_llrs(llrs: &[f32]) -> Vec<[f32; 8]> {
+    llrs.chunks_exact(8)
+        .map(|llrs| llrs.try_into().unwrap())
+        .collect()
+}
+
+/// Sends the encoded bytes as they are.
+pub struct NoInterleaver;
+
+impl Interleaver for NoInterleaver {
+    fn name(&self) -> String {
+        "None".to_string()
+    }
+
+    fn depth(&self) -> usize {
+        1
+    }
+
+    fn interleave(&self, data: &[u8]) -> Vec<u8> {
+        data.to_vec()
+    }
+
+    fn deinterleave(&self, data: &[u8]) -> Vec<u8> {
+        data.to_vec()
+    }
+
+    fn deinterleave_soft(&self, llrs: &[f32]) -> Vec<f32> {
+        llrs.to_vec()
+    }
+}
+
+/// Bit level block interleaver: every block of 8 bytes is transposed as an
+/// 8x8 bit matrix, so a burst of up to 8 bits hits 8 different bytes.
+pub struct BlockInterleaver;
+
+impl Interleaver for BlockInterleaver {
+    fn name(&self) -> String {
+        "Block(8x8 bit)".to_string()
+    }
+
+    fn depth(&self) -> usize {
+        8
+    }
+
+    fn interleave(&self, data: &[u8]) -> Vec<u8> {
+        // zero padding to make the number of bytes a multiple of 8, the
+        // length header of the pipeline drops it again after decoding.
+        let mut data = data.to_vec();
+        data.resize(data.len().next_multiple_of(8), 0);
+        data.chunks_exact(8).flat_map(transpose_block).collect()
+    }
+
+    // the transposition is its own inverse.
+    fn deinterleave(&self, data: &[u8]) -> Vec<u8> {
+        self.interleave(data)
+    }
+
+    // bit i of byte j swaps places with bit j of byte i, counting bits from
+    // the most significant one while the ratios start at the least.
+    fn deinterleave_soft(&self, llrs: &[f32]) -> Vec<f32> {
+        let mut llrs = llrs.to_vec();
+        llrs.resize(llrs.len().next_multiple_of(64), 0.0);
+        let mut output = llrs.clone();
+        for (block, output) in llrs.chunks_exact(64).zip(output.chunks_exact_mut(64)) {
+            for i in 0..8 {
+                for j in 0..8 {
+                    output[8 * i + 7 - j] = block[8 * j + 7 - i];
+                }
+            }
+        }
+        output
+    }
+}
+
+// interleave 8 bytes of data.
+fn transpose_block(block: &[u8]) -> Vec<u8> {
+    let mut interleave = vec![];
+    let mut interleaved_byte = 0b0000_0000;
+    let mut count = 0b0u8;
+    for i in 0..8 {
+        for byte in block {
+            interleaved_byte |= ((byte >> (7 - i)) & 1) << (7 - count);
+            count += 1;
+            if count == 8 {
+                // we have interleaved 8 bits
+                interleave.push(interleaved_byte);
+                interleaved_byte = 0b0000_0000;
+                count = 0;
+            }
+        }
+    }
+    interleave
+}
+
+/// Forney convolutional interleaver: byte n is sent on branch n mod branches,
+/// branch j delays its bytes by j * delay positions of that branch. The
+/// stream grows by the largest delay, the empty slots are sent as zeros.
+pub struct ConvolutionalInterleaver {
+    branches: usize,
+    delay: usize,
+}
+
+impl ConvolutionalInterleaver {
+    pub fn new(branches: usize, delay: usize) -> Self {
+        assert!(branches > 0, "convolutional interleaver needs a branch");
+        Self { branches, delay }
+    }
+
+    fn shift(&self, index: usize) -> usize {
+        index % self.branches * self.delay * self.branches
+    }
+
+    fn tail(&self) -> usize {
+        (self.branches - 1) * self.delay * self.branches
+    }
+}
+
+impl Interleaver for ConvolutionalInterleaver {
+    fn name(&self) -> String {
+        format!("Convolutional({}x{})", self.branches, self.delay)
+    }
+
+    fn depth(&self) -> usize {
+        self.branches
+    }
+
+    fn interleave(&self, data: &[u8]) -> Vec<u8> {
+        let mut output = vec![0; data.len() + self.tail()];
+        for (index, &byte) in data.iter().enumerate() {
+            output[index + self.shift(index)] = byte;
+        }
+        output
+    }
+
+    fn deinterleave(&self, data: &[u8]) -> Vec<u8> {
+        let len = data.len().saturating_sub(self.tail());
+        (0..len)
+            .map(|index| data[index + self.shift(index)])
+            .collect()
+    }
+
+    fn deinterleave_soft(&self, llrs: &[f32]) -> Vec<f32> {
+        let bytes = byte_llrs(llrs);
+        let len = bytes.len().saturating_sub(self.tail());
+        (0..len)
+            .flat_map(|index| bytes[index + self.shift(index)])
+            .collect()
+    }
+}
+
+/// Byte level block interleaver: full blocks of rows x columns bytes are
+/// written row by row and read column by column, a trailing partial block
+/// is passed through unchanged.
+pub struct ByteInterleaver {
+    rows: usize,
+    columns: usize,
+}
+
+impl ByteInterleaver {
+    /// Spreads a burst of up to `rows` bytes over as many rows.
+    pub fn new(rows: usize, columns: usize) -> Self {
+        assert!(rows > 0 && columns > 0, "empty interleaver blocks");
+        Self { rows, columns }
+    }
+}
+
+impl Interleaver for ByteInterleaver {
+    fn name(&self) -> String {
+        format!("Block({}x{})", self.rows, self.columns)
+    }
+
+    fn depth(&self) -> usize {
+        self.rows
+    }
+
+    fn interleave(&self, data: &[u8]) -> Vec<u8> {
+        transpose_bytes(data, self.rows, self.columns)
+    }
+
+    fn deinterleave(&self, data: &[u8]) -> Vec<u8> {
+        transpose_bytes(data, self.columns, self.rows)
+    }
+
+    fn deinterleave_soft(&self, llrs: &[f32]) -> Vec<f32> {
+        transpose_bytes(&byte_llrs(llrs), self.columns, self.rows).concat()
+    }
+}
+
+fn transpose_bytes<T: Copy>(data: &[T], rows: usize, columns: usize) -> Vec<T> {
+    let block = rows * columns;
+    let mut output = Vec::with_capacity(data.len());
+    let mut chunks = data.chunks_exact(block);
+    for chunk in &mut chunks {
+        output.extend((0..block).map(|i| chunk[(i % rows) * columns + i / rows]));
+    }
+    output.extend_from_slice(chunks.remainder());
+    output
+}
+
+/// Helical interleaver: full blocks of rows x columns bytes are written row
+/// by row and read along the diagonals, every row shifted by one column
+/// against the previous one. A trailing partial block is passed through.
+pub struct HelicalInterleaver {
+    rows: usize,
+    columns: usize,
+}
+
+impl HelicalInterleaver {
+    /// Spreads a burst of up to `rows` bytes over as many rows and columns.
+    pub fn new(rows: usize, columns: usize) -> Self {
+        assert!(rows > 0 && columns > 0, "empty interleaver blocks");
+        Self { rows, columns }
+    }
+
+    // input index of output byte i within a block.
+    fn source(&self, i: usize) -> usize {
+        let (row, column) = (i % self.rows, i / self.rows);
+        row * self.columns + (column + row) % self.columns
+    }
+}
+
+impl Interleaver for HelicalInterleaver {
+    fn name(&self) -> String {
+        format!("Helical({}x{})", self.rows, self.columns)
+    }
+
+    fn depth(&self) -> usize {
+        self.rows
+    }
+
+    fn interleave(&self, data: &[u8]) -> Vec<u8> {
+        let block = self.rows * self.columns;
+        let mut output = Vec::with_capacity(data.len());
+        let mut chunks = data.chunks_exact(block);
+        for chunk in &mut chunks {
+            output.extend((0..block).map(|i| chunk[self.source(i)]));
+        }
+        output.extend_from_slice(chunks.remainder());
+        output
+    }
+
+    fn deinterleave(&self, data: &[u8]) -> Vec<u8> {
+        self.restore(data)
+    }
+
+    fn deinterleave_soft(&self, llrs: &[f32]) -> Vec<f32> {
+        self.restore(&byte_llrs(llrs)).concat()
+    }
+}
+
+impl HelicalInterleaver {
+    fn restore<T: Copy>(&self, data: &[T]) -> Vec<T> {
+        let block = self.rows * self.columns;
+        let mut output = data.to_vec();
+        for (chunk, output) in data.chunks_exact(block).zip(output.chunks_exact_mut(block)) {
+            for (i, &byte) in chunk.iter().enumerate() {
+                output[self.source(i)] = byte;
+            }
+        }
+        output
+    }
+}
+
+/// Pseudo random permutation of every block of bytes. The seed travels in a
+/// header in front of the stream, so the receiver rebuilds the permutation
+/// from the stream alone.
+pub struct SeededInterleaver {
+    seed: u64,
+    block_bytes: usize,
+}
+
+impl SeededInterleaver {
+    /// Permutes blocks of `block_bytes` bytes by the permutation drawn from
+    /// `seed`, and the shorter last block by one of its own.
+    pub fn new(seed: u64, block_bytes: usize) -> Self {
+        assert!(block_bytes > 0, "empty interleaver blocks");
+        Self { seed, block_bytes }
+    }
+
+    // Fisher-Yates shuffle, output byte i is input byte permutation[i].
+    fn permutation(seed: u64, len: usize) -> Vec<usize> {
+        let mut rng = SplitMix64::new(seed);
+        let mut permutation = (0..len).collect::<Vec<_>>();
+        for i in (1..len).rev() {
+            permutation.swap(i, rng.below(i + 1));
+        }
+        permutation
+    }
+
+    fn blocks(&self, seed: u64, len: usize) -> impl Iterator<Item = Vec<usize>> {
+        let full = Self::permutation(seed, self.block_bytes);
+        let tail = Self::permutation(seed, len % self.block_bytes);
+        (0..len / self.block_bytes)
+            .map(move |_| full.clone())
+            .chain(Some(tail).filter(|tail| !tail.is_empty()))
+    }
+}
+
+impl Interleaver for SeededInterleaver {
+    fn name(&self) -> String {
+        format!("Seeded({} B, {:#x})", self.block_bytes, self.seed)
+    }
+
+    fn depth(&self) -> usize {
+        self.block_bytes
+    }
+
+    fn interleave(&self, data: &[u8]) -> Vec<u8> {
+        let mut header = self.seed.to_be_bytes().to_vec();
+        header.extend_from_slice(&(data.len() as u64).to_be_bytes());
+        let mut output = header.repeat(SEEDED_HEADER_COPIES);
+        for (block, permutation) in data
+            .chunks(self.block_bytes)
+            .zip(self.blocks(self.seed, data.len()))
+        {
+            output.extend(permutation.iter().map(|&i| block[i]));
+        }
+        output
+    }
+
+    fn deinterleave(&self, data: &[u8]) -> Vec<u8> {
+        if data.len() < SEEDED_HEADER_BYTES * SEEDED_HEADER_COPIES {
+            return vec![];
+        }
+        let (copies, data) = data.split_at(SEEDED_HEADER_BYTES * SEEDED_HEADER_COPIES);
+        let (seed, len) = Self::header(copies);
+        self.restore(seed, len, data)
+    }
+
+    // the header is decided bit by bit, the payload keeps its ratios.
+    fn deinterleave_soft(&self, llrs: &[f32]) -> Vec<f32> {
+        if llrs.len() < 8 * SEEDED_HEADER_BYTES * SEEDED_HEADER_COPIES {
+            return vec![];
+        }
+        let (copies, llrs) = llrs.split_at(8 * SEEDED_HEADER_BYTES * SEEDED_HEADER_COPIES);
+        let (seed, len) = Self::header(&crate::hard_decisions(copies));
+        self.restore(seed, len, &byte_llrs(llrs)).concat()
+    }
+}
+
+impl SeededInterleaver {
+    // majority voted seed and payload length.
+    fn header(copies: &[u8]) -> (u64, usize) {
+        let header = (0..SEEDED_HEADER_BYTES)
+            .map(|i| {
+                let (a, b, c) = (
+                    copies[i],
+                    copies[SEEDED_HEADER_BYTES + i],
+                    copies[2 * SEEDED_HEADER_BYTES + i],
+                );
+                (a & b) | (a & c) | (b & c)
+            })
+            .collect::<Vec<_>>();
+        let seed = u64::from_be_bytes(header[..8].try_into().unwrap());
+        // a length beyond memory is cut to what arrived anyway.
+        let len = u64::from_be_bytes(header[8..].try_into().unwrap())
+            .try_into()
+            .unwrap_or(usize::MAX);
+        (seed, len)
+    }
+
+    fn restore<T: Copy + Default>(&self, seed: u64, len: usize, data: &[T]) -> Vec<T> {
+        // bytes the inner decoder appended beyond the payload are dropped.
+        let len = len.min(data.len());
+        let mut output = vec![T::default(); len];
+        let mut offset = 0;
+        for (block, permutation) in data[..len]
+            .chunks(self.block_bytes)
+            .zip(self.blocks(seed, len))
+        {
+            for (&byte, &i) in block.iter().zip(&permutation) {
+                output[offset + i] = byte;
+            }
+            offset += block.len();
+        }
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn interleavers() -> Vec<Box<dyn Interleaver>> {
+        vec![
+            Box::new(NoInterleaver),
+            Box::new(BlockInterleaver),
+            Box::new(ConvolutionalInterleaver::new(4, 3)),
+            Box::new(ByteInterleaver::new(4, 5)),
+            Box::new(HelicalInterleaver::new(4, 5)),
+            Box::new(SeededInterleaver::new(0x5EED, 16)),
+        ]
+    }
+
+    #[test]
+    fn deinterleaving_restores_every_length() {
+        for interleaver in interleavers() {
+            for len in [0, 1, 7, 20, 21, 64, 100] {
+                let data = (0..len as u8).collect::<Vec<_>>();
+                let interleaved = interleaver.interleave(&data);
+                let mut restored = interleaver.deinterleave(&interleaved);
+                // the block interleaver pads, the pipeline header drops it.
+                restored.truncate(len);
+                assert_eq!(restored, data, "{} of {} bytes", interleaver.name(), len);
+
+                let llrs = interleaved
+                    .iter()
+                    .flat_map(|&byte| (0..8).map(move |i| (byte >> i & 1) as f32))
+                    .map(|bit| 1.0 - 2.0 * bit)
+                    .collect::<Vec<_>>();
+                let mut restored = crate::hard_decisions(&interleaver.deinterleave_soft(&llrs));
+                restored.truncate(len);
+                assert_eq!(restored, data, "soft {}", interleaver.name());
+            }
+        }
+    }
+
+    #[test]
+    fn byte_interleaver_spreads_a_burst_over_the_rows() {
+        let interleaver = ByteInterleaver::new(4, 5);
+        let data = (0..20).collect::<Vec<u8>>();
+        let interleaved = interleaver.interleave(&data);
+        // the first bytes sent are the first column.
+        assert_eq!(interleaved[..4], [0, 5, 10, 15]);
+        let helical = HelicalInterleaver::new(4, 5).interleave(&data);
+        assert_eq!(helical[..4], [0, 6, 12, 18]);
+    }
+
+    #[test]
+    fn seeded_interleaver_reads_the_seed_from_the_stream() {
+        let data = (0..100).collect::<Vec<u8>>();
+        let interleaved = SeededInterleaver::new(7, 16).interleave(&data);
+        assert_ne!(
+            interleaved[SEEDED_HEADER_COPIES * SEEDED_HEADER_BYTES..],
+            data
+        );
+        // a receiver built with another seed follows the header.
+        let mut corrupted = interleaved.clone();
+        corrupted[0] ^= 0xff;
+        assert_eq!(SeededInterleaver::new(8, 16).deinterleave(&corrupted), data);
+    }
+
+    #[test]
+    fn empty_blocks_are_rejected() {
+        let constructors: [fn() -> Box<dyn Interleaver>; 5] = [
+            || Box::new(ByteInterleaver::new(0, 5)),
+            || Box::new(ByteInterleaver::new(4, 0)),
+            || Box::new(HelicalInterleaver::new(0, 5)),
+            || Box::new(HelicalInterleaver::new(4, 0)),
+            || Box::new(SeededInterleaver::new(7, 0)),
+        ];
+        for constructor in constructors {
+            assert!(std::panic::catch_unwind(constructor).is_err());
+        }
+    }
+
+    #[test]
+    fn seeded_interleaver_keeps_lengths_beyond_32_bits() {
+        let interleaver = SeededInterleaver::new(7, 16);
+        let mut interleaved = interleaver.interleave(&[1, 2, 3]);
+        // a length the stream cannot hold is cut to what arrived.
+        for copy in 0..SEEDED_HEADER_COPIES {
+            let at = copy * SEEDED_HEADER_BYTES + 8;
+            interleaved[at..at + 8].copy_from_slice(&(u32::MAX as u64 + 3).to_be_bytes());
+        }
+        assert_eq!(
+            SeededInterleaver::header(&interleaved).1 as u64,
+            u32::MAX as u64 + 3
+        );
+        assert_eq!(interleaver.deinterleave(&interleaved).len(), 3);
+    }
+}
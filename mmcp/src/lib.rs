@@ -1,28 +1,652 @@
+pub mod adaptive;
 pub mod analytics;
+pub mod arq;
+pub mod audio;
+pub mod bench;
+pub mod build_info;
+pub mod bursts;
 pub mod channel;
+pub mod coder;
+pub mod compression;
+pub mod config;
+pub mod encryption;
+pub mod error_map;
+pub mod experiment;
+pub mod feedback;
+pub mod framing;
+#[cfg(feature = "history")]
+pub mod history;
+pub mod interleaver;
+pub mod line_coding;
+pub mod media;
+pub mod memory;
+pub mod metrics;
+pub mod monte_carlo;
 pub mod pipeline;
+pub mod plot;
+pub mod plugin;
+pub mod progress;
+pub mod puncturing;
+pub mod registry;
+pub mod residuals;
+pub mod rng;
+#[cfg(feature = "wasm")]
+pub mod sandbox;
+pub mod sweep;
+pub mod theory;
+pub mod trials;
 pub use std::time::Instant;
 
-use channel::Channel;
+use analytics::{Analytics, ReportFormat};
+use async_std::task;
+use channel::{Channel, MarkovChain, TraceFormat};
+use clap::Parser;
+use color_eyre::eyre::{eyre, Result, WrapErr};
+use config::Config;
+use experiment::{Experiment, Manifest};
+use pipeline::{Files, RunFuture};
+use rand::{rngs::StdRng, RngCore, SeedableRng};
+use std::{
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+};
+use tracing::Level;
+use tracing_subscriber::{
+    filter::Targets,
+    fmt::{self, format::FmtSpan},
+    prelude::*,
+};
+
+/// Command line options shared by the pipeline binaries.
+#[derive(Debug, Parser)]
+#[command(version)]
+pub struct Options {
+    /// File sent through the pipeline instead of the configured payload.
+    #[arg(long)]
+    pub input: Option<PathBuf>,
+    /// Runs every file in this directory as the payload, one after the
+    /// other, and adds a report of the errors per payload and coder.
+    #[arg(long, conflicts_with = "input")]
+    pub payload_dir: Option<PathBuf>,
+    /// Writes the decoded payloads into this directory, as result with the
+    /// run, the channel and the extension of the input.
+    #[arg(long)]
+    pub output_dir: Option<PathBuf>,
+    /// Keeps the payload in memory, reading it once, and writes no files
+    /// but the report, the decoded payloads only compared with it.
+    #[arg(long)]
+    pub in_memory: bool,
+    /// Sends this many random bytes drawn from the seed instead of a payload
+    /// file, in memory.
+    #[arg(long, value_name = "BYTES", conflicts_with_all = ["input", "payload_dir"])]
+    pub random_payload: Option<usize>,
+    /// Runs over the channels of this TOML file, `[[channels]]` tables as in
+    /// the configuration, instead of the configured ones.
+    #[arg(long)]
+    pub channels: Option<PathBuf>,
+    /// Seed of every channel, drawn at random if not given. The report shows
+    /// the seed, so a run can be replayed exactly.
+    #[arg(long)]
+    pub seed: Option<u64>,
+    /// Repeats every run this many times, the seed one higher each time, and
+    /// reports the mean residual errors and times of every coder and channel
+    /// over the trials with their 95% confidence interval.
+    #[arg(
+        long,
+        value_name = "N",
+        default_value = "1",
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    pub trials: u64,
+    /// Format of the report: table, csv, json, md or html.
+    #[arg(long, alias = "report")]
+    pub report_format: Option<ReportFormat>,
+    /// Writes a line per run with the plain figures, times in seconds, to
+    /// this CSV file as well, for spreadsheets.
+    #[arg(long, value_name = "PATH")]
+    pub csv: Option<PathBuf>,
+    /// Compares two reports written with `--report json` instead of running
+    /// anything, a row per run of either with its changes.
+    #[arg(long, num_args = 2, value_names = ["BEFORE", "AFTER"])]
+    pub compare: Vec<PathBuf>,
+    /// Adds every run to this SQLite database, with the time and the
+    /// channel parameters.
+    #[cfg(feature = "history")]
+    #[arg(long, value_name = "PATH")]
+    pub history: Option<PathBuf>,
+    /// Shows how the runs in the database of `--history` changed over time
+    /// instead of running anything.
+    #[cfg(feature = "history")]
+    #[arg(long, requires = "history")]
+    pub show_history: bool,
+    /// Runs the coders over a grid of channel parameters instead of the
+    /// fixed channels, only the selected ones in binaries with a registry
+    /// of coders.
+    #[arg(long)]
+    pub sweep: bool,
+    /// Plots the residual bit error rate of the sweep over that of the
+    /// channel to this file, a curve per coder, as SVG or PNG by the
+    /// extension.
+    #[arg(long, value_name = "PATH", requires = "sweep")]
+    pub plot: Option<PathBuf>,
+    /// Times the coders over random payloads of these sizes in bytes,
+    /// separated by commas, and sends every payload over the channels for
+    /// the errors next to the throughput. Runs the selected coders, or else
+    /// every one, in binaries with a registry of coders.
+    #[arg(long, value_name = "BYTES", value_delimiter = ',')]
+    pub bench: Vec<usize>,
+    /// Untimed rounds of encoding and decoding before those of `--bench`.
+    #[arg(long, value_name = "ROUNDS", default_value = "1")]
+    pub bench_warmup: usize,
+    /// Timed rounds of encoding and decoding of `--bench`, the throughput
+    /// their average.
+    #[arg(long, value_name = "ROUNDS", default_value = "5")]
+    pub bench_iterations: usize,
+    /// Runs the coders over each channel until their residual errors over
+    /// the trials add up to this many, the seed one higher every trial, and
+    /// reports the estimate of the trials instead of a row per trial. The
+    /// trials run in memory and write no decoded payloads. Runs the selected
+    /// coders, or else every one, in binaries with a registry of coders.
+    #[arg(long, value_name = "ERRORS")]
+    pub target_errors: Option<u64>,
+    /// Trials of `--target-errors` per coder and channel at most.
+    #[arg(
+        long,
+        value_name = "N",
+        default_value = "1000",
+        requires = "target_errors"
+    )]
+    pub max_trials: u64,
+    /// Tests for every two coders run over the same channels with the same
+    /// seeds, so with the same errors, whether one left significantly fewer
+    /// residual errors, over the trials or else over blocks of the payload,
+    /// and reports it after the report.
+    #[arg(long)]
+    pub significance: bool,
+    /// Runs only the coder registered under this name, in binaries with a
+    /// registry of coders. May be given several times, the report then has
+    /// a row per coder and channel.
+    #[arg(long = "coder")]
+    pub coders: Vec<String>,
+    /// Loads a coder from this shared library into the registry, runs the
+    /// loaded coders unless one is selected. May be given several times.
+    #[arg(long = "plugin")]
+    pub plugins: Vec<PathBuf>,
+    /// Runs this coder compiled to WebAssembly in a sandbox instead of the
+    /// fixed coders. May be given several times.
+    #[cfg(feature = "wasm")]
+    #[arg(long = "wasm")]
+    pub wasm: Vec<PathBuf>,
+    /// Fuel, roughly instructions, a sandboxed coder may use per call.
+    #[cfg(feature = "wasm")]
+    #[arg(long, default_value = "20000000000")]
+    pub wasm_fuel: u64,
+    /// Memory a sandboxed coder may grow to, in MiB.
+    #[cfg(feature = "wasm")]
+    #[arg(long, default_value = "256")]
+    pub wasm_memory: usize,
+    /// Writes the error trace of every run into this directory, the n-th row
+    /// of the report to run-<n>.trace.
+    #[arg(long)]
+    pub capture: Option<PathBuf>,
+    /// Writes a map of the residual errors of every run into this directory,
+    /// a bit per payload bit set where it was decoded wrong, and a heatmap
+    /// of it as PNG, named after the run as the decoded payloads are.
+    #[arg(long, value_name = "DIRECTORY")]
+    pub error_maps: Option<PathBuf>,
+    /// Replays an error trace instead of the fixed channels.
+    #[arg(long)]
+    pub replay: Option<PathBuf>,
+    /// Format of the replayed trace: positions (also csv) or bitmap.
+    #[arg(long, default_value = "positions")]
+    pub trace_format: TraceFormat,
+    /// Connects the stages of runs built with `pipeline!` through queues of
+    /// this many items, a full queue holding back the stage before it, and
+    /// reports how full the queues got.
+    #[arg(long, value_name = "ITEMS")]
+    pub queue: Option<usize>,
+    /// Runs up to this many channels at once, each on a thread of its own,
+    /// one per core if not given. Runs sharing a coder, as the fixed runs of
+    /// mmcp-impl, still run one after the other. Runs at the same time have
+    /// no peak memory or allocations of their own, the report shows none.
+    #[arg(long, short)]
+    pub jobs: Option<usize>,
+    /// Prints nothing but the report, no progress of the runs.
+    #[arg(long, short, conflicts_with = "verbose")]
+    pub quiet: bool,
+    /// Traces the stages of every run and what the coders did on standard
+    /// error, given twice batch by batch and block by block. `RUST_LOG`
+    /// filters the traces instead if set.
+    #[arg(long, short, action = clap::ArgAction::Count)]
+    pub verbose: u8,
+    /// Leaves out the uncoded baseline row of every channel.
+    #[arg(long)]
+    pub no_baseline: bool,
+    /// Reads the configuration from this file instead of mmcp.toml.
+    #[arg(long = "config")]
+    pub config_file: Option<PathBuf>,
+    /// Runs the experiment of this name from the configuration and writes
+    /// its manifest.
+    #[arg(long)]
+    pub experiment: Option<String>,
+    /// Repeats the experiment run recorded in this manifest, with its seed
+    /// unless another one is given.
+    #[arg(long)]
+    pub manifest: Option<PathBuf>,
+    #[arg(skip)]
+    pub config: Config,
+    /// The experiment run, its settings already in `config`.
+    #[arg(skip)]
+    pub experiment_run: Option<Experiment>,
+    /// The trial running, counted from zero, see [`Options::start_trial`].
+    #[arg(skip)]
+    pub trial: u64,
+}
+
+impl Options {
+    /// Parses the command line, reads the configuration and settles the
+    /// seed, so every channel of the process shares it. The command line
+    /// wins over the configuration.
+    pub fn parse() -> Result<Self> {
+        <Self as Parser>::parse().settle()
+    }
+
+    /// Like [`Options::parse`] for options parsed as part of the arguments
+    /// of a binary.
+    pub fn settle(self) -> Result<Self> {
+        let mut options = self;
+        options.config = match &options.config_file {
+            Some(path) => Config::load(path)?,
+            None => Config::find()?,
+        };
+        let mut seed = options.config.seed;
+        if let Some(path) = &options.manifest {
+            let manifest = Manifest::load(path)?;
+            seed = Some(manifest.seed);
+            options.experiment_run = Some(manifest.experiment);
+        } else if let Some(name) = &options.experiment {
+            options.experiment_run = Some(options.config.experiment(name)?);
+        }
+        if let Some(experiment) = &options.experiment_run {
+            options.config.apply(experiment);
+        }
+        if let Some(input) = &options.input {
+            options.config.payload = input.clone();
+        }
+        if let Some(directory) = &options.output_dir {
+            std::fs::create_dir_all(directory)?;
+            let mut output = directory.join("result");
+            if let Some(extension) = options.config.payload.extension() {
+                output.set_extension(extension);
+            }
+            options.config.output = output;
+        }
+        if let Some(path) = &options.channels {
+            options.config.channels = config::load_channels(path)?;
+        }
+        if let Some(format) = options.report_format {
+            options.config.report.format = format;
+        }
+        options.seed = Some(options.seed.or(seed).unwrap_or_else(rand::random));
+        pipeline::set_queue_capacity(options.queue.unwrap_or(0));
+        progress::set_enabled(!options.quiet);
+        error_map::set_directory(options.error_maps.clone());
+        trace(options.verbose)?;
+        pipeline::set_files(options.files(&options.config.payload, options.config.output.clone())?);
+        Ok(options)
+    }
+
+    /// The seed of the trial running, the seed given in the first.
+    pub fn seed(&self) -> u64 {
+        self.seed.unwrap_or_default().wrapping_add(self.trial)
+    }
+
+    /// Seeds the runs from now on for the trial, counted from zero, up to
+    /// `--trials`. The random payload is drawn anew with the next payload
+    /// used.
+    pub fn start_trial(&mut self, trial: u64) {
+        self.trial = trial;
+    }
+
+    /// The fixed channels, or the replayed trace, each prepared.
+    pub fn channels(&self) -> Result<Vec<Channel>> {
+        let channels = match &self.replay {
+            Some(trace) => vec![Channel::import(trace, self.trace_format)?],
+            None => self.config.channels(),
+        };
+        Ok(channels
+            .into_iter()
+            .map(|channel| self.prepare(channel))
+            .collect())
+    }
+
+    /// Seeds the channel and captures its errors if asked to.
+    pub fn prepare(&self, channel: Channel) -> Channel {
+        let channel = channel.with_seed(self.seed());
+        match self.capture {
+            Some(_) => channel.with_trace_capture(),
+            None => channel,
+        }
+    }
+
+    /// The files of `--payload-dir` in name order, or else the payload. None
+    /// with `--compare` or `--show-history`, which run nothing.
+    pub fn payloads(&self) -> Result<Vec<PathBuf>> {
+        if !self.compare.is_empty() {
+            return Ok(vec![]);
+        }
+        #[cfg(feature = "history")]
+        if self.show_history {
+            return Ok(vec![]);
+        }
+        let Some(directory) = &self.payload_dir else {
+            return Ok(vec![self.config.payload.clone()]);
+        };
+        let mut payloads = vec![];
+        for entry in std::fs::read_dir(directory)? {
+            let path = entry?.path();
+            if path.is_file() {
+                payloads.push(path);
+            }
+        }
+        payloads.sort();
+        Ok(payloads)
+    }
+
+    /// Sends this payload in the runs from now on, decoded into a file with
+    /// its extension.
+    pub fn use_payload(&self, payload: &Path) -> Result<()> {
+        let mut output = self.config.output.clone();
+        match payload.extension() {
+            Some(extension) => output.set_extension(extension),
+            None => output.set_extension(""),
+        };
+        pipeline::set_files(self.files(payload, output)?);
+        Ok(())
+    }
+
+    // the files of runs sending the payload, read into memory if asked to.
+    fn files(&self, payload: &Path, output: PathBuf) -> Result<Files> {
+        if let Some(bytes) = self.random_payload {
+            let mut random = vec![0; bytes];
+            StdRng::seed_from_u64(self.seed()).fill_bytes(&mut random);
+            return Ok(Files::in_memory(format!("random-{}", bytes), random));
+        }
+        let memory = match self.in_memory {
+            true => Some(
+                std::fs::read(payload)
+                    .wrap_err_with(|| format!("failed to read {}", payload.display()))?
+                    .into(),
+            ),
+            false => None,
+        };
+        Ok(Files {
+            payload: payload.to_path_buf(),
+            output,
+            memory,
+        })
+    }
+
+    /// Runs `run` over every channel, up to `--jobs` at once on threads of
+    /// their own, and adds a report row per channel in the order given. The
+    /// index of the channel tells the run what to send over it.
+    pub async fn run_parallel<F>(
+        &self,
+        channels: Vec<Channel>,
+        results: &mut Vec<Analytics>,
+        run: F,
+    ) -> Result<()>
+    where
+        F: for<'a> Fn(usize, &'a mut Channel) -> RunFuture<'a> + Sync,
+    {
+        let jobs = self
+            .jobs
+            .unwrap_or_else(|| std::thread::available_parallelism().map_or(1, |cores| cores.get()));
+        let first = pipeline::reserve_runs(channels.len());
+        let channels: Vec<_> = channels.into_iter().map(Mutex::new).collect();
+        let next = AtomicUsize::new(0);
+        let mut runs = std::thread::scope(|scope| {
+            let workers: Vec<_> = (0..jobs.clamp(1, channels.len().max(1)))
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut runs = vec![];
+                        loop {
+                            let index = next.fetch_add(1, Ordering::Relaxed);
+                            let Some(channel) = channels.get(index) else {
+                                return runs;
+                            };
+                            pipeline::number_next_run(first + index);
+                            let mut channel = channel.lock().unwrap();
+                            runs.push((index, task::block_on(run(index, &mut channel))));
+                        }
+                    })
+                })
+                .collect();
+            workers
+                .into_iter()
+                .flat_map(|worker| worker.join().unwrap())
+                .collect::<Vec<_>>()
+        });
+        runs.sort_by_key(|&(index, _)| index);
+        for (channel, (_, run_metrics)) in channels.into_iter().zip(runs) {
+            let channel = channel.into_inner().unwrap();
+            self.save_trace(results.len(), &channel)?;
+            results.push(analytics::analyze(&channel, run_metrics?).await?);
+        }
+        Ok(())
+    }
+
+    /// Sends the payload over the channel prepared by the options without
+    /// any coding, one report row the coded rows of the channel can be
+    /// compared against. Does nothing with `--no-baseline`.
+    pub async fn baseline(&self, channel: Channel, results: &mut Vec<Analytics>) -> Result<()> {
+        if self.no_baseline {
+            return Ok(());
+        }
+        let mut channel = self.prepare(channel);
+        let run_metrics = pipeline::run_uncoded(&mut channel).await?;
+        self.save_trace(results.len(), &channel)?;
+        results.push(analytics::analyze(&channel, run_metrics).await?);
+        Ok(())
+    }
+
+    #[cfg(feature = "wasm")]
+    pub fn sandbox_limits(&self) -> sandbox::SandboxLimits {
+        sandbox::SandboxLimits {
+            fuel: self.wasm_fuel,
+            memory_bytes: self.wasm_memory << 20,
+        }
+    }
+
+    /// Writes the report as configured, and the manifest when running an
+    /// experiment. Fails if a run crossed one of the thresholds.
+    pub fn report(&self, results: &[Analytics]) -> Result<()> {
+        if let [before, after] = &self.compare[..] {
+            return analytics::compare(before, after);
+        }
+        #[cfg(feature = "history")]
+        if let Some(path) = &self.history {
+            let mut history = history::History::open(path)?;
+            if self.show_history {
+                analytics::print(history.trends()?);
+                return Ok(());
+            }
+            history.record(results)?;
+        }
+        // the rows of the Monte Carlo trials only tell something summed up
+        // per channel, so the trials report takes the place of the report.
+        match self.target_errors {
+            Some(_) => analytics::write_trials_report(
+                results,
+                self.config.report.format,
+                self.config.report.file.as_deref(),
+            )?,
+            None => analytics::write_report(
+                results,
+                self.config.report.format,
+                self.config.report.file.as_deref(),
+            )?,
+        }
+        if let Some(path) = &self.csv {
+            analytics::write_csv(results, path)?;
+        }
+        if let Some(path) = &self.plot {
+            plot::ber_curves(results, path)?;
+        }
+        if self.payload_dir.is_some() {
+            let file = self.beside_report("payloads");
+            analytics::write_payload_report(results, self.config.report.format, file.as_deref())?;
+        }
+        if self.trials > 1 && self.target_errors.is_none() {
+            let file = self.beside_report("trials");
+            analytics::write_trials_report(results, self.config.report.format, file.as_deref())?;
+        }
+        if self.significance {
+            let file = self.beside_report("significance");
+            analytics::write_significance_report(
+                results,
+                self.config.report.format,
+                file.as_deref(),
+            )?;
+        }
+        // the seed of the first trial, which the others follow.
+        if let Some(experiment) = &self.experiment_run {
+            let seed = self.seed.unwrap_or_default();
+            let path = Manifest::new(experiment, seed, results).save(&self.config.manifest_dir)?;
+            if !self.quiet {
+                eprintln!("manifest written to {}", path.display());
+            }
+        }
+        let failed = analytics::failed_thresholds(results, &self.config.thresholds);
+        if !failed.is_empty() {
+            return Err(eyre!(
+                "{} thresholds failed:\n{}",
+                failed.len(),
+                failed.join("\n")
+            ));
+        }
+        Ok(())
+    }
+
+    // next to the report file, with the suffix added to its name.
+    fn beside_report(&self, suffix: &str) -> Option<PathBuf> {
+        self.config.report.file.as_ref().map(|file| {
+            let mut name = file.file_stem().unwrap_or_default().to_os_string();
+            name.push(format!("-{}", suffix));
+            let mut path = file.with_file_name(name);
+            if let Some(extension) = file.extension() {
+                path.set_extension(extension);
+            }
+            path
+        })
+    }
+
+    /// Saves the error trace of the n-th run if asked to.
+    pub fn save_trace(&self, run: usize, channel: &Channel) -> Result<()> {
+        match &self.capture {
+            Some(directory) => {
+                std::fs::create_dir_all(directory)?;
+                channel.save_trace(directory.join(format!("run-{}.trace", run)))
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+// traces the runs on standard error as `RUST_LOG` filters them, or else the
+// crates of the pipeline at the level of the verbosity. the spans come with
+// the time spent in them once they close.
+fn trace(verbose: u8) -> Result<()> {
+    let targets: Targets = match std::env::var("RUST_LOG") {
+        Ok(filter) => filter.parse().wrap_err("invalid RUST_LOG")?,
+        Err(_) => {
+            let level = match verbose {
+                0 => return Ok(()),
+                1 => Level::DEBUG,
+                _ => Level::TRACE,
+            };
+            // every crate named mmcp something.
+            Targets::new().with_target("mmcp", level)
+        }
+    };
+    // settling again keeps the subscriber of the first time.
+    let _ = tracing_subscriber::registry()
+        .with(
+            fmt::layer()
+                .with_writer(std::io::stderr)
+                .with_span_events(FmtSpan::CLOSE),
+        )
+        .with(targets)
+        .try_init();
+    Ok(())
+}
 
 pub fn to_bits(byte: u8) -> Vec<u8> {
-    (0..8).into_iter().map(|i| (byte >> i) & 0b01).collect()
+    (0..8).map(|i| (byte >> i) & 0b01).collect()
 }
 
 pub fn to_byte(bits: &[u8]) -> u8 {
-    (0..bits.len())
-        .into_iter()
-        .map(|i| bits[i] << i)
+    bits.iter()
+        .enumerate()
+        .map(|(i, bit)| bit << i)
         .fold(0, |a, b| a | b)
 }
 
-pub fn channels() -> Vec<Channel> {
+// packs the sign of every log likelihood ratio into bytes, in `to_bits` order.
+pub fn hard_decisions(llrs: &[f32]) -> Vec<u8> {
+    llrs.chunks(8)
+        .map(|llrs| {
+            let bits = llrs
+                .iter()
+                .map(|&llr| (llr < 0.0) as u8)
+                .collect::<Vec<_>>();
+            to_byte(&bits)
+        })
+        .collect()
+}
+
+// BPSK over AWGN from 0 to 10 dB Eb/N0, for bit error rate curves.
+pub fn awgn_channels() -> Vec<Channel> {
+    (0..=5)
+        .map(|step| Channel::awgn(2.0 * step as f64))
+        .collect()
+}
+
+// channels whose error probability changes over time: short and long
+// periodic fades, a chain of clear, fair and deeply faded states and one
+// alternating between clear and noisy stretches of about 100,000 bits.
+pub fn fading_channels() -> Vec<Channel> {
+    vec![
+        Channel::periodic_fade(1024, 8, 0.5),
+        Channel::periodic_fade(4096, 64, 0.5),
+        Channel::markov(MarkovChain::new(
+            vec![0.0, 0.01, 0.5],
+            vec![
+                vec![0.999, 0.001, 0.0],
+                vec![0.01, 0.985, 0.005],
+                vec![0.0, 0.05, 0.95],
+            ],
+        )),
+        Channel::markov(MarkovChain::new(
+            vec![0.0001, 0.01],
+            vec![vec![0.99999, 0.00001], vec![0.00001, 0.99999]],
+        )),
+    ]
+}
+
+// channels mild enough for retransmissions to get frames through.
+pub fn arq_channels() -> Vec<Channel> {
     vec![
         Channel::new(0.01, 0.01),
-        Channel::new(0.1, 0.05),
-        Channel::new(0.75, 0.1),
-        Channel::new(0.5, 0.5),
-        Channel::new(0.5, 0.75),
-        Channel::new(0.75, 0.90),
+        Channel::gilbert_elliott(0.001, 0.1, 0.0001, 0.5),
+        Channel::gilbert_elliott(0.0005, 0.02, 0.0, 0.3),
     ]
 }
+
+// the channels of the default configuration.
+pub fn channels() -> Vec<Channel> {
+    Config::default().channels()
+}
@@ -0,0 +1,218 @@
+use color_eyre::eyre::Result;
+
+use crate::{
+    coder::{Coder, CodewordStats, FrameStats, LineStats, PacketStats, SegmentStats},
+    to_bits, to_byte,
+};
+
+// 6 bit sub-blocks abcdei of the low five bits EDCBA for a negative running
+// disparity, a first. a positive disparity sends the complement of the
+// unbalanced ones and of D.07.
+const FIVE_SIX: [u8; 32] = [
+    0b100111, 0b011101, 0b101101, 0b110001, 0b110101, 0b101001, 0b011001, 0b111000, 0b111001,
+    0b100101, 0b010101, 0b110100, 0b001101, 0b101100, 0b011100, 0b010111, 0b011011, 0b100011,
+    0b010011, 0b110010, 0b001011, 0b101010, 0b011010, 0b111010, 0b110011, 0b100110, 0b010110,
+    0b110110, 0b001110, 0b101110, 0b011110, 0b101011,
+];
+// 4 bit sub-blocks fghj of the high three bits HGF, likewise with D.x.3.
+const THREE_FOUR: [u8; 8] = [
+    0b1011, 0b1001, 0b0101, 0b1100, 0b1101, 0b1010, 0b0110, 0b1110,
+];
+// D.x.7 alternative that avoids a run of five equal bits after some x.
+const ALTERNATE_SEVEN: u8 = 0b0111;
+
+/// Line codes the stream of the wrapped coder with 8b/10b: every byte goes
+/// out as a 10 bit code group chosen by the running disparity, so the line
+/// stays DC balanced. The receiver counts code groups that do not exist and
+/// valid ones arriving against the running disparity. Invalid groups decode
+/// to zero bytes.
+pub struct EightBTenB {
+    inner: Box<dyn Coder>,
+    // byte and following disparity of every code group, for a negative and
+    // a positive running disparity before it.
+    groups: Vec<[Option<(u8, bool)>; 2]>,
+    stats: LineStats,
+}
+
+impl EightBTenB {
+    pub fn new(inner: Box<dyn Coder>) -> Self {
+        let mut groups = vec![[None; 2]; 1 << 10];
+        for positive in [false, true] {
+            for byte in 0..=u8::MAX {
+                let (group, next) = code_group(byte, positive);
+                groups[group as usize][positive as usize] = Some((byte, next));
+            }
+        }
+        Self {
+            inner,
+            groups,
+            stats: LineStats::default(),
+        }
+    }
+}
+
+impl Coder for EightBTenB {
+    fn name(&self) -> String {
+        format!("{} + 8b/10b", self.inner.name())
+    }
+
+    fn encode(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+        let encoded = self.inner.encode(data)?;
+        let mut bits = Vec::with_capacity(10 * encoded.len());
+        let mut positive = false;
+        for &byte in &encoded {
+            let group;
+            (group, positive) = code_group(byte, positive);
+            bits.extend((0..10).rev().map(|i| (group >> i) as u8 & 1));
+        }
+        Ok(bits.chunks(8).map(to_byte).collect())
+    }
+
+    fn decode(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+        let bits = data
+            .iter()
+            .flat_map(|&byte| to_bits(byte))
+            .collect::<Vec<_>>();
+        self.stats = LineStats::default();
+        let mut decoded = Vec::with_capacity(bits.len() / 10);
+        let mut positive = false;
+        for bits in bits.chunks_exact(10) {
+            let group = bits.iter().fold(0, |group, &bit| group << 1 | bit as usize);
+            self.stats.symbols += 1;
+            let byte = match self.groups[group] {
+                [Some(expected), _] if !positive => Some(expected),
+                [_, Some(expected)] if positive => Some(expected),
+                [Some(other), _] | [_, Some(other)] => {
+                    self.stats.disparity_errors += 1;
+                    Some(other)
+                }
+                [None, None] => None,
+            };
+            match byte {
+                Some((byte, next)) => {
+                    decoded.push(byte);
+                    positive = next;
+                }
+                // an invalid group still tells which way the line drifted.
+                None => {
+                    self.stats.invalid += 1;
+                    decoded.push(0);
+                    match group.count_ones() {
+                        6.. => positive = true,
+                        ..=4 => positive = false,
+                        _ => {}
+                    }
+                }
+            }
+        }
+        self.inner.decode(&decoded)
+    }
+
+    fn shortening(&self, data_bytes: usize) -> u32 {
+        self.inner.shortening(data_bytes)
+    }
+
+    fn codeword_bits(&self) -> Option<usize> {
+        self.inner.codeword_bits()
+    }
+
+    fn codeword_stats(&self) -> Option<CodewordStats> {
+        self.inner.codeword_stats()
+    }
+
+    fn segment_stats(&self) -> Option<SegmentStats> {
+        self.inner.segment_stats()
+    }
+
+    fn layer_stats(&self) -> Vec<(String, SegmentStats)> {
+        self.inner.layer_stats()
+    }
+
+    fn frame_stats(&self) -> Option<FrameStats> {
+        self.inner.frame_stats()
+    }
+
+    fn packet_stats(&self) -> Option<PacketStats> {
+        self.inner.packet_stats()
+    }
+
+    fn line_stats(&self) -> Option<LineStats> {
+        Some(self.stats)
+    }
+}
+
+// code group abcdeifghj of a byte and whether the running disparity is
+// positive after it.
+fn code_group(byte: u8, positive: bool) -> (u16, bool) {
+    let (x, y) = (byte & 0x1f, byte >> 5);
+    let (six, positive) = sub_block(FIVE_SIX[x as usize], 6, positive);
+    let alternate = y == 7
+        && if positive {
+            matches!(x, 11 | 13 | 14)
+        } else {
+            matches!(x, 17 | 18 | 20)
+        };
+    let four = if alternate {
+        ALTERNATE_SEVEN
+    } else {
+        THREE_FOUR[y as usize]
+    };
+    let (four, positive) = sub_block(four, 4, positive);
+    ((six as u16) << 4 | four as u16, positive)
+}
+
+// the sub-block for the running disparity, which flips after unbalanced ones.
+fn sub_block(code: u8, width: u32, positive: bool) -> (u8, bool) {
+    let unbalanced = 2 * code.count_ones() != width;
+    // D.07 and D.x.3 are balanced but still alternate.
+    let alternating = matches!((width, code), (6, 0b111000) | (4, 0b1100));
+    let code = if positive && (unbalanced || alternating) {
+        !code & ((1 << width) - 1)
+    } else {
+        code
+    };
+    (code, positive ^ unbalanced)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coder::Uncoded;
+
+    #[test]
+    fn every_byte_round_trips_dc_balanced() {
+        let mut coder = EightBTenB::new(Box::new(Uncoded));
+        let data = (0..=u8::MAX).chain(0..=u8::MAX).collect::<Vec<_>>();
+        let encoded = coder.encode(&data).unwrap();
+        assert_eq!(encoded.len(), 10 * data.len() / 8);
+        let ones = encoded.iter().map(|byte| byte.count_ones()).sum::<u32>();
+        assert!((ones as i64 - (4 * encoded.len()) as i64).abs() <= 1);
+        assert_eq!(coder.decode(&encoded).unwrap(), data);
+        let stats = coder.line_stats().unwrap();
+        assert_eq!(stats.symbols, data.len() as u32);
+        assert_eq!(stats.invalid + stats.disparity_errors, 0);
+    }
+
+    #[test]
+    fn empty_input_stays_empty() {
+        let mut coder = EightBTenB::new(Box::new(Uncoded));
+        assert!(coder.encode(&[]).unwrap().is_empty());
+        assert!(coder.decode(&[]).unwrap().is_empty());
+        assert_eq!(coder.line_stats().unwrap().symbols, 0);
+    }
+
+    #[test]
+    fn flipped_bits_show_as_line_errors() {
+        let mut coder = EightBTenB::new(Box::new(Uncoded));
+        let data = vec![0x00; 8];
+        let mut encoded = coder.encode(&data).unwrap();
+        for byte in encoded.iter_mut().step_by(3) {
+            *byte ^= 0x01;
+        }
+        let decoded = coder.decode(&encoded).unwrap();
+        assert_eq!(decoded.len(), data.len());
+        assert_ne!(decoded, data);
+        let stats = coder.line_stats().unwrap();
+        assert!(stats.invalid + stats.disparity_errors > 0);
+    }
+}
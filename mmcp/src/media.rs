@@ -0,0 +1,387 @@
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Seek, SeekFrom};
+
+/// The samples of an MP4 file, the frames of its video tracks and the
+/// sample runs of its audio tracks, by where they lie in the file. Tells
+/// what the bytes decoded wrong did to the media.
+pub struct Mp4 {
+    // sorted by offset.
+    samples: Vec<Sample>,
+    // the length of the longest track in seconds.
+    duration: f64,
+}
+
+struct Sample {
+    offset: u64,
+    size: u64,
+    // when it is presented, in seconds from the start.
+    time: f64,
+    kind: Kind,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Kind {
+    Frame,
+    // a frame the frames up to the next key frame are predicted from.
+    KeyFrame,
+    Audio,
+    Other,
+}
+
+/// What the residual errors of a run hit in an MP4 payload.
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+pub struct MediaDamage {
+    /// Seconds of the media with a sample hit, out of all of them.
+    pub seconds_hit: u32,
+    pub seconds: u32,
+    pub frames: u32,
+    /// Of the frames hit, those the following frames depend on, so the
+    /// damage spreads up to the next key frame.
+    pub key_frames: u32,
+    pub audio_samples: u32,
+    /// Whether bytes outside every sample were hit, the boxes telling a
+    /// player where the samples are among them.
+    pub container: bool,
+}
+
+impl Mp4 {
+    /// The samples of the file, None if it is no MP4 file or has no tracks.
+    /// Reads the headers of the boxes at the top and the movie box only,
+    /// not the media.
+    pub fn read(mut file: impl Read + Seek) -> Option<Self> {
+        let length = file.seek(SeekFrom::End(0)).ok()?;
+        let mut offset = 0;
+        while offset < length {
+            file.seek(SeekFrom::Start(offset)).ok()?;
+            let mut header = [0; 16];
+            file.read_exact(&mut header[..8]).ok()?;
+            let kind: [u8; 4] = header[4..8].try_into().unwrap();
+            if offset == 0 && kind != *b"ftyp" {
+                return None;
+            }
+            // sized as the boxes inside them are.
+            let (header, size) = match u32::from_be_bytes(header[..4].try_into().unwrap()) {
+                0 => (8, length - offset),
+                1 => {
+                    file.read_exact(&mut header[8..]).ok()?;
+                    (16, u64::from_be_bytes(header[8..].try_into().unwrap()))
+                }
+                size => (8, size as u64),
+            };
+            if size < header || size > length - offset {
+                return None;
+            }
+            if kind == *b"moov" {
+                let mut moov = vec![0; (size - header) as usize];
+                file.read_exact(&mut moov).ok()?;
+                return Self::parse(&moov);
+            }
+            offset += size;
+        }
+        None
+    }
+
+    // the samples the movie box lists.
+    fn parse(moov: &[u8]) -> Option<Self> {
+        let mut samples = vec![];
+        let mut duration: f64 = 0.0;
+        for (_, trak) in Boxes::new(moov).filter(|(kind, _)| kind == b"trak") {
+            let mdia = child(trak, b"mdia")?;
+            let (timescale, track_duration) = timescale(child(mdia, b"mdhd")?)?;
+            duration = duration.max(track_duration as f64 / timescale as f64);
+            let handler = child(mdia, b"hdlr")?.get(8..12)?;
+            let stbl = child(child(mdia, b"minf")?, b"stbl")?;
+            samples.extend(track(stbl, handler, timescale)?);
+        }
+        if samples.is_empty() {
+            return None;
+        }
+        samples.sort_by_key(|sample| sample.offset);
+        Some(Self { samples, duration })
+    }
+
+    /// What errors in the bytes at these sorted offsets did to the media.
+    pub fn damage(&self, offsets: impl Iterator<Item = u64>) -> MediaDamage {
+        let mut damage = MediaDamage {
+            seconds: self.duration.ceil() as u32,
+            ..MediaDamage::default()
+        };
+        let mut seconds_hit = vec![];
+        let mut last = None;
+        for offset in offsets {
+            let index = self
+                .samples
+                .partition_point(|sample| sample.offset <= offset)
+                .checked_sub(1);
+            let Some(index) = index.filter(|&index| {
+                let sample = &self.samples[index];
+                offset < sample.offset + sample.size
+            }) else {
+                damage.container = true;
+                continue;
+            };
+            if last == Some(index) {
+                continue;
+            }
+            last = Some(index);
+            let sample = &self.samples[index];
+            match sample.kind {
+                Kind::Frame => damage.frames += 1,
+                Kind::KeyFrame => {
+                    damage.frames += 1;
+                    damage.key_frames += 1;
+                }
+                Kind::Audio => damage.audio_samples += 1,
+                Kind::Other => {}
+            }
+            seconds_hit.push(sample.time as u32);
+        }
+        seconds_hit.sort_unstable();
+        seconds_hit.dedup();
+        damage.seconds_hit = seconds_hit.len() as u32;
+        damage
+    }
+}
+
+// the boxes following each other in the data, each its type and content.
+struct Boxes<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Boxes<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+}
+
+impl<'a> Iterator for Boxes<'a> {
+    type Item = ([u8; 4], &'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let size = u32::from_be_bytes(self.data.get(..4)?.try_into().ok()?) as u64;
+        let kind: [u8; 4] = self.data.get(4..8)?.try_into().ok()?;
+        // a size of one has the real one follow as 64 bits, zero runs to
+        // the end.
+        let (header, size) = match size {
+            0 => (8, self.data.len() as u64),
+            1 => (
+                16,
+                u64::from_be_bytes(self.data.get(8..16)?.try_into().ok()?),
+            ),
+            size => (8, size),
+        };
+        let content = self.data.get(header..usize::try_from(size).ok()?)?;
+        self.data = &self.data[size as usize..];
+        Some((kind, content))
+    }
+}
+
+fn child<'a>(parent: &'a [u8], kind: &[u8; 4]) -> Option<&'a [u8]> {
+    Boxes::new(parent)
+        .find(|(child, _)| child == kind)
+        .map(|(_, content)| content)
+}
+
+fn u32_at(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(
+        data.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+fn u64_at(data: &[u8], offset: usize) -> Option<u64> {
+    Some(u64::from_be_bytes(
+        data.get(offset..offset + 8)?.try_into().ok()?,
+    ))
+}
+
+// the units per second of a track and its duration in them, both versions
+// of the box.
+fn timescale(mdhd: &[u8]) -> Option<(u32, u64)> {
+    match mdhd.first()? {
+        1 => Some((u32_at(mdhd, 20)?, u64_at(mdhd, 24)?)),
+        _ => Some((u32_at(mdhd, 12)?, u32_at(mdhd, 16)? as u64)),
+    }
+    .filter(|&(timescale, _)| timescale > 0)
+}
+
+// entries of a table box, which follow its version, flags and count.
+fn entries(table: &[u8], width: usize) -> Option<impl Iterator<Item = &[u8]>> {
+    let count = u32_at(table, 4)? as usize;
+    Some(table.get(8..8 + count * width)?.chunks_exact(width))
+}
+
+// the samples of a track from its sample tables.
+fn track(stbl: &[u8], handler: &[u8], timescale: u32) -> Option<Vec<Sample>> {
+    let kind = match handler {
+        b"vide" => Kind::Frame,
+        b"soun" => Kind::Audio,
+        _ => Kind::Other,
+    };
+    // sizes of one for all, or of each.
+    let stsz = child(stbl, b"stsz")?;
+    let (size, count) = (u32_at(stsz, 4)?, u32_at(stsz, 8)? as usize);
+    let sizes: Vec<u64> = match size {
+        0 => stsz
+            .get(12..12 + 4 * count)?
+            .chunks_exact(4)
+            .map(|size| u32::from_be_bytes(size.try_into().unwrap()) as u64)
+            .collect(),
+        size => vec![size as u64; count],
+    };
+    let chunks: Vec<u64> = match child(stbl, b"stco") {
+        Some(stco) => entries(stco, 4)?
+            .map(|offset| u32::from_be_bytes(offset.try_into().unwrap()) as u64)
+            .collect(),
+        None => entries(child(stbl, b"co64")?, 8)?
+            .map(|offset| u64::from_be_bytes(offset.try_into().unwrap()))
+            .collect(),
+    };
+    // runs of chunks with the same number of samples, from their first
+    // chunk counted from one.
+    let runs: Vec<(usize, usize)> = entries(child(stbl, b"stsc")?, 12)?
+        .map(|entry| {
+            let field = |at| u32::from_be_bytes(entry[at..at + 4].try_into().unwrap()) as usize;
+            (field(0), field(4))
+        })
+        .collect();
+    let durations = entries(child(stbl, b"stts")?, 8)?.flat_map(|entry| {
+        let field = |at| u32::from_be_bytes(entry[at..at + 4].try_into().unwrap());
+        std::iter::repeat_n(field(4), field(0) as usize)
+    });
+    // every sample a key frame without the table.
+    let key_frames: Option<Vec<u32>> = child(stbl, b"stss").and_then(|stss| {
+        Some(
+            entries(stss, 4)?
+                .map(|number| u32::from_be_bytes(number.try_into().unwrap()))
+                .collect(),
+        )
+    });
+
+    let mut offsets = vec![];
+    for (chunk, &start) in chunks.iter().enumerate() {
+        let run = runs.partition_point(|&(first, _)| first <= chunk + 1);
+        let per_chunk = runs.get(run.checked_sub(1)?)?.1;
+        let mut offset = start;
+        for _ in 0..per_chunk {
+            let Some(&size) = sizes.get(offsets.len()) else {
+                break;
+            };
+            offsets.push(offset);
+            offset += size;
+        }
+    }
+    let mut time = 0u64;
+    let samples = offsets
+        .into_iter()
+        .zip(sizes)
+        .zip(durations)
+        .enumerate()
+        .map(|(index, ((offset, size), duration))| {
+            let key = key_frames
+                .as_ref()
+                .is_none_or(|keys| keys.binary_search(&(index as u32 + 1)).is_ok());
+            let sample = Sample {
+                offset,
+                size,
+                time: time as f64 / timescale as f64,
+                kind: match kind {
+                    Kind::Frame if key => Kind::KeyFrame,
+                    kind => kind,
+                },
+            };
+            time += duration as u64;
+            sample
+        })
+        .collect();
+    Some(samples)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn mp4_box(kind: &[u8; 4], content: &[u8]) -> Vec<u8> {
+        let mut bytes = ((content.len() + 8) as u32).to_be_bytes().to_vec();
+        bytes.extend(kind);
+        bytes.extend(content);
+        bytes
+    }
+
+    // a table box of the entries, each of 32 bit fields.
+    fn table(kind: &[u8; 4], entries: &[&[u32]]) -> Vec<u8> {
+        let mut content = vec![0; 4];
+        content.extend((entries.len() as u32).to_be_bytes());
+        for field in entries.iter().flat_map(|entry| entry.iter()) {
+            content.extend(field.to_be_bytes());
+        }
+        mp4_box(kind, &content)
+    }
+
+    // four frames of 10 bytes a second apart in a single chunk at byte 24,
+    // the first and third key frames.
+    fn video() -> Vec<u8> {
+        let mut file = mp4_box(b"ftyp", b"isom\0\0\0\0");
+        file.extend(mp4_box(b"mdat", &[0; 40]));
+        let mut mdhd = vec![0; 12];
+        mdhd.extend(1u32.to_be_bytes());
+        mdhd.extend(4u32.to_be_bytes());
+        let mut hdlr = vec![0; 8];
+        hdlr.extend(b"vide");
+        let mut stsz = vec![0; 8];
+        stsz.extend(4u32.to_be_bytes());
+        stsz.extend([10u32; 4].iter().flat_map(|size| size.to_be_bytes()));
+        let stbl = [
+            mp4_box(b"stsz", &stsz),
+            table(b"stco", &[&[24]]),
+            table(b"stsc", &[&[1, 4, 1]]),
+            table(b"stts", &[&[4, 1]]),
+            table(b"stss", &[&[1], &[3]]),
+        ]
+        .concat();
+        let minf = mp4_box(b"stbl", &stbl);
+        let mdia = [
+            mp4_box(b"mdhd", &mdhd),
+            mp4_box(b"hdlr", &hdlr),
+            mp4_box(b"minf", &minf),
+        ]
+        .concat();
+        let trak = mp4_box(b"mdia", &mdia);
+        file.extend(mp4_box(b"moov", &mp4_box(b"trak", &trak)));
+        file
+    }
+
+    #[test]
+    fn samples_are_read_from_the_movie_box() {
+        let mp4 = Mp4::read(Cursor::new(video())).unwrap();
+        let offsets: Vec<u64> = mp4.samples.iter().map(|sample| sample.offset).collect();
+        assert_eq!(offsets, [24, 34, 44, 54]);
+        assert_eq!(mp4.duration, 4.0);
+        let damage = mp4.damage(std::iter::empty());
+        assert_eq!(damage.seconds, 4);
+        assert_eq!(damage.seconds_hit, 0);
+        assert_eq!(damage.frames, 0);
+        assert!(!damage.container);
+    }
+
+    #[test]
+    fn damage_counts_every_sample_hit_once() {
+        let mp4 = Mp4::read(Cursor::new(video())).unwrap();
+        // the file type box, the first frame twice, the third and fourth.
+        let damage = mp4.damage([5, 25, 26, 45, 55].into_iter());
+        assert_eq!(damage.frames, 3);
+        assert_eq!(damage.key_frames, 2);
+        assert_eq!(damage.audio_samples, 0);
+        assert_eq!(damage.seconds_hit, 3);
+        assert!(damage.container);
+    }
+
+    #[test]
+    fn other_files_are_no_mp4() {
+        assert!(Mp4::read(Cursor::new(b"RIFF\0\0\0\0WAVE".to_vec())).is_none());
+        let video = video();
+        // the movie box cut short, and missing.
+        assert!(Mp4::read(Cursor::new(video[..video.len() - 4].to_vec())).is_none());
+        assert!(Mp4::read(Cursor::new(video[..64].to_vec())).is_none());
+    }
+}
@@ -0,0 +1,126 @@
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicU64, AtomicUsize, Ordering},
+};
+
+// bytes allocated on the heap right now, and the most since the last run
+// started.
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+// allocations and reallocations so far.
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+// runs measuring their memory right now, and runs that started to so far.
+static RUNNING: AtomicUsize = AtomicUsize::new(0);
+static STARTED: AtomicU64 = AtomicU64::new(0);
+
+/// The allocator of every binary using the library, the system one counting
+/// the allocations and the bytes allocated.
+pub struct CountingAllocator;
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let pointer = System.alloc(layout);
+        if !pointer.is_null() {
+            allocated(layout.size());
+        }
+        pointer
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let pointer = System.alloc_zeroed(layout);
+        if !pointer.is_null() {
+            allocated(layout.size());
+        }
+        pointer
+    }
+
+    unsafe fn dealloc(&self, pointer: *mut u8, layout: Layout) {
+        System.dealloc(pointer, layout);
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+
+    unsafe fn realloc(&self, pointer: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let moved = System.realloc(pointer, layout, new_size);
+        if !moved.is_null() {
+            ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+            allocated(new_size);
+        }
+        moved
+    }
+}
+
+fn allocated(size: usize) {
+    ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+    let allocated = ALLOCATED.fetch_add(size, Ordering::Relaxed) + size;
+    PEAK.fetch_max(allocated, Ordering::Relaxed);
+}
+
+/// The memory a run allocated, from its start on. The counters are those of
+/// the process, so runs at the same time, as with `--jobs`, tell nothing
+/// about their own memory.
+pub struct Usage {
+    start: usize,
+    allocations: u64,
+    // the number the run started as, and whether another one was running.
+    started: u64,
+    shared: bool,
+}
+
+impl Usage {
+    pub fn start() -> Self {
+        let shared = RUNNING.fetch_add(1, Ordering::Relaxed) > 0;
+        let start = ALLOCATED.load(Ordering::Relaxed);
+        PEAK.store(start, Ordering::Relaxed);
+        Self {
+            start,
+            allocations: ALLOCATIONS.load(Ordering::Relaxed),
+            started: STARTED.fetch_add(1, Ordering::Relaxed) + 1,
+            shared,
+        }
+    }
+
+    // no other run was running at the start or started since.
+    fn alone(&self) -> bool {
+        !self.shared && STARTED.load(Ordering::Relaxed) == self.started
+    }
+
+    /// The most bytes allocated at once on top of those allocated at the
+    /// start, None if another run ran at the same time.
+    pub fn peak(&self) -> Option<usize> {
+        self.alone()
+            .then(|| PEAK.load(Ordering::Relaxed).saturating_sub(self.start))
+    }
+
+    /// Allocations since the start, growing a `Vec` in place or not
+    /// included, None if another run ran at the same time.
+    pub fn allocations(&self) -> Option<u64> {
+        self.alone()
+            .then(|| ALLOCATIONS.load(Ordering::Relaxed) - self.allocations)
+    }
+}
+
+impl Drop for Usage {
+    fn drop(&mut self) {
+        RUNNING.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overlapping_runs_have_no_peak() {
+        let first = Usage::start();
+        let second = Usage::start();
+        assert_eq!(second.peak(), None);
+        assert_eq!(second.allocations(), None);
+        drop(second);
+        // the second run started during the first one.
+        assert_eq!(first.peak(), None);
+        assert_eq!(first.allocations(), None);
+    }
+}
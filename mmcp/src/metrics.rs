@@ -0,0 +1,70 @@
+use std::{cell::RefCell, sync::RwLock};
+
+// metrics every run reports, zero if it recorded none.
+static REGISTERED: RwLock<Vec<String>> = RwLock::new(vec![]);
+
+thread_local! {
+    // what the run going on on this thread recorded, in the order first
+    // recorded.
+    static RECORDED: RefCell<Vec<(String, f64)>> = const { RefCell::new(vec![]) };
+}
+
+/// Has every run from now on report the metric, as a column of its own
+/// after the others, even runs that never record it.
+pub fn register(name: &str) {
+    let mut registered = REGISTERED.write().unwrap();
+    if !registered.iter().any(|registered| registered == name) {
+        registered.push(name.to_string());
+    }
+}
+
+/// Adds to the metric of the run going on on this thread, counting things
+/// such as decoder iterations or retransmissions. A metric recorded but not
+/// registered is reported by the runs recording it.
+pub fn add(name: &str, value: f64) {
+    record(name, |metric| *metric += value);
+}
+
+/// Sets the metric of the run going on on this thread, for figures that do
+/// not add up.
+pub fn set(name: &str, value: f64) {
+    record(name, |metric| *metric = value);
+}
+
+fn record(name: &str, update: impl FnOnce(&mut f64)) {
+    RECORDED.with_borrow_mut(|recorded| {
+        match recorded.iter_mut().find(|(recorded, _)| recorded == name) {
+            Some((_, metric)) => update(metric),
+            None => {
+                let mut metric = 0.0;
+                update(&mut metric);
+                recorded.push((name.to_string(), metric));
+            }
+        }
+    });
+}
+
+/// Forgets what was recorded on this thread, as a run starts.
+pub fn start() {
+    RECORDED.with_borrow_mut(Vec::clear);
+}
+
+/// The metrics of the run that went on on this thread, those registered
+/// first.
+pub fn finish() -> Vec<(String, f64)> {
+    let mut recorded = RECORDED.take();
+    let mut metrics: Vec<(String, f64)> = REGISTERED
+        .read()
+        .unwrap()
+        .iter()
+        .map(|name| {
+            let value = recorded
+                .iter()
+                .position(|(recorded, _)| recorded == name)
+                .map_or(0.0, |index| recorded.remove(index).1);
+            (name.clone(), value)
+        })
+        .collect();
+    metrics.append(&mut recorded);
+    metrics
+}
@@ -0,0 +1,105 @@
+use color_eyre::eyre::{Result, WrapErr};
+
+use crate::{
+    analytics::{self, Analytics},
+    channel::Channel,
+    coder::Coder,
+    interleaver::Interleaver,
+    pipeline::{self, Files},
+};
+
+/// Trials of a coder over each channel, the seed one higher every trial,
+/// until the residual errors of the channel add up to a target, one report
+/// row per trial. Good channels need many trials for a reliable bit error
+/// rate, bad ones few; [`analytics::trials_report`] estimates it. The
+/// trials run in memory, their decoded payloads only compared, so hundreds
+/// of trials write no file.
+pub struct MonteCarlo {
+    /// Residual bit errors over the trials of a channel it stops at.
+    pub target_errors: u64,
+    /// Trials of a channel at most, for channels the coder all but never
+    /// gets wrong.
+    pub max_trials: u64,
+}
+
+impl MonteCarlo {
+    /// Runs the coder over every channel in turn, a fresh copy of it every
+    /// trial. The payload is read into memory first if it is not already.
+    pub async fn run(
+        &self,
+        coder: &mut dyn Coder,
+        interleaver: &dyn Interleaver,
+        channels: impl Fn() -> Result<Vec<Channel>>,
+        seed: u64,
+    ) -> Result<Vec<Analytics>> {
+        let files = pipeline::files();
+        let _restore = RestoreFiles(files.clone());
+        if files.memory.is_none() {
+            let payload = std::fs::read(&files.payload)
+                .wrap_err_with(|| format!("failed to read {}", files.payload.display()))?;
+            pipeline::set_files(Files::in_memory(files.payload, payload));
+        }
+        let mut results = vec![];
+        for index in 0..channels()?.len() {
+            let mut errors = 0;
+            for trial in 0..self.max_trials.max(1) {
+                let mut channel = channels()?
+                    .swap_remove(index)
+                    .with_seed(seed.wrapping_add(trial));
+                let run_metrics = pipeline::run(coder, interleaver, &mut channel).await?;
+                let analytics = analytics::analyze(&channel, run_metrics).await?;
+                errors += analytics.residual_bit_errors();
+                results.push(analytics);
+                if errors >= self.target_errors {
+                    break;
+                }
+            }
+        }
+        Ok(results)
+    }
+}
+
+// puts back the files the runs read before the trials, however they end.
+struct RestoreFiles(Files);
+
+impl Drop for RestoreFiles {
+    fn drop(&mut self) {
+        pipeline::set_files(std::mem::take(&mut self.0));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{coder::Uncoded, interleaver::NoInterleaver};
+    use std::io::Write;
+
+    #[test]
+    fn trials_leave_the_files_as_they_were() {
+        let _lock = pipeline::FILES_LOCK
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        let mut payload = tempfile::NamedTempFile::new().unwrap();
+        payload.write_all(&[0x5a; 1000]).unwrap();
+        pipeline::set_files(Files {
+            payload: payload.path().to_path_buf(),
+            ..Files::default()
+        });
+        let monte_carlo = MonteCarlo {
+            target_errors: 1,
+            max_trials: 3,
+        };
+        let results = async_std::task::block_on(monte_carlo.run(
+            &mut Uncoded,
+            &NoInterleaver,
+            || Ok(vec![Channel::new(0.0, 0.1)]),
+            1,
+        ))
+        .unwrap();
+        // an error free channel runs every trial, all of them in memory.
+        assert_eq!(results.len(), 3);
+        let files = pipeline::files();
+        assert_eq!(files.payload, payload.path());
+        assert!(files.memory.is_none());
+    }
+}
@@ -1,64 +1,1601 @@
 use async_std::{
+    channel,
     fs::{File, OpenOptions},
     io::BufReader,
     io::BufWriter,
     prelude::*,
+    stream, task,
+};
+use color_eyre::eyre::{Result, WrapErr};
+use serde::{Deserialize, Serialize};
+use std::{
+    cell::{Cell, RefCell},
+    future::Future,
+    path::{Path, PathBuf},
+    pin::Pin,
+    rc::Rc,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc, Arc, RwLock,
+    },
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+use tempfile::NamedTempFile;
+use tracing::{debug_span, trace_span, Instrument, Span};
+
+use crate::{
+    adaptive::RateStats,
+    analytics::seconds,
+    arq::ArqStats,
+    channel::Channel,
+    coder::{
+        CipherStats, Coder, CodewordStats, CompressionStats, FrameStats, LineStats, PacketStats,
+        SegmentStats, Uncoded,
+    },
+    feedback::{Duplex, Feedback, FeedbackStats},
+    framing::StreamHeader,
+    interleaver::{Interleaver, NoInterleaver},
+    memory, metrics,
+    progress::Progress,
+    residuals::{Comparison, Residuals},
 };
-use color_eyre::eyre::Result;
 
 const BUF_SIZE: usize = 4096;
+// batches of a block coder's run sent but not yet decoded at most.
+const BATCHES_IN_FLIGHT: usize = 4;
+/// Bytes in a chunk of a chunked pipeline, the last one may be shorter.
+pub const CHUNK_SIZE: usize = 4096;
+
+// settled once per process, before the first run.
+static FILES: RwLock<Option<Files>> = RwLock::new(None);
+// held by the tests setting the files, which every run of the process reads.
+#[cfg(test)]
+pub(crate) static FILES_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+// items the queues between the stages of a pipeline! run hold, none if 0.
+static QUEUE_CAPACITY: AtomicUsize = AtomicUsize::new(0);
+// runs started or reserved so far, numbering their outputs.
+static RUNS: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    // the number reserved for the next run on this thread.
+    static RUN: Cell<Option<usize>> = const { Cell::new(None) };
+}
+
+/// A run borrowing its channel, as given to [`crate::Options::run_parallel`].
+pub type RunFuture<'a> = Pin<Box<dyn Future<Output = Result<RunMetrics>> + 'a>>;
 
+/// The payload every run sends and where it writes the decoded payload.
+#[derive(Debug, Clone)]
+pub struct Files {
+    pub payload: PathBuf,
+    pub output: PathBuf,
+    /// The payload already read, when the runs keep everything in memory
+    /// and touch no files. `payload` only names it then.
+    pub memory: Option<Arc<[u8]>>,
+}
+
+impl Files {
+    /// The payload the runs send.
+    pub fn source(&self) -> Source {
+        match &self.memory {
+            Some(bytes) => Source::Memory {
+                name: self.payload.clone(),
+                bytes: bytes.clone(),
+            },
+            None => Source::File(self.payload.clone()),
+        }
+    }
+
+    /// Runs sending these bytes, named after `name` in the report, without
+    /// reading or writing any file.
+    pub fn in_memory(name: impl Into<PathBuf>, payload: Vec<u8>) -> Self {
+        Self {
+            payload: name.into(),
+            memory: Some(payload.into()),
+            ..Self::default()
+        }
+    }
+}
+
+impl Default for Files {
+    fn default() -> Self {
+        Self {
+            payload: PathBuf::from("resources/original.mp4"),
+            output: PathBuf::from("result.mp4"),
+            memory: None,
+        }
+    }
+}
+
+/// Bytes a run sends or that are compared, in a file or already read.
+#[derive(Debug, Clone)]
+pub enum Source {
+    File(PathBuf),
+    /// Bytes in memory, named in the report as a file would be.
+    Memory {
+        name: PathBuf,
+        bytes: Arc<[u8]>,
+    },
+}
+
+impl Source {
+    /// The file name shown in the report.
+    pub fn name(&self) -> String {
+        let (Source::File(path) | Source::Memory { name: path, .. }) = self;
+        path.file_name()
+            .map_or(String::new(), |name| name.to_string_lossy().into_owned())
+    }
+
+    pub async fn length(&self) -> Result<u64> {
+        Ok(match self {
+            Source::File(path) => async_std::fs::metadata(path)
+                .await
+                .wrap_err_with(|| format!("failed to read {}", path.display()))?
+                .len(),
+            Source::Memory { bytes, .. } => bytes.len() as u64,
+        })
+    }
+
+    /// The bytes a chunk of up to [`CHUNK_SIZE`] at a time, ending with the
+    /// error a file failed to read with.
+    pub async fn chunks(
+        &self,
+    ) -> Result<Box<dyn Stream<Item = std::io::Result<Vec<u8>>> + Send + Unpin>> {
+        Ok(match self {
+            Source::File(path) => {
+                Box::new(ReadChunks(File::open(path).await.wrap_err_with(|| {
+                    format!("failed to read {}", path.display())
+                })?))
+            }
+            Source::Memory { bytes, .. } => {
+                let chunks: Vec<_> = bytes
+                    .chunks(CHUNK_SIZE)
+                    .map(|chunk| Ok(chunk.to_vec()))
+                    .collect();
+                Box::new(stream::from_iter(chunks))
+            }
+        })
+    }
+}
+
+/// Where a run wrote its decoded payload, and what it decoded wrong.
+#[derive(Debug)]
+pub struct Output {
+    // a temporary file next to the path, moved there once the run is
+    // analyzed, so another process writing the same name never leaves half
+    // a file. none when the runs keep everything in memory, the decoded
+    // payload only compared then.
+    file: Option<(NamedTempFile, PathBuf)>,
+    label: String,
+    residuals: Residuals,
+}
+
+impl Output {
+    /// The number of the run and its channel, `001-awgn-eb-n0-5-0-db`, as
+    /// in the names of the decoded payloads.
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    /// The decoded payload against the payload, known once it is written,
+    /// taken out of the output.
+    pub fn take_residuals(&mut self) -> Residuals {
+        std::mem::take(&mut self.residuals)
+    }
+
+    /// Moves the temporary file into place.
+    pub fn persist(self) -> Result<()> {
+        if let Some((temp, path)) = self.file {
+            temp.persist(&path)
+                .wrap_err_with(|| format!("failed to write {}", path.display()))?;
+        }
+        Ok(())
+    }
+}
+
+/// Reserves the numbers of the next `count` runs and returns the first, so
+/// runs on several threads are numbered in the order they were planned.
+pub fn reserve_runs(count: usize) -> usize {
+    RUNS.fetch_add(count, Ordering::Relaxed) + 1
+}
+
+/// Gives the next run on this thread the reserved number.
+pub fn number_next_run(run: usize) {
+    RUN.with(|next| next.set(Some(run)));
+}
+
+/// Connects the stages of the runs `pipeline!` defines through queues of
+/// this many items, or directly if 0.
+pub fn set_queue_capacity(capacity: usize) {
+    QUEUE_CAPACITY.store(capacity, Ordering::Relaxed);
+}
+
+pub fn set_files(files: Files) {
+    *FILES.write().unwrap() = Some(files);
+}
+
+pub fn files() -> Files {
+    FILES.read().unwrap().clone().unwrap_or_default()
+}
+
+/// Where the next run writes its decoded payload: the output with the number
+/// of the run and the channel added to its name, `result-001-awgn-eb-n0-5-0-db.mp4`
+/// for the first run over an AWGN channel, so no two runs write the same file.
+/// No file when the runs keep everything in memory.
+pub fn next_output(channel: &Channel) -> Result<Output> {
+    let run = RUN
+        .with(Cell::take)
+        .unwrap_or_else(|| RUNS.fetch_add(1, Ordering::Relaxed) + 1);
+    let mut slug = String::new();
+    for c in channel.channel_information().model().to_string().chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let label = format!("{:03}-{}", run, slug.trim_end_matches('-'));
+    let files = files();
+    if files.memory.is_some() {
+        return Ok(Output {
+            file: None,
+            label,
+            residuals: Residuals::default(),
+        });
+    }
+    let output = files.output;
+    let mut name = output.file_stem().unwrap_or_default().to_os_string();
+    name.push(format!("-{}", label));
+    if let Some(extension) = output.extension() {
+        name.push(".");
+        name.push(extension);
+    }
+    let directory = match output.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let temp = tempfile::Builder::new()
+        .prefix(".mmcp-")
+        .tempfile_in(directory)?;
+    Ok(Output {
+        file: Some((temp, output.with_file_name(name))),
+        label,
+        residuals: Residuals::default(),
+    })
+}
+
+#[derive(Debug)]
+pub struct RunMetrics {
+    pub coder: String,
+    /// The file the decoded payload was written to, and what it got wrong.
+    pub output: Output,
+    pub interleaver: String,
+    pub end_to_end_time: Duration,
+    /// Time from the first byte of the payload going into the encoder until
+    /// the first byte of the decoded payload came out.
+    pub latency: Option<Duration>,
+    /// The queue after every stage, named after the stage, when the stages
+    /// are connected through queues.
+    pub queue_stats: Vec<(String, QueueStats)>,
+    /// Time every stage spent working, named after the stage. Stages working
+    /// at the same time add up to more than the end to end time.
+    pub busy_time: Vec<(String, Duration)>,
+    /// The busy time of the stages encoding, of the channel and of the
+    /// stages decoding.
+    pub stage_time: Option<StageTime>,
+    /// Bytes the run had allocated at most at once, see [`memory::Usage`].
+    /// None if other runs ran at the same time.
+    pub peak_memory: Option<usize>,
+    pub allocations: Option<u64>,
+    pub input_byte_count: u64,
+    pub channel_byte_count: u64,
+    /// Payload bits per codeword of the coder, see [`Coder::codeword_bits`].
+    pub codeword_bits: Option<usize>,
+    pub codeword_stats: Option<CodewordStats>,
+    pub segment_stats: Option<SegmentStats>,
+    pub layer_stats: Vec<(String, SegmentStats)>,
+    pub frame_stats: Option<FrameStats>,
+    pub packet_stats: Option<PacketStats>,
+    pub line_stats: Option<LineStats>,
+    pub compression_stats: Option<CompressionStats>,
+    pub cipher_stats: Option<CipherStats>,
+    pub feedback_stats: Option<FeedbackStats>,
+    pub arq_stats: Option<ArqStats>,
+    pub rate_stats: Option<RateStats>,
+    pub header_valid: Option<bool>,
+    /// Named metrics the coders recorded during the run, see [`metrics`].
+    pub metrics: Vec<(String, f64)>,
+}
+
+/// Defines `pipeline_run`, which streams the input through the encode
+/// stages, the channel and the decode stages, each list in the order given:
+/// `pipeline!(compress, encode | decode, decompress)`. Every stage is an async
+/// function from a byte stream to a byte stream. `pipeline!(encode, decode)`
+/// is short for a single stage on either side.
+///
+/// To compare several stage lists in one binary, each gets a run of its own
+/// named after it: `pipeline!(hamming = encode | decode)` defines `hamming`,
+/// which reports its rows as coder `hamming`.
+///
+/// Moving a byte per stream item costs more than most stages do with it.
+/// With `chunks:` in front, `pipeline!(chunks: encode | decode)`, the stages
+/// pass on chunks of up to [`pipeline::CHUNK_SIZE`] bytes as `Vec<u8>`
+/// instead. [`pipeline::bytes`] and [`pipeline::chunks`] wrap a byte-wise
+/// stage for such a pipeline.
+///
+/// [`pipeline::CHUNK_SIZE`]: crate::pipeline::CHUNK_SIZE
+/// [`pipeline::bytes`]: crate::pipeline::bytes
+/// [`pipeline::chunks`]: crate::pipeline::chunks
 #[macro_export]
 macro_rules! pipeline {
+    (chunks: $($encode:ident),+ | $($decode:ident),+) => {
+        $crate::pipeline!(
+            @run pipeline_run, env!("CARGO_PKG_NAME"), input_chunks, process_chunks, output_chunks,
+            $($encode),+ | $($decode),+
+        );
+    };
+    ($run:ident = chunks: $($encode:ident),+ | $($decode:ident),+) => {
+        $crate::pipeline!(
+            @run $run, stringify!($run), input_chunks, process_chunks, output_chunks,
+            $($encode),+ | $($decode),+
+        );
+    };
     ($encode:ident, $decode:ident) => {
-        async fn pipeline_run(
-            channel: &mut crate::channel::Channel,
-        ) -> Result<(std::time::Duration, u32, u32)> {
+        $crate::pipeline!($encode | $decode);
+    };
+    ($run:ident = $($encode:ident),+ | $($decode:ident),+) => {
+        $crate::pipeline!(
+            @run $run, stringify!($run), input, process, output,
+            $($encode),+ | $($decode),+
+        );
+    };
+    ($($encode:ident),+ | $($decode:ident),+) => {
+        $crate::pipeline!(
+            @run pipeline_run, env!("CARGO_PKG_NAME"), input, process, output,
+            $($encode),+ | $($decode),+
+        );
+    };
+    (
+        @run $run:ident, $coder:expr, $input:ident, $process:ident, $output:ident,
+        $($encode:ident),+ | $($decode:ident),+
+    ) => {
+        async fn $run(
+            channel: &mut $crate::channel::Channel,
+        ) -> Result<$crate::pipeline::RunMetrics> {
             use async_std::prelude::*;
-            use std::time::{Duration, Instant};
+            use std::time::Instant;
 
             let start = Instant::now();
-            let stream = crate::pipeline::input().await?;
-            let mut input_byte_count: u32 = 0;
-            let stream = stream.map(|b| {
-                input_byte_count += 1;
-                b
+            let memory = $crate::memory::Usage::start();
+            $crate::metrics::start();
+            let mut output = $crate::pipeline::next_output(channel)?;
+            let mut input_byte_count: u64 = 0;
+            let mut channel_byte_count: u64 = 0;
+            let first_input = std::cell::Cell::new(None);
+            let mut latency = None;
+            let busy_time = $crate::pipeline::BusyTime::default();
+            let progress = $crate::progress::Progress::start($coder, channel);
+            let queues = $crate::pipeline::Queues::default();
+            let read_error = $crate::pipeline::ReadError::default();
+            let stream = read_error.until($crate::pipeline::$input().await?);
+            let stream = queues.add("input", stream.map(|item| {
+                first_input.set(first_input.get().or_else(|| Some(Instant::now())));
+                input_byte_count += $crate::pipeline::StreamItem::byte_count(&item);
+                item
+            }));
+            $(let stream = {
+                let timer = busy_time.stage(stringify!($encode));
+                let stream = timer.call($encode(timer.input(stream))).await?;
+                queues.add(stringify!($encode), timer.output(stream).map(|item| {
+                    progress.stage(stringify!($encode), $crate::pipeline::StreamItem::byte_count(&item));
+                    item
+                }))
+            };)+
+            let stream = stream.map(|item| {
+                channel_byte_count += $crate::pipeline::StreamItem::byte_count(&item);
+                item
             });
-            let stream = $encode(stream).await?;
-            let mut channel_byte_count: u32 = 0;
-            let stream = stream.map(|b| {
-                channel_byte_count += 1;
-                b
+            let stream = {
+                let timer = busy_time.channel();
+                let stream = timer.call(channel.$process(timer.input(stream))).await?;
+                queues.add("channel", timer.output(stream).map(|item| {
+                    progress.stage("channel", $crate::pipeline::StreamItem::byte_count(&item));
+                    item
+                }))
+            };
+            $(let stream = {
+                let timer = busy_time.stage(stringify!($decode));
+                let stream = timer.call($decode(timer.input(stream))).await?;
+                queues.add(stringify!($decode), timer.output(stream).map(|item| {
+                    progress.stage(stringify!($decode), $crate::pipeline::StreamItem::byte_count(&item));
+                    item
+                }))
+            };)+
+            let stream = stream.map(|item| {
+                latency = latency.or_else(|| first_input.get().map(|first: Instant| first.elapsed()));
+                progress.decoded($crate::pipeline::StreamItem::byte_count(&item));
+                item
             });
-            let stream = channel.process(stream).await?;
-            let stream = $decode(stream).await?;
-            crate::pipeline::output(stream).await?;
-            Ok((start.elapsed(), input_byte_count, channel_byte_count))
+            let queue_stats = queues
+                .drive($crate::pipeline::$output(&mut output, stream))
+                .await?;
+            read_error.check()?;
+            let (busy_time, stage_time) = busy_time.stages();
+            Ok($crate::pipeline::RunMetrics {
+                coder: $coder.to_string(),
+                output,
+                interleaver: "None".to_string(),
+                end_to_end_time: start.elapsed(),
+                latency,
+                queue_stats,
+                busy_time,
+                stage_time,
+                peak_memory: memory.peak(),
+                allocations: memory.allocations(),
+                input_byte_count,
+                channel_byte_count,
+                codeword_bits: None,
+                codeword_stats: None,
+                segment_stats: None,
+                layer_stats: vec![],
+                frame_stats: None,
+                packet_stats: None,
+                line_stats: None,
+                compression_stats: None,
+                cipher_stats: None,
+                feedback_stats: None,
+                arq_stats: None,
+                rate_stats: None,
+                header_valid: None,
+                metrics: $crate::metrics::finish(),
+            })
+        }
+    };
+}
+
+pub async fn run(
+    coder: &mut dyn Coder,
+    interleaver: &dyn Interleaver,
+    channel: &mut Channel,
+) -> Result<RunMetrics> {
+    if let Some(block) = coder.block() {
+        if interleaver.depth() == 1 && !channel.soft_output() && channel.keeps_length() {
+            return run_blocks(coder, interleaver, block, channel).await;
+        }
+    }
+    let start = Instant::now();
+    let memory = memory::Usage::start();
+    metrics::start();
+    let progress = Progress::start(&coder.name(), channel);
+    let run = run_span(coder, channel);
+    let data: Vec<u8> = input()
+        .await?
+        .collect::<std::io::Result<_>>()
+        .await
+        .wrap_err("failed to read the payload")?;
+    let header = StreamHeader::new(coder, interleaver.depth(), data.len());
+    let encode_start = Instant::now();
+    let encoded = debug_span!(parent: &run, "encode", bytes = data.len())
+        .in_scope(|| coder.encode(&header.frame(&data)))?;
+    let encoded = interleaver.interleave(&encoded);
+    let encode_time = encode_start.elapsed();
+    progress.stage("encode", encoded.len() as u64);
+    let channel_byte_count = encoded.len() as u64;
+    // nothing to send carries no rate, the channel is left as if uncoded.
+    channel.set_code_rate(match data.is_empty() {
+        true => 1.0,
+        false => data.len() as f64 / encoded.len() as f64,
+    });
+    // the coder needs all of the data at once, the phases follow each other.
+    let channel_start = Instant::now();
+    let (decoded, channel_time, decode_time) = if channel.soft_output() {
+        let llrs = debug_span!(parent: &run, "channel", bytes = encoded.len())
+            .in_scope(|| channel.process_soft(&encoded));
+        let channel_time = channel_start.elapsed();
+        let llrs = interleaver.deinterleave_soft(&llrs);
+        let decoded = debug_span!(parent: &run, "decode", bytes = llrs.len() / 8)
+            .in_scope(|| coder.decode_soft(&llrs))?;
+        (
+            decoded,
+            channel_time,
+            channel_start.elapsed() - channel_time,
+        )
+    } else {
+        let sent = debug_span!(parent: &run, "channel", bytes = encoded.len());
+        let received: Vec<u8> = async {
+            Ok::<_, color_eyre::Report>(
+                channel
+                    .process(stream::from_iter(encoded))
+                    .await?
+                    .map(|byte| {
+                        progress.stage("channel", 1);
+                        byte
+                    })
+                    .collect()
+                    .await,
+            )
         }
+        .instrument(sent)
+        .await?;
+        let channel_time = channel_start.elapsed();
+        let received = interleaver.deinterleave(&received);
+        let decoded = debug_span!(parent: &run, "decode", bytes = received.len())
+            .in_scope(|| coder.decode(&received))?;
+        (
+            decoded,
+            channel_time,
+            channel_start.elapsed() - channel_time,
+        )
     };
+    let (received_header, payload) = StreamHeader::unframe(&decoded);
+    let header_valid = received_header.is_some_and(|received| header.matches(&received));
+    // without a valid header there is no trustworthy length, keep everything.
+    let decoded = match received_header {
+        Some(received) if header_valid => {
+            &payload[..(received.payload_length as usize).min(payload.len())]
+        }
+        _ => payload,
+    }
+    .to_vec();
+    let latency = encode_start.elapsed();
+    progress.decoded(decoded.len() as u64);
+    let mut decoded_output = next_output(channel)?;
+    output(&mut decoded_output, stream::from_iter(decoded)).await?;
+    Ok(RunMetrics {
+        coder: coder.name(),
+        output: decoded_output,
+        interleaver: interleaver.name(),
+        end_to_end_time: start.elapsed(),
+        latency: Some(latency),
+        queue_stats: vec![],
+        busy_time: vec![
+            ("encode".to_string(), encode_time),
+            ("channel".to_string(), channel_time),
+            ("decode".to_string(), decode_time),
+        ],
+        stage_time: Some(StageTime {
+            encode: encode_time,
+            channel: channel_time,
+            decode: decode_time,
+        }),
+        peak_memory: memory.peak(),
+        allocations: memory.allocations(),
+        input_byte_count: data.len() as u64,
+        channel_byte_count,
+        codeword_bits: coder.codeword_bits(),
+        codeword_stats: coder.codeword_stats(),
+        segment_stats: coder.segment_stats(),
+        layer_stats: coder.layer_stats(),
+        frame_stats: coder.frame_stats(),
+        packet_stats: coder.packet_stats(),
+        line_stats: coder.line_stats(),
+        compression_stats: coder.compression_stats(),
+        cipher_stats: coder.cipher_stats(),
+        feedback_stats: None,
+        arq_stats: None,
+        rate_stats: None,
+        header_valid: Some(header_valid),
+        metrics: metrics::finish(),
+    })
 }
 
-pub async fn input() -> Result<impl Stream<Item = u8>> {
-    let file = BufReader::with_capacity(BUF_SIZE, File::open("resources/original.mp4").await?);
-    Ok(file.bytes().map(|b| b.unwrap()))
+// the span the stages of a run are traced in, batch by batch at the trace
+// level if the coder codes block by block.
+fn run_span(coder: &dyn Coder, channel: &Channel) -> Span {
+    debug_span!(
+        "run",
+        coder = %coder.name(),
+        channel = %channel.channel_information().model()
+    )
 }
 
-pub async fn output<S>(mut stream: S) -> Result<()>
+// `run` a batch of blocks at a time for coders coding block by block. The
+// channel sends every batch on a thread of its own while the coder encodes
+// the batches after it and decodes the ones before, at most BATCHES_IN_FLIGHT
+// batches on their way, so the memory used stays the same whatever the size
+// of the payload.
+async fn run_blocks(
+    coder: &mut dyn Coder,
+    interleaver: &dyn Interleaver,
+    (block_bytes, encoded_bytes): (usize, usize),
+    channel: &mut Channel,
+) -> Result<RunMetrics> {
+    let start = Instant::now();
+    let memory = memory::Usage::start();
+    metrics::start();
+    let files = files();
+    let payload_length = match &files.memory {
+        Some(payload) => payload.len(),
+        None => async_std::fs::metadata(&files.payload).await?.len() as usize,
+    };
+    let header = StreamHeader::new(coder, 1, payload_length);
+    let framed_bytes = StreamHeader::framed_bytes(payload_length);
+    channel.set_code_rate(match payload_length {
+        0 => 1.0,
+        _ => payload_length as f64 / (framed_bytes * encoded_bytes).div_ceil(block_bytes) as f64,
+    });
+    let batch = block_bytes * (CHUNK_SIZE / block_bytes).max(1);
+    let mut input = input_chunks().await?;
+    let mut decoded_output = next_output(channel)?;
+    let mut writer = Writer::open(&mut decoded_output).await?;
+    let mut pending = header.frame(&[]);
+    let mut input_done = false;
+    let mut encoded_all = false;
+    let mut in_flight = 0;
+    let mut channel_byte_count = 0;
+    let mut segment_stats: Option<SegmentStats> = None;
+    let mut codeword_stats: Option<CodewordStats> = None;
+    let mut received = Received::new(&header);
+    let progress = Progress::start(&coder.name(), channel);
+    let run = run_span(coder, channel);
+    let mut first_input = None;
+    let mut latency = None;
+    let mut encode_time = Duration::ZERO;
+    let mut decode_time = Duration::ZERO;
+    let channel_time = std::thread::scope(|scope| -> Result<Duration> {
+        // every batch goes with the number of bytes it encodes.
+        let (to_channel, sending) = mpsc::sync_channel::<(Vec<u8>, usize)>(BATCHES_IN_FLIGHT);
+        let (to_decoder, receiving) = mpsc::sync_channel(BATCHES_IN_FLIGHT);
+        let run = &run;
+        let sender = scope.spawn(move || {
+            let mut busy = Duration::ZERO;
+            for (encoded, data_bytes) in sending {
+                let sent = Instant::now();
+                let received = trace_span!(parent: run, "channel", bytes = encoded.len())
+                    .in_scope(|| channel.send(&encoded));
+                busy += sent.elapsed();
+                // the decoder only goes away if the run failed.
+                if to_decoder.send((received, data_bytes)).is_err() {
+                    break;
+                }
+            }
+            busy
+        });
+        loop {
+            // decode what came back, waiting for it once nothing is left to
+            // encode or enough is on its way.
+            let sent = if encoded_all || in_flight == BATCHES_IN_FLIGHT {
+                if in_flight == 0 {
+                    break;
+                }
+                match receiving.recv() {
+                    Ok(sent) => Some(sent),
+                    // the channel thread panicked, joining it tells.
+                    Err(_) => break,
+                }
+            } else {
+                receiving.try_recv().ok()
+            };
+            if let Some((sent, data_bytes)) = sent {
+                in_flight -= 1;
+                progress.stage("channel", sent.len() as u64);
+                let decode_start = Instant::now();
+                let decoded = trace_span!(parent: run, "decode", bytes = sent.len())
+                    .in_scope(|| coder.decode_batch(&sent, data_bytes))?;
+                decode_time += decode_start.elapsed();
+                // the segments decoded before are the codewords before.
+                if let Some(stats) = coder.codeword_stats() {
+                    let offset = segment_stats.map_or(0, |total| {
+                        total.corrected + total.uncorrectable + total.passed
+                    });
+                    codeword_stats
+                        .get_or_insert_with(CodewordStats::default)
+                        .append(stats, offset as usize);
+                }
+                if let Some(stats) = coder.segment_stats() {
+                    *segment_stats.get_or_insert_with(SegmentStats::default) += stats;
+                }
+                progress.stage("decode", decoded.len() as u64);
+                let payload = received.payload(&decoded);
+                progress.decoded(payload.len() as u64);
+                if !payload.is_empty() {
+                    latency = latency.or(first_input.map(|first: Instant| first.elapsed()));
+                    task::block_on(writer.write(payload))?;
+                }
+                continue;
+            }
+            while pending.len() < batch && !input_done {
+                match task::block_on(input.next()) {
+                    Some(chunk) => pending.extend(chunk.wrap_err("failed to read the payload")?),
+                    None => input_done = true,
+                }
+            }
+            if pending.is_empty() {
+                encoded_all = true;
+                continue;
+            }
+            let block: Vec<u8> = pending.drain(..pending.len().min(batch)).collect();
+            let encode_start = Instant::now();
+            first_input.get_or_insert(encode_start);
+            let encoded = trace_span!(parent: run, "encode", bytes = block.len())
+                .in_scope(|| coder.encode(&block))?;
+            encode_time += encode_start.elapsed();
+            progress.stage("encode", encoded.len() as u64);
+            channel_byte_count += encoded.len();
+            // room for it, the channel thread only stops with a panic.
+            if to_channel.send((encoded, block.len())).is_err() {
+                break;
+            }
+            in_flight += 1;
+        }
+        drop(to_channel);
+        Ok(sender.join().expect("the channel thread panicked"))
+    })?;
+    writer.finish().await?;
+    Ok(RunMetrics {
+        coder: coder.name(),
+        output: decoded_output,
+        interleaver: interleaver.name(),
+        end_to_end_time: start.elapsed(),
+        latency,
+        queue_stats: vec![],
+        busy_time: vec![
+            ("encode".to_string(), encode_time),
+            ("channel".to_string(), channel_time),
+            ("decode".to_string(), decode_time),
+        ],
+        stage_time: Some(StageTime {
+            encode: encode_time,
+            channel: channel_time,
+            decode: decode_time,
+        }),
+        peak_memory: memory.peak(),
+        allocations: memory.allocations(),
+        input_byte_count: payload_length as u64,
+        channel_byte_count: channel_byte_count as u64,
+        codeword_bits: coder.codeword_bits(),
+        codeword_stats,
+        segment_stats,
+        layer_stats: segment_stats
+            .map(|stats| vec![(coder.name(), stats)])
+            .unwrap_or_default(),
+        frame_stats: coder.frame_stats(),
+        packet_stats: coder.packet_stats(),
+        line_stats: coder.line_stats(),
+        compression_stats: coder.compression_stats(),
+        cipher_stats: coder.cipher_stats(),
+        feedback_stats: None,
+        arq_stats: None,
+        rate_stats: None,
+        header_valid: Some(received.header_valid.unwrap_or(false)),
+        metrics: metrics::finish(),
+    })
+}
+
+// the decoded stream of `run_blocks`: the header, copied until all of it
+// arrived, then what is left of the payload, everything without a valid
+// header.
+struct Received<'a> {
+    sent: &'a StreamHeader,
+    header: Vec<u8>,
+    header_valid: Option<bool>,
+    remaining: usize,
+}
+
+impl<'a> Received<'a> {
+    fn new(sent: &'a StreamHeader) -> Self {
+        Self {
+            sent,
+            header: vec![],
+            header_valid: None,
+            remaining: 0,
+        }
+    }
+
+    // the payload among the decoded bytes.
+    fn payload<'d>(&mut self, mut decoded: &'d [u8]) -> &'d [u8] {
+        if self.header_valid.is_none() {
+            let header_bytes = StreamHeader::framed_bytes(0);
+            let missing = (header_bytes - self.header.len()).min(decoded.len());
+            self.header.extend_from_slice(&decoded[..missing]);
+            decoded = &decoded[missing..];
+            if self.header.len() == header_bytes {
+                let (received, _) = StreamHeader::unframe(&self.header);
+                let valid = received.is_some_and(|received| self.sent.matches(&received));
+                self.remaining = match received {
+                    Some(received) if valid => received.payload_length as usize,
+                    _ => usize::MAX,
+                };
+                self.header_valid = Some(valid);
+            }
+        }
+        let payload = &decoded[..decoded.len().min(self.remaining)];
+        self.remaining -= payload.len();
+        payload
+    }
+}
+
+/// The uncoded run of a baseline row, as a [`RunFuture`].
+pub fn run_uncoded(channel: &mut Channel) -> RunFuture<'_> {
+    Box::pin(async move { run(&mut Uncoded, &NoInterleaver, channel).await })
+}
+
+/// Like [`run`] for a stage that sends over the channel itself and hears
+/// back from its receiver over the feedback channel.
+pub async fn run_duplex(
+    stage: &mut dyn Duplex,
+    channel: &mut Channel,
+    feedback: &mut Feedback,
+) -> Result<RunMetrics> {
+    let start = Instant::now();
+    let memory = memory::Usage::start();
+    metrics::start();
+    let data: Vec<u8> = input()
+        .await?
+        .collect::<std::io::Result<_>>()
+        .await
+        .wrap_err("failed to read the payload")?;
+    let header = StreamHeader::named(&stage.name(), 1, data.len());
+    let sent_bytes = channel.sent_bytes();
+    let transfer_start = Instant::now();
+    let decoded = stage.transfer(&header.frame(&data), channel, feedback)?;
+    let channel_byte_count = (channel.sent_bytes() - sent_bytes) as u64;
+    let (received_header, payload) = StreamHeader::unframe(&decoded);
+    let header_valid = received_header.is_some_and(|received| header.matches(&received));
+    let decoded = match received_header {
+        Some(received) if header_valid => {
+            &payload[..(received.payload_length as usize).min(payload.len())]
+        }
+        _ => payload,
+    }
+    .to_vec();
+    let latency = transfer_start.elapsed();
+    let mut decoded_output = next_output(channel)?;
+    output(&mut decoded_output, stream::from_iter(decoded)).await?;
+    Ok(RunMetrics {
+        coder: stage.name(),
+        output: decoded_output,
+        interleaver: "None".to_string(),
+        end_to_end_time: start.elapsed(),
+        latency: Some(latency),
+        queue_stats: vec![],
+        busy_time: vec![],
+        stage_time: None,
+        peak_memory: memory.peak(),
+        allocations: memory.allocations(),
+        input_byte_count: data.len() as u64,
+        channel_byte_count,
+        codeword_bits: None,
+        codeword_stats: None,
+        segment_stats: None,
+        layer_stats: vec![],
+        frame_stats: None,
+        packet_stats: None,
+        line_stats: None,
+        compression_stats: None,
+        cipher_stats: None,
+        feedback_stats: Some(feedback.stats()),
+        arq_stats: stage.arq_stats(),
+        rate_stats: stage.rate_stats(),
+        header_valid: Some(header_valid),
+        metrics: metrics::finish(),
+    })
+}
+
+/// Composes a pipeline at runtime from coder stages, applied in the order
+/// added on the way to the channel and in reverse on the way back, and the
+/// interleaver in front of the channel.
+pub struct PipelineBuilder {
+    stages: Vec<Box<dyn Coder>>,
+    interleaver: Box<dyn Interleaver>,
+}
+
+impl Default for PipelineBuilder {
+    fn default() -> Self {
+        Self {
+            stages: vec![],
+            interleaver: Box::new(NoInterleaver),
+        }
+    }
+}
+
+impl PipelineBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn stage(mut self, stage: Box<dyn Coder>) -> Self {
+        self.stages.push(stage);
+        self
+    }
+
+    pub fn interleaver(mut self, interleaver: Box<dyn Interleaver>) -> Self {
+        self.interleaver = interleaver;
+        self
+    }
+
+    pub fn build(self) -> Pipeline {
+        Pipeline {
+            stages: Stages(self.stages),
+            interleaver: self.interleaver,
+        }
+    }
+}
+
+/// A pipeline composed by [`PipelineBuilder`].
+pub struct Pipeline {
+    stages: Stages,
+    interleaver: Box<dyn Interleaver>,
+}
+
+impl Pipeline {
+    /// Like [`run`] with the stages as one coder.
+    pub async fn run(&mut self, channel: &mut Channel) -> Result<RunMetrics> {
+        run(&mut self.stages, self.interleaver.as_ref(), channel).await
+    }
+}
+
+// the stages of a pipeline in series. every statistic comes from the
+// outermost stage keeping it.
+struct Stages(Vec<Box<dyn Coder>>);
+
+impl Coder for Stages {
+    // the layers of every stage, those passing the data on as it is, alone
+    // or inside another stage, left out.
+    fn name(&self) -> String {
+        let uncoded = Uncoded.name();
+        let layers = self
+            .0
+            .iter()
+            .map(|stage| stage.name())
+            .collect::<Vec<_>>()
+            .join(" + ");
+        let layers = layers
+            .split(" + ")
+            .filter(|&layer| !layer.is_empty() && layer != uncoded)
+            .collect::<Vec<_>>();
+        if layers.is_empty() {
+            return uncoded;
+        }
+        layers.join(" + ")
+    }
+
+    fn encode(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+        self.0
+            .iter_mut()
+            .try_fold(data.to_vec(), |data, stage| stage.encode(&data))
+    }
+
+    fn decode(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+        self.0
+            .iter_mut()
+            .rev()
+            .try_fold(data.to_vec(), |data, stage| stage.decode(&data))
+    }
+
+    // only the stage next to the channel gets the ratios.
+    fn decode_soft(&mut self, llrs: &[f32]) -> Result<Vec<u8>> {
+        let Some((innermost, outer)) = self.0.split_last_mut() else {
+            return Ok(crate::hard_decisions(llrs));
+        };
+        let decoded = innermost.decode_soft(llrs)?;
+        outer
+            .iter_mut()
+            .rev()
+            .try_fold(decoded, |data, stage| stage.decode(&data))
+    }
+
+    // the payload only fills the codewords of the first stage in order.
+    fn codeword_bits(&self) -> Option<usize> {
+        self.0.first()?.codeword_bits()
+    }
+
+    fn codeword_stats(&self) -> Option<CodewordStats> {
+        self.0.first()?.codeword_stats()
+    }
+
+    fn segment_stats(&self) -> Option<SegmentStats> {
+        self.0.iter().find_map(|stage| stage.segment_stats())
+    }
+
+    fn layer_stats(&self) -> Vec<(String, SegmentStats)> {
+        self.0
+            .iter()
+            .flat_map(|stage| stage.layer_stats())
+            .collect()
+    }
+
+    fn frame_stats(&self) -> Option<FrameStats> {
+        self.0.iter().find_map(|stage| stage.frame_stats())
+    }
+
+    fn packet_stats(&self) -> Option<PacketStats> {
+        self.0.iter().find_map(|stage| stage.packet_stats())
+    }
+
+    fn line_stats(&self) -> Option<LineStats> {
+        self.0.iter().find_map(|stage| stage.line_stats())
+    }
+
+    fn compression_stats(&self) -> Option<CompressionStats> {
+        self.0.iter().find_map(|stage| stage.compression_stats())
+    }
+
+    fn cipher_stats(&self) -> Option<CipherStats> {
+        self.0.iter().find_map(|stage| stage.cipher_stats())
+    }
+}
+
+/// The payload a byte at a time, ending with the error the file failed to
+/// read with.
+pub async fn input() -> Result<Box<dyn Stream<Item = std::io::Result<u8>> + Send + Unpin>> {
+    let files = files();
+    if let Some(payload) = files.memory {
+        let bytes = (0..payload.len()).map(move |index| Ok(payload[index]));
+        return Ok(Box::new(stream::from_iter(bytes)));
+    }
+    let file = BufReader::with_capacity(BUF_SIZE, File::open(files.payload).await?);
+    Ok(Box::new(file.bytes()))
+}
+
+pub async fn output<S>(output: &mut Output, stream: S) -> Result<()>
 where
     S: Stream<Item = u8> + std::marker::Unpin,
 {
-    let mut output = BufWriter::with_capacity(
-        BUF_SIZE,
-        OpenOptions::new()
-            .create(true)
-            .write(true)
-            .open("result.mp4")
-            .await?,
-    );
-    while let Some(b) = stream.next().await {
-        let buf = vec![b];
-        output.write(&buf[..]).await?;
-    }
-    output.flush().await?;
-    Ok(())
+    output_chunks(output, chunks(stream)).await
+}
+
+/// Like [`input`] a chunk of up to [`CHUNK_SIZE`] bytes at a time.
+pub async fn input_chunks(
+) -> Result<Box<dyn Stream<Item = std::io::Result<Vec<u8>>> + Send + Unpin>> {
+    files().source().chunks().await
+}
+
+/// Like [`output`] for a stream of chunks.
+pub async fn output_chunks<S>(output: &mut Output, mut stream: S) -> Result<()>
+where
+    S: Stream<Item = Vec<u8>> + std::marker::Unpin,
+{
+    let mut writer = Writer::open(output).await?;
+    while let Some(chunk) = stream.next().await {
+        writer.write(&chunk).await?;
+    }
+    writer.finish().await
+}
+
+// writes a decoded payload piece by piece into the temporary file of the
+// output, if it has one, comparing every piece with the payload.
+struct Writer<'a> {
+    file: Option<BufWriter<File>>,
+    comparison: Comparison,
+    residuals: &'a mut Residuals,
+}
+
+impl<'a> Writer<'a> {
+    async fn open(output: &'a mut Output) -> Result<Self> {
+        let file = match &output.file {
+            Some((temp, _)) => Some(BufWriter::with_capacity(
+                BUF_SIZE,
+                OpenOptions::new()
+                    .write(true)
+                    .truncate(true)
+                    .open(temp.path())
+                    .await?,
+            )),
+            None => None,
+        };
+        Ok(Self {
+            file,
+            comparison: Comparison::open(&files().source()).await?,
+            residuals: &mut output.residuals,
+        })
+    }
+
+    async fn write(&mut self, bytes: &[u8]) -> Result<()> {
+        if let Some(file) = &mut self.file {
+            file.write_all(bytes).await?;
+        }
+        self.comparison.compare(bytes).await
+    }
+
+    async fn finish(self) -> Result<()> {
+        if let Some(mut file) = self.file {
+            file.flush().await?;
+        }
+        *self.residuals = self.comparison.finish().await?;
+        Ok(())
+    }
+}
+
+/// What the stages of [`pipeline!`] pass on, single bytes or chunks of them.
+pub trait StreamItem {
+    fn byte_count(&self) -> u64;
+}
+
+impl StreamItem for u8 {
+    fn byte_count(&self) -> u64 {
+        1
+    }
+}
+
+impl StreamItem for Vec<u8> {
+    fn byte_count(&self) -> u64 {
+        self.len() as u64
+    }
+}
+
+/// How full a queue between two stages was, looked at whenever the stage
+/// before it passed on an item.
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+pub struct QueueStats {
+    pub capacity: usize,
+    pub items: u64,
+    pub max_depth: usize,
+    depth_sum: u64,
+    /// Items the stage had to wait with because the queue was full, the
+    /// stage after it being slower.
+    pub full: u64,
+}
+
+impl QueueStats {
+    pub fn mean_depth(&self) -> f64 {
+        self.depth_sum as f64 / self.items.max(1) as f64
+    }
+}
+
+type Pump<'a> = Pin<Box<dyn Future<Output = ()> + 'a>>;
+
+/// The bounded queues between the stages of a run, see
+/// [`set_queue_capacity`]. Every queue is filled by a pump of its own, the
+/// pumps and the last stage all make progress in the task of the run, a full
+/// queue holding back the stages before it.
+pub struct Queues<'a> {
+    capacity: usize,
+    pumps: RefCell<Vec<Pump<'a>>>,
+    stats: RefCell<Vec<(String, Rc<Cell<QueueStats>>)>>,
+}
+
+impl Default for Queues<'_> {
+    fn default() -> Self {
+        Self {
+            capacity: QUEUE_CAPACITY.load(Ordering::Relaxed),
+            pumps: RefCell::new(vec![]),
+            stats: RefCell::new(vec![]),
+        }
+    }
+}
+
+impl<'a> Queues<'a> {
+    /// Puts a queue after the stage giving the stream, or passes the stream
+    /// on as it is without queues.
+    pub fn add<S>(&self, stage: &str, mut stream: S) -> Queued<S>
+    where
+        S: Stream + Unpin + 'a,
+        S::Item: 'a,
+    {
+        if self.capacity == 0 {
+            return Queued::Direct(stream);
+        }
+        let (sender, receiver) = channel::bounded(self.capacity);
+        let stats = Rc::new(Cell::new(QueueStats {
+            capacity: self.capacity,
+            ..QueueStats::default()
+        }));
+        self.stats
+            .borrow_mut()
+            .push((stage.to_string(), Rc::clone(&stats)));
+        self.pumps.borrow_mut().push(Box::pin(async move {
+            while let Some(item) = stream.next().await {
+                let mut queue = stats.get();
+                let depth = sender.len();
+                queue.items += 1;
+                queue.max_depth = queue.max_depth.max(depth);
+                queue.depth_sum += depth as u64;
+                queue.full += sender.is_full() as u64;
+                stats.set(queue);
+                // the receiver only goes away if the run failed.
+                if sender.send(item).await.is_err() {
+                    break;
+                }
+            }
+        }));
+        Queued::Queue(Box::pin(receiver))
+    }
+
+    /// Runs the last stage and every pump to the end and returns how full
+    /// the queues were.
+    pub async fn drive<F>(self, last: F) -> Result<Vec<(String, QueueStats)>>
+    where
+        F: Future<Output = Result<()>>,
+    {
+        let pumps =
+            self.pumps
+                .into_inner()
+                .into_iter()
+                .fold(Box::pin(async {}) as Pump, |pumps, pump| {
+                    Box::pin(async move {
+                        pumps.join(pump).await;
+                    })
+                });
+        last.join(pumps).await.0?;
+        Ok(self
+            .stats
+            .into_inner()
+            .into_iter()
+            .map(|(stage, stats)| (stage, stats.get()))
+            .collect())
+    }
+}
+
+/// The stream after a stage, through its queue if there is one.
+pub enum Queued<S: Stream> {
+    Direct(S),
+    Queue(Pin<Box<channel::Receiver<S::Item>>>),
+}
+
+impl<S: Stream + Unpin> Stream for Queued<S> {
+    type Item = S::Item;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<S::Item>> {
+        match &mut *self {
+            Queued::Direct(stream) => Pin::new(stream).poll_next(cx),
+            Queued::Queue(receiver) => receiver.as_mut().poll_next(cx),
+        }
+    }
+}
+
+/// Time the stages of a [`crate::pipeline!`] run spent working. Every stage
+/// is timed while it sets up its stream and while the stream is polled, less
+/// the time it waited for the stage before it.
+#[derive(Default)]
+pub struct BusyTime {
+    stages: RefCell<Vec<(String, StageTimer)>>,
+    // the index of the channel among the stages.
+    channel: Cell<Option<usize>>,
+}
+
+impl BusyTime {
+    pub fn stage(&self, stage: &str) -> StageTimer {
+        let timer = StageTimer::default();
+        self.stages
+            .borrow_mut()
+            .push((stage.to_string(), timer.clone()));
+        timer
+    }
+
+    /// Like [`BusyTime::stage`] for the channel, the stages before it
+    /// encoding and the ones after it decoding.
+    pub fn channel(&self) -> StageTimer {
+        self.channel.set(Some(self.stages.borrow().len()));
+        self.stage("channel")
+    }
+
+    /// The time of every stage, and summed up per side of the channel.
+    pub fn stages(self) -> (Vec<(String, Duration)>, Option<StageTime>) {
+        let stages: Vec<_> = self
+            .stages
+            .into_inner()
+            .into_iter()
+            .map(|(stage, timer)| (stage, timer.busy.get().saturating_sub(timer.waiting.get())))
+            .collect();
+        let sum = |stages: &[(String, Duration)]| stages.iter().map(|(_, busy)| *busy).sum();
+        let stage_time = self.channel.get().map(|channel| StageTime {
+            encode: sum(&stages[..channel]),
+            channel: stages[channel].1,
+            decode: sum(&stages[channel + 1..]),
+        });
+        (stages, stage_time)
+    }
+}
+
+/// The busy time of a run on either side of the channel and in it.
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+pub struct StageTime {
+    #[serde(with = "seconds")]
+    pub encode: Duration,
+    #[serde(with = "seconds")]
+    pub channel: Duration,
+    #[serde(with = "seconds")]
+    pub decode: Duration,
+}
+
+/// Times a stage of [`BusyTime`], its input apart from the rest.
+#[derive(Default, Clone)]
+pub struct StageTimer {
+    busy: Rc<Cell<Duration>>,
+    waiting: Rc<Cell<Duration>>,
+}
+
+impl StageTimer {
+    /// The stream the stage reads from, its time spent waiting.
+    pub fn input<S>(&self, stream: S) -> Timed<S> {
+        Timed {
+            stream,
+            time: Rc::clone(&self.waiting),
+        }
+    }
+
+    /// The stream the stage gives, its time spent busy.
+    pub fn output<S>(&self, stream: S) -> Timed<S> {
+        Timed {
+            stream,
+            time: Rc::clone(&self.busy),
+        }
+    }
+
+    /// The stage setting up its stream.
+    pub async fn call<F: Future>(&self, stage: F) -> F::Output {
+        let start = Instant::now();
+        let output = stage.await;
+        self.busy.set(self.busy.get() + start.elapsed());
+        output
+    }
+}
+
+/// A stream adding the time it is polled up, see [`StageTimer`].
+pub struct Timed<S> {
+    stream: S,
+    time: Rc<Cell<Duration>>,
+}
+
+impl<S: Stream + Unpin> Stream for Timed<S> {
+    type Item = S::Item;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<S::Item>> {
+        let start = Instant::now();
+        let item = Pin::new(&mut self.stream).poll_next(cx);
+        self.time.set(self.time.get() + start.elapsed());
+        item
+    }
+}
+
+/// The bytes of the chunks one by one, to feed a chunked stream to a
+/// byte-wise stage.
+pub fn bytes<S>(stream: S) -> impl Stream<Item = u8> + Unpin
+where
+    S: Stream<Item = Vec<u8>> + Unpin,
+{
+    stream.flat_map(stream::from_iter)
+}
+
+/// The bytes in chunks of [`CHUNK_SIZE`], to pass on what a byte-wise stage
+/// gives in a chunked pipeline.
+pub fn chunks<S>(stream: S) -> impl Stream<Item = Vec<u8>> + Unpin
+where
+    S: Stream<Item = u8> + Unpin,
+{
+    blocks(stream, CHUNK_SIZE)
+}
+
+/// The bytes in blocks of `size`, the last one possibly shorter, for stages
+/// coding a block at a time instead of holding on to the whole payload.
+pub fn blocks<S>(stream: S, size: usize) -> impl Stream<Item = Vec<u8>> + Unpin
+where
+    S: Stream<Item = u8> + Unpin,
+{
+    Chunks {
+        stream,
+        size,
+        chunk: Vec::with_capacity(size),
+    }
+}
+
+// reads a file a chunk at a time.
+struct ReadChunks(File);
+
+impl Stream for ReadChunks {
+    type Item = std::io::Result<Vec<u8>>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<std::io::Result<Vec<u8>>>> {
+        let mut chunk = vec![0; CHUNK_SIZE];
+        match Pin::new(&mut self.0).poll_read(cx, &mut chunk) {
+            Poll::Ready(Ok(0)) => Poll::Ready(None),
+            Poll::Ready(Ok(read)) => {
+                chunk.truncate(read);
+                Poll::Ready(Some(Ok(chunk)))
+            }
+            Poll::Ready(Err(error)) => Poll::Ready(Some(Err(error))),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+/// Ends the input of a `pipeline!` run at the first error reading it, which
+/// [`ReadError::check`] returns once the run is over, the stages only ever
+/// seeing the bytes.
+#[derive(Debug, Clone, Default)]
+pub struct ReadError(Arc<std::sync::Mutex<Option<std::io::Error>>>);
+
+impl ReadError {
+    pub fn until<S, T>(&self, stream: S) -> UntilError<S>
+    where
+        S: Stream<Item = std::io::Result<T>> + Unpin,
+    {
+        UntilError {
+            stream,
+            error: self.clone(),
+        }
+    }
+
+    pub fn check(&self) -> Result<()> {
+        match self.0.lock().unwrap().take() {
+            Some(error) => Err(error).wrap_err("failed to read the payload"),
+            None => Ok(()),
+        }
+    }
+}
+
+/// The stream of [`ReadError::until`].
+pub struct UntilError<S> {
+    stream: S,
+    error: ReadError,
+}
+
+impl<S, T> Stream for UntilError<S>
+where
+    S: Stream<Item = std::io::Result<T>> + Unpin,
+{
+    type Item = T;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        if self.error.0.lock().unwrap().is_some() {
+            return Poll::Ready(None);
+        }
+        match Pin::new(&mut self.stream).poll_next(cx) {
+            Poll::Ready(Some(Ok(item))) => Poll::Ready(Some(item)),
+            Poll::Ready(Some(Err(error))) => {
+                *self.error.0.lock().unwrap() = Some(error);
+                Poll::Ready(None)
+            }
+            Poll::Ready(None) => Poll::Ready(None),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+// collects the bytes of a stream into chunks of size bytes.
+struct Chunks<S> {
+    stream: S,
+    size: usize,
+    chunk: Vec<u8>,
+}
+
+impl<S: Stream<Item = u8> + Unpin> Stream for Chunks<S> {
+    type Item = Vec<u8>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Vec<u8>>> {
+        loop {
+            match Pin::new(&mut self.stream).poll_next(cx) {
+                Poll::Ready(Some(byte)) => {
+                    self.chunk.push(byte);
+                    if self.chunk.len() == self.size {
+                        let size = self.size;
+                        let chunk = std::mem::replace(&mut self.chunk, Vec::with_capacity(size));
+                        return Poll::Ready(Some(chunk));
+                    }
+                }
+                Poll::Ready(None) if self.chunk.is_empty() => return Poll::Ready(None),
+                Poll::Ready(None) => return Poll::Ready(Some(std::mem::take(&mut self.chunk))),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{analytics, coder::Uncoded};
+    use rand::{rngs::StdRng, RngCore, SeedableRng};
+
+    // appends its tag on the way to the channel and takes it off on the way
+    // back, failing if another tag is last.
+    struct Tag(u8);
+
+    impl Coder for Tag {
+        fn name(&self) -> String {
+            (self.0 as char).to_string()
+        }
+
+        fn encode(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+            let mut encoded = data.to_vec();
+            encoded.push(self.0);
+            Ok(encoded)
+        }
+
+        fn decode(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+            match data.split_last() {
+                Some((&tag, rest)) if tag == self.0 => Ok(rest.to_vec()),
+                _ => Err(color_eyre::eyre::eyre!("{} is not last", self.name())),
+            }
+        }
+    }
+
+    #[test]
+    fn builder_applies_stages_in_order_and_back_in_reverse() {
+        let mut pipeline = PipelineBuilder::new()
+            .stage(Box::new(Tag(b'a')))
+            .stage(Box::new(Tag(b'b')))
+            .build();
+        assert_eq!(pipeline.stages.name(), "a + b");
+        let encoded = pipeline.stages.encode(b"payload").unwrap();
+        assert_eq!(encoded, b"payloadab");
+        assert_eq!(pipeline.stages.decode(&encoded).unwrap(), b"payload");
+    }
+
+    #[test]
+    fn uncoded_layers_are_left_out_of_the_name() {
+        let pipeline = PipelineBuilder::new()
+            .stage(Box::new(Uncoded))
+            .stage(Box::new(crate::compression::Compressed::new(
+                64,
+                Box::new(Uncoded),
+            )))
+            .stage(Box::new(Tag(b'a')))
+            .build();
+        assert_eq!(pipeline.stages.name(), "zlib/64 + a");
+        let pipeline = PipelineBuilder::new().stage(Box::new(Uncoded)).build();
+        assert_eq!(pipeline.stages.name(), "Uncoded");
+    }
+
+    #[test]
+    fn empty_builder_is_uncoded() {
+        let mut pipeline = PipelineBuilder::new().build();
+        assert_eq!(pipeline.stages.name(), "Uncoded");
+        assert_eq!(pipeline.stages.encode(b"payload").unwrap(), b"payload");
+    }
+
+    #[test]
+    fn read_errors_end_the_input_and_fail_the_run() {
+        task::block_on(async {
+            // a directory opens fine but reads fail.
+            let source = Source::File(std::env::temp_dir());
+            let mut chunks = source.chunks().await.unwrap();
+            assert!(chunks.next().await.unwrap().is_err());
+
+            let read_error = ReadError::default();
+            let stream = read_error.until(source.chunks().await.unwrap());
+            assert_eq!(stream.collect::<Vec<_>>().await.len(), 0);
+            let error = read_error.check().unwrap_err();
+            assert!(error.to_string().contains("failed to read the payload"));
+
+            let read_error = ReadError::default();
+            let bytes = Source::Memory {
+                name: PathBuf::from("memory"),
+                bytes: Arc::from(&b"payload"[..]),
+            };
+            let stream = read_error.until(bytes.chunks().await.unwrap());
+            assert_eq!(stream.collect::<Vec<_>>().await, [b"payload".to_vec()]);
+            read_error.check().unwrap();
+        });
+    }
+
+    #[test]
+    fn in_memory_payload_round_trips() {
+        let _lock = FILES_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut payload = vec![0; 10_000];
+        StdRng::seed_from_u64(1).fill_bytes(&mut payload);
+        set_files(Files::in_memory("random", payload));
+        task::block_on(async {
+            // the whole stream and the block path, both without errors.
+            let mut channel = Channel::new(0.0, 0.1).with_seed(1);
+            let mut pipeline = PipelineBuilder::new().stage(Box::new(Tag(b'a'))).build();
+            let run_metrics = pipeline.run(&mut channel).await.unwrap();
+            let analytics = analytics::analyze(&channel, run_metrics).await.unwrap();
+            assert_eq!(analytics.payload(), "random");
+            assert_eq!(analytics.residual_bit_errors(), 0);
+            assert_eq!(analytics.channel_bit_errors(), 0);
+
+            let mut channel = Channel::new(0.0, 0.1).with_seed(1);
+            let run_metrics = run(&mut Uncoded, &NoInterleaver, &mut channel)
+                .await
+                .unwrap();
+            assert_eq!(run_metrics.input_byte_count, 10_000);
+            let analytics = analytics::analyze(&channel, run_metrics).await.unwrap();
+            assert_eq!(analytics.residual_bit_errors(), 0);
+
+            // a noisy channel leaves errors the comparison finds.
+            let mut channel = Channel::new(0.1, 0.05).with_seed(1);
+            let run_metrics = run(&mut Uncoded, &NoInterleaver, &mut channel)
+                .await
+                .unwrap();
+            let analytics = analytics::analyze(&channel, run_metrics).await.unwrap();
+            assert!(analytics.channel_bit_errors() > 0);
+            assert!(analytics.residual_bit_errors() > 0);
+        });
+    }
 }
@@ -0,0 +1,155 @@
+use color_eyre::eyre::{eyre, Result};
+use plotters::{coord::Shift, prelude::*};
+use std::path::Path;
+
+use crate::analytics::Analytics;
+
+const SIZE: (u32, u32) = (900, 600);
+
+/// Plots the residual bit error rate of every run over the bit error rate of
+/// its channel, both on a logarithmic scale, a curve per coder. Runs without
+/// errors on either side have no place on the scale and are left out. The
+/// extension of the file, svg or png, picks the format.
+pub fn ber_curves(analytics: &[Analytics], path: &Path) -> Result<()> {
+    let mut curves: Vec<(String, Vec<(f64, f64)>)> = vec![];
+    for run in analytics.iter().map(Analytics::summary) {
+        if run.channel_errors == 0 || run.residual_errors == 0 {
+            continue;
+        }
+        let point = (
+            run.channel_errors as f64 / run.channel_bits as f64,
+            run.residual_errors as f64 / run.input_bits as f64,
+        );
+        let name = match run.interleaver.as_str() {
+            "None" => run.coder,
+            interleaver => format!("{}, {}", run.coder, interleaver),
+        };
+        match curves.iter_mut().find(|(curve, _)| *curve == name) {
+            Some((_, points)) => points.push(point),
+            None => curves.push((name, vec![point])),
+        }
+    }
+    for (_, points) in &mut curves {
+        points.sort_by(|a, b| a.0.total_cmp(&b.0));
+    }
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some("svg") => draw(SVGBackend::new(path, SIZE).into_drawing_area(), &curves),
+        Some("png") => draw(BitMapBackend::new(path, SIZE).into_drawing_area(), &curves),
+        _ => Err(eyre!(
+            "cannot plot to {}, expected a .svg or .png file",
+            path.display()
+        )),
+    }
+}
+
+fn draw<DB: DrawingBackend>(
+    root: DrawingArea<DB, Shift>,
+    curves: &[(String, Vec<(f64, f64)>)],
+) -> Result<()>
+where
+    DB::ErrorType: 'static,
+{
+    if curves.is_empty() {
+        return Err(eyre!("no run with errors to plot"));
+    }
+    // a decade of room around the points, up to an error rate of one.
+    let range = |rates: Vec<f64>| {
+        let low = rates.iter().copied().fold(1.0, f64::min) / 10.0;
+        let high = rates.iter().copied().fold(0.0, f64::max) * 10.0;
+        low..high.min(1.0)
+    };
+    let points = curves.iter().flat_map(|(_, points)| points);
+    let x = range(points.clone().map(|point| point.0).collect());
+    let y = range(points.map(|point| point.1).collect());
+
+    root.fill(&WHITE)?;
+    let mut chart = ChartBuilder::on(&root)
+        .caption("Residual bit error rate", ("sans-serif", 24))
+        .margin(20)
+        .x_label_area_size(50)
+        .y_label_area_size(70)
+        .build_cartesian_2d(x.log_scale(), y.log_scale())?;
+    chart
+        .configure_mesh()
+        .x_desc("Channel bit error rate")
+        .y_desc("Residual bit error rate")
+        .x_label_formatter(&|rate| format!("{:.0e}", rate))
+        .y_label_formatter(&|rate| format!("{:.0e}", rate))
+        .draw()?;
+    for (index, (name, points)) in curves.iter().enumerate() {
+        let color = Palette99::pick(index).to_rgba();
+        chart
+            .draw_series(LineSeries::new(
+                points.iter().copied(),
+                color.stroke_width(2),
+            ))?
+            .label(name)
+            .legend(move |(x, y)| {
+                PathElement::new(vec![(x, y), (x + 20, y)], color.stroke_width(2))
+            });
+        chart.draw_series(
+            points
+                .iter()
+                .map(|&point| Circle::new(point, 3, color.filled())),
+        )?;
+    }
+    chart
+        .configure_series_labels()
+        .position(SeriesLabelPosition::LowerRight)
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .draw()?;
+    root.present()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        analytics,
+        channel::Channel,
+        coder::Uncoded,
+        interleaver::NoInterleaver,
+        pipeline::{self, set_files, Files, FILES_LOCK},
+    };
+    use async_std::task;
+
+    // the uncoded runs over binary symmetric channels flipping the share of
+    // the bits given.
+    fn runs(error_rates: &[f64]) -> Vec<Analytics> {
+        let _lock = FILES_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        set_files(Files::in_memory("payload", vec![0x5a; 4096]));
+        task::block_on(async {
+            let mut runs = vec![];
+            for &error_rate in error_rates {
+                let mut channel = Channel::gilbert_elliott(0.0, 1.0, error_rate, 0.0).with_seed(1);
+                let run_metrics = pipeline::run(&mut Uncoded, &NoInterleaver, &mut channel)
+                    .await
+                    .unwrap();
+                runs.push(analytics::analyze(&channel, run_metrics).await.unwrap());
+            }
+            runs
+        })
+    }
+
+    #[test]
+    fn curves_are_drawn_for_runs_with_errors() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("ber.svg");
+        ber_curves(&runs(&[0.01, 0.05, 0.0]), &path).unwrap();
+        let svg = std::fs::read_to_string(&path).unwrap();
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.contains("Uncoded"));
+        assert!(svg.contains("Channel bit error rate"));
+    }
+
+    #[test]
+    fn nothing_to_plot_or_no_format_fails() {
+        let directory = tempfile::tempdir().unwrap();
+        let error = ber_curves(&runs(&[0.0]), &directory.path().join("ber.svg")).unwrap_err();
+        assert!(error.to_string().contains("no run with errors"));
+        let error = ber_curves(&runs(&[0.05]), &directory.path().join("ber.txt")).unwrap_err();
+        assert!(error.to_string().contains("expected a .svg or .png file"));
+    }
+}
@@ -0,0 +1,113 @@
+use color_eyre::eyre::{bail, eyre, Result, WrapErr};
+use libloading::Library;
+use std::{
+    ffi::{c_char, CStr},
+    path::Path,
+    sync::Arc,
+};
+
+use crate::coder::Coder;
+
+/// Version of the entry points below, a plugin reports the one it was
+/// built against from `mmcp_abi_version`.
+pub const ABI_VERSION: u32 = 1;
+
+// `mmcp_encode` and `mmcp_decode`: input bytes and their count in, a buffer
+// the plugin allocated and its length out. zero on success.
+type CodeFn = unsafe extern "C" fn(*const u8, usize, *mut *mut u8, *mut usize) -> i32;
+// `mmcp_free`: hands a buffer from `mmcp_encode` or `mmcp_decode` back.
+type FreeFn = unsafe extern "C" fn(*mut u8, usize);
+// `mmcp_name`: a static, NUL terminated name shown in the report.
+type NameFn = unsafe extern "C" fn() -> *const c_char;
+type VersionFn = unsafe extern "C" fn() -> u32;
+
+/// A coder loaded from a shared library exporting the C entry points
+/// `mmcp_abi_version`, `mmcp_name`, `mmcp_encode`, `mmcp_decode` and
+/// `mmcp_free`. Copies share the loaded library.
+#[derive(Clone)]
+pub struct Plugin {
+    // keeps the entry points below valid.
+    _library: Arc<Library>,
+    name: String,
+    encode: CodeFn,
+    decode: CodeFn,
+    free: FreeFn,
+}
+
+impl Plugin {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        // loading runs the initializers of the library, the user vouches for it.
+        let library = unsafe { Library::new(path) }
+            .wrap_err_with(|| format!("failed to load plugin {}", path.display()))?;
+        let symbol = |name: &str| eyre!("plugin {} does not export {}", path.display(), name);
+        // the signatures are the ones this module documents for each name.
+        let (version, name, encode, decode, free) = unsafe {
+            (
+                *library
+                    .get::<VersionFn>(b"mmcp_abi_version\0")
+                    .map_err(|_| symbol("mmcp_abi_version"))?,
+                *library
+                    .get::<NameFn>(b"mmcp_name\0")
+                    .map_err(|_| symbol("mmcp_name"))?,
+                *library
+                    .get::<CodeFn>(b"mmcp_encode\0")
+                    .map_err(|_| symbol("mmcp_encode"))?,
+                *library
+                    .get::<CodeFn>(b"mmcp_decode\0")
+                    .map_err(|_| symbol("mmcp_decode"))?,
+                *library
+                    .get::<FreeFn>(b"mmcp_free\0")
+                    .map_err(|_| symbol("mmcp_free"))?,
+            )
+        };
+        let version = unsafe { version() };
+        if version != ABI_VERSION {
+            bail!(
+                "plugin {} was built for version {} of the plugin interface, not {}",
+                path.display(),
+                version,
+                ABI_VERSION
+            );
+        }
+        let name = unsafe { CStr::from_ptr(name()) }
+            .to_string_lossy()
+            .into_owned();
+        Ok(Self {
+            _library: Arc::new(library),
+            name,
+            encode,
+            decode,
+            free,
+        })
+    }
+
+    fn call(&self, code: CodeFn, data: &[u8]) -> Result<Vec<u8>> {
+        let (mut output, mut len) = (std::ptr::null_mut(), 0);
+        let status = unsafe { code(data.as_ptr(), data.len(), &mut output, &mut len) };
+        if status != 0 {
+            bail!("plugin {} failed with status {}", self.name, status);
+        }
+        if output.is_null() {
+            return Ok(vec![]);
+        }
+        // the plugin handed over `len` initialized bytes until `mmcp_free`.
+        let result = unsafe { std::slice::from_raw_parts(output, len) }.to_vec();
+        unsafe { (self.free)(output, len) };
+        Ok(result)
+    }
+}
+
+impl Coder for Plugin {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn encode(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+        self.call(self.encode, data)
+    }
+
+    fn decode(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+        self.call(self.decode, data)
+    }
+}
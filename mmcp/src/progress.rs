@@ -0,0 +1,126 @@
+use std::{
+    io::{IsTerminal, Write},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use crate::{channel::Channel, pipeline};
+
+// time between two redraws of the progress line, whichever run draws it.
+const REDRAW: Duration = Duration::from_millis(100);
+
+// off unless standard error is a terminal, settled with the options.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+// when the progress line was drawn last.
+static DRAWN: Mutex<Option<Instant>> = Mutex::new(None);
+
+/// Shows the progress of the runs from now on if standard error is a
+/// terminal and `enabled` is true.
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(
+        enabled && std::io::stderr().is_terminal(),
+        Ordering::Relaxed,
+    );
+}
+
+/// The progress of a run on a line of standard error, the bytes every stage
+/// passed on and the share of the payload decoded, with the time left at
+/// the pace so far. Runs at the same time, as with `--jobs`, take turns.
+pub struct Progress {
+    run: String,
+    payload_bytes: u64,
+    start: Instant,
+    decoded: AtomicU64,
+    stages: Mutex<Vec<(String, u64)>>,
+}
+
+impl Progress {
+    /// The progress of the coder sending the payload over the channel.
+    pub fn start(coder: &str, channel: &Channel) -> Self {
+        let files = pipeline::files();
+        let payload_bytes = match &files.memory {
+            Some(payload) => payload.len() as u64,
+            None => std::fs::metadata(&files.payload).map_or(0, |metadata| metadata.len()),
+        };
+        Self {
+            run: format!("{}, {}", coder, channel.channel_information().model()),
+            payload_bytes,
+            start: Instant::now(),
+            decoded: AtomicU64::new(0),
+            stages: Mutex::new(vec![]),
+        }
+    }
+
+    /// Adds bytes the stage passed on.
+    pub fn stage(&self, stage: &str, bytes: u64) {
+        let mut stages = self.stages.lock().unwrap();
+        match stages.iter_mut().find(|(name, _)| name == stage) {
+            Some((_, total)) => *total += bytes,
+            None => stages.push((stage.to_string(), bytes)),
+        }
+        drop(stages);
+        self.draw();
+    }
+
+    /// Adds bytes of the decoded payload.
+    pub fn decoded(&self, bytes: u64) {
+        self.decoded.fetch_add(bytes, Ordering::Relaxed);
+        self.draw();
+    }
+
+    fn draw(&self) {
+        if !ENABLED.load(Ordering::Relaxed) {
+            return;
+        }
+        let Ok(mut drawn) = DRAWN.try_lock() else {
+            return;
+        };
+        if drawn.is_some_and(|drawn| drawn.elapsed() < REDRAW) {
+            return;
+        }
+        *drawn = Some(Instant::now());
+        let done = self.decoded.load(Ordering::Relaxed) as f64 / self.payload_bytes.max(1) as f64;
+        let stages = self
+            .stages
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(stage, bytes)| format!("{} {}", stage, size(*bytes)))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let left = match done > 0.0 {
+            true => format!(
+                "{:.0} s left",
+                self.start.elapsed().as_secs_f64() * (1.0 - done.min(1.0)) / done
+            ),
+            false => "-".to_string(),
+        };
+        eprint!(
+            "\r\x1b[K{}: {:.0}% ({}), {}",
+            self.run,
+            done.min(1.0) * 100.0,
+            stages,
+            left
+        );
+        let _ = std::io::stderr().flush();
+    }
+}
+
+// takes the line away, the report comes next.
+impl Drop for Progress {
+    fn drop(&mut self) {
+        if ENABLED.load(Ordering::Relaxed) {
+            eprint!("\r\x1b[K");
+        }
+    }
+}
+
+fn size(bytes: u64) -> String {
+    match bytes {
+        0..1_000_000 => format!("{:.1} kB", bytes as f64 / 1e3),
+        _ => format!("{:.1} MB", bytes as f64 / 1e6),
+    }
+}
@@ -0,0 +1,185 @@
+use color_eyre::eyre::Result;
+
+use crate::{
+    coder::{Coder, CodewordStats, SegmentStats},
+    framing::certain_llrs,
+    to_byte,
+};
+
+/// Splits the bits of a mother code into increments sent one after the
+/// other. Every increment lists the bit indexes, in [`crate::to_bits`] order
+/// and counted within a period of `period` bits, it sends. Bits in no
+/// increment are never sent and reach the decoder erased.
+#[derive(Debug, Clone)]
+pub struct PuncturePattern {
+    period: usize,
+    // whether an increment sends the bit at each index of the period.
+    increments: Vec<Vec<bool>>,
+}
+
+impl PuncturePattern {
+    pub fn new(period: usize, increments: Vec<Vec<usize>>) -> Self {
+        assert!(
+            period > 0 && !increments.is_empty(),
+            "a puncture pattern needs a period and an increment"
+        );
+        let increments = increments
+            .into_iter()
+            .map(|indexes| {
+                let mut sent = vec![false; period];
+                for index in indexes {
+                    assert!(index < period, "bit index {} outside the period", index);
+                    sent[index] = true;
+                }
+                sent
+            })
+            .collect();
+        Self { period, increments }
+    }
+
+    pub fn increments(&self) -> usize {
+        self.increments.len()
+    }
+
+    // bits of the period the increment sends.
+    fn count(&self, increment: usize) -> usize {
+        self.increments[increment]
+            .iter()
+            .filter(|&&sent| sent)
+            .count()
+    }
+
+    fn sent(&self, bits: usize, increment: usize) -> impl Iterator<Item = usize> + '_ {
+        (0..bits).filter(move |i| self.increments[increment][i % self.period])
+    }
+
+    /// The bits of the encoded data the increment sends, packed into bytes.
+    pub fn puncture(&self, encoded: &[u8], increment: usize) -> Vec<u8> {
+        let bits = self
+            .sent(8 * encoded.len(), increment)
+            .map(|i| (encoded[i / 8] >> (i % 8)) & 1)
+            .collect::<Vec<_>>();
+        bits.chunks(8).map(to_byte).collect()
+    }
+
+    /// Adds the ratios received for an increment to the ratios of the
+    /// mother code bits they were sent for, so repeated bits combine.
+    pub fn depuncture(&self, llrs: &mut [f32], received: &[f32], increment: usize) {
+        for (i, &llr) in self.sent(llrs.len(), increment).zip(received) {
+            llrs[i] += llr;
+        }
+    }
+}
+
+/// Derives a higher rate code from the wrapped coder by sending only the
+/// bits of its output the first increment of the pattern lists. The others
+/// reach its soft decoder erased.
+pub struct Punctured {
+    pattern: PuncturePattern,
+    inner: Box<dyn Coder>,
+}
+
+impl Punctured {
+    pub fn new(pattern: PuncturePattern, inner: Box<dyn Coder>) -> Self {
+        assert!(
+            pattern.count(0) > 0,
+            "a punctured code sends at least one bit"
+        );
+        Self { pattern, inner }
+    }
+
+    // longest output of the wrapped coder whose punctured bits fit the
+    // received bytes. bytes it adds beyond the original are erased.
+    fn encoded_bytes(&self, received_bits: usize) -> usize {
+        let pattern = &self.pattern;
+        let sent = |bytes: usize| {
+            let bits = 8 * bytes;
+            bits / pattern.period * pattern.count(0)
+                + pattern.increments[0][..bits % pattern.period]
+                    .iter()
+                    .filter(|&&sent| sent)
+                    .count()
+        };
+        let mut bytes = received_bits * self.pattern.period / self.pattern.count(0) / 8;
+        bytes = bytes.saturating_sub(self.pattern.period);
+        while sent(bytes + 1).div_ceil(8) * 8 <= received_bits {
+            bytes += 1;
+        }
+        bytes
+    }
+}
+
+impl Coder for Punctured {
+    fn name(&self) -> String {
+        format!(
+            "{} punctured {}/{}",
+            self.inner.name(),
+            self.pattern.count(0),
+            self.pattern.period
+        )
+    }
+
+    fn encode(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+        Ok(self.pattern.puncture(&self.inner.encode(data)?, 0))
+    }
+
+    fn decode(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+        self.decode_soft(&certain_llrs(data))
+    }
+
+    fn decode_soft(&mut self, llrs: &[f32]) -> Result<Vec<u8>> {
+        let mut encoded = vec![0.0; 8 * self.encoded_bytes(llrs.len())];
+        self.pattern.depuncture(&mut encoded, llrs, 0);
+        self.inner.decode_soft(&encoded)
+    }
+
+    fn shortening(&self, data_bytes: usize) -> u32 {
+        self.inner.shortening(data_bytes)
+    }
+
+    fn codeword_bits(&self) -> Option<usize> {
+        self.inner.codeword_bits()
+    }
+
+    fn codeword_stats(&self) -> Option<CodewordStats> {
+        self.inner.codeword_stats()
+    }
+
+    fn segment_stats(&self) -> Option<SegmentStats> {
+        self.inner.segment_stats()
+    }
+
+    fn layer_stats(&self) -> Vec<(String, SegmentStats)> {
+        self.inner.layer_stats()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{coder::Uncoded, hard_decisions};
+
+    #[test]
+    fn depuncturing_every_increment_restores_the_encoded_bits() {
+        let pattern = PuncturePattern::new(8, vec![vec![0, 2, 4, 6], vec![1, 3, 5, 7]]);
+        let encoded = (0..=255).collect::<Vec<u8>>();
+        let mut llrs = vec![0.0; 8 * encoded.len()];
+        pattern.depuncture(&mut llrs, &certain_llrs(&pattern.puncture(&encoded, 0)), 0);
+        // the bits of the other increment are still erased.
+        assert!(llrs.iter().skip(1).step_by(2).all(|&llr| llr == 0.0));
+        assert!(llrs.iter().step_by(2).all(|&llr| llr != 0.0));
+        pattern.depuncture(&mut llrs, &certain_llrs(&pattern.puncture(&encoded, 1)), 1);
+        assert_eq!(hard_decisions(&llrs), encoded);
+    }
+
+    #[test]
+    fn punctured_bits_reach_the_decoder_erased() {
+        // the most significant bit of every byte is never sent.
+        let pattern = PuncturePattern::new(8, vec![(0..7).collect()]);
+        let mut coder = Punctured::new(pattern, Box::new(Uncoded));
+        let encoded = coder.encode(&[0xff; 10]).unwrap();
+        assert_eq!(encoded.len(), 9);
+        assert_eq!(coder.decode(&encoded).unwrap(), [0x7f; 10]);
+        assert_eq!(coder.name(), "Uncoded punctured 7/8");
+    }
+}
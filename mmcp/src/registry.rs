@@ -0,0 +1,163 @@
+use async_std::{prelude::*, stream};
+use color_eyre::eyre::{eyre, Result};
+use std::future::Future;
+
+use crate::{coder::Coder, pipeline::CHUNK_SIZE};
+
+type Input = stream::FromIter<std::vec::IntoIter<u8>>;
+type ChunkInput = stream::FromIter<std::vec::IntoIter<Vec<u8>>>;
+type Factory = Box<dyn Fn() -> Box<dyn Coder> + Send + Sync>;
+
+/// Coders selectable by name at runtime, each registered with a function
+/// making a fresh one, on whichever thread runs it.
+#[derive(Default)]
+pub struct Registry {
+    coders: Vec<(String, Factory)>,
+}
+
+impl Registry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(
+        mut self,
+        name: &str,
+        coder: impl Fn() -> Box<dyn Coder> + Send + Sync + 'static,
+    ) -> Self {
+        self.coders.push((name.to_string(), Box::new(coder)));
+        self
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.coders.iter().map(|(name, _)| name.as_str()).collect()
+    }
+
+    pub fn create(&self, name: &str) -> Result<Box<dyn Coder>> {
+        self.coders
+            .iter()
+            .find(|(registered, _)| registered == name)
+            .map(|(_, coder)| coder())
+            .ok_or_else(|| {
+                eyre!(
+                    "no coder named {:?}, choose one of {}",
+                    name,
+                    self.names().join(", ")
+                )
+            })
+    }
+}
+
+/// Runs the stream stages written for [`crate::pipeline!`] as a [`Coder`],
+/// so they can be registered next to the others.
+pub struct StreamCoder<E, D> {
+    name: String,
+    encode: E,
+    decode: D,
+}
+
+impl<E, D> StreamCoder<E, D> {
+    pub fn new(name: &str, encode: E, decode: D) -> Self {
+        Self {
+            name: name.to_string(),
+            encode,
+            decode,
+        }
+    }
+}
+
+impl<E, EF, ES, D, DF, DS> Coder for StreamCoder<E, D>
+where
+    E: Fn(Input) -> EF,
+    EF: Future<Output = Result<ES>>,
+    ES: Stream<Item = u8>,
+    D: Fn(Input) -> DF,
+    DF: Future<Output = Result<DS>>,
+    DS: Stream<Item = u8>,
+{
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn encode(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+        through(&self.encode, data)
+    }
+
+    fn decode(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+        through(&self.decode, data)
+    }
+}
+
+// streams the data through a stage and collects what comes out.
+fn through<F, Fut, S>(stage: &F, data: &[u8]) -> Result<Vec<u8>>
+where
+    F: Fn(Input) -> Fut,
+    Fut: Future<Output = Result<S>>,
+    S: Stream<Item = u8>,
+{
+    async_std::task::block_on(async {
+        let mut output = Box::pin(stage(stream::from_iter(data.to_vec())).await?);
+        let mut collected = Vec::with_capacity(data.len());
+        while let Some(byte) = output.next().await {
+            collected.push(byte);
+        }
+        Ok(collected)
+    })
+}
+
+/// Like [`StreamCoder`] for the stages of a chunked [`crate::pipeline!`].
+pub struct ChunkCoder<E, D> {
+    name: String,
+    encode: E,
+    decode: D,
+}
+
+impl<E, D> ChunkCoder<E, D> {
+    pub fn new(name: &str, encode: E, decode: D) -> Self {
+        Self {
+            name: name.to_string(),
+            encode,
+            decode,
+        }
+    }
+}
+
+impl<E, EF, ES, D, DF, DS> Coder for ChunkCoder<E, D>
+where
+    E: Fn(ChunkInput) -> EF,
+    EF: Future<Output = Result<ES>>,
+    ES: Stream<Item = Vec<u8>>,
+    D: Fn(ChunkInput) -> DF,
+    DF: Future<Output = Result<DS>>,
+    DS: Stream<Item = Vec<u8>>,
+{
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn encode(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+        through_chunks(&self.encode, data)
+    }
+
+    fn decode(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+        through_chunks(&self.decode, data)
+    }
+}
+
+// streams the data in chunks through a stage and collects what comes out.
+fn through_chunks<F, Fut, S>(stage: &F, data: &[u8]) -> Result<Vec<u8>>
+where
+    F: Fn(ChunkInput) -> Fut,
+    Fut: Future<Output = Result<S>>,
+    S: Stream<Item = Vec<u8>>,
+{
+    async_std::task::block_on(async {
+        let chunks: Vec<_> = data.chunks(CHUNK_SIZE).map(<[u8]>::to_vec).collect();
+        let mut output = Box::pin(stage(stream::from_iter(chunks)).await?);
+        let mut collected = Vec::with_capacity(data.len());
+        while let Some(chunk) = output.next().await {
+            collected.extend(chunk);
+        }
+        Ok(collected)
+    })
+}
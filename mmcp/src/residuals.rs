@@ -0,0 +1,166 @@
+use async_std::{prelude::*, stream::Stream};
+use color_eyre::eyre::Result;
+
+use crate::{
+    audio::{AudioQuality, Meter, Wav},
+    pipeline::Source,
+};
+
+/// What a run decoded wrong, found comparing the decoded payload with the
+/// payload while it is written, so neither is read again to analyze it, or
+/// comparing any two sources with [`Residuals::between`].
+#[derive(Debug, Default)]
+pub struct Residuals {
+    // the bytes decoded wrong, their index in the payload shifted up by a
+    // byte and the payload byte xored with the decoded one below it.
+    differences: Vec<u64>,
+    bit_errors: u64,
+    payload_length: u64,
+    decoded_length: u64,
+    audio: Option<AudioQuality>,
+}
+
+impl Residuals {
+    /// What `result` got wrong of `reference`, both read a chunk at a time
+    /// side by side.
+    pub async fn between(reference: &Source, result: &Source) -> Result<Self> {
+        let mut comparison = Comparison::open(reference).await?;
+        let mut chunks = result.chunks().await?;
+        while let Some(chunk) = chunks.next().await {
+            comparison.compare(&chunk?).await?;
+        }
+        comparison.finish().await
+    }
+
+    /// The bytes decoded wrong by their index, xored with the payload, in
+    /// order. Missing bytes are not among them.
+    pub fn differences(&self) -> impl Iterator<Item = (u64, u8)> + '_ {
+        self.differences
+            .iter()
+            .map(|difference| (difference >> 8, *difference as u8))
+    }
+
+    /// Bits decoded wrong, not counting missing or extra bytes.
+    pub fn bit_errors(&self) -> u64 {
+        self.bit_errors
+    }
+
+    pub fn payload_length(&self) -> u64 {
+        self.payload_length
+    }
+
+    /// Bytes of the payload at its end the decoded one lacks.
+    pub fn missing_bytes(&self) -> u64 {
+        self.payload_length.saturating_sub(self.decoded_length)
+    }
+
+    /// Bytes the decoded payload has beyond the end of the payload.
+    pub fn extra_bytes(&self) -> u64 {
+        self.decoded_length.saturating_sub(self.payload_length)
+    }
+
+    /// How the decoded audio compares, for WAV payloads.
+    pub fn audio(&self) -> Option<AudioQuality> {
+        self.audio
+    }
+
+    // the decoded bytes against those of the payload at the same index.
+    fn compare(&mut self, index: u64, payload: &[u8], decoded: &[u8]) {
+        // eight bytes at a time, the bytes looked at one by one only where
+        // the words differ.
+        let (payload_words, decoded_words) = (payload.chunks_exact(8), decoded.chunks_exact(8));
+        let rest = payload_words.remainder().len();
+        for (word, (p, d)) in payload_words.zip(decoded_words).enumerate() {
+            let difference = u64::from_le_bytes(p.try_into().unwrap())
+                ^ u64::from_le_bytes(d.try_into().unwrap());
+            if difference != 0 {
+                self.add(index + 8 * word as u64, &difference.to_le_bytes());
+            }
+        }
+        let start = payload.len() - rest;
+        let difference: Vec<u8> = payload[start..]
+            .iter()
+            .zip(&decoded[start..])
+            .map(|(p, d)| p ^ d)
+            .collect();
+        self.add(index + start as u64, &difference);
+    }
+
+    fn add(&mut self, index: u64, differences: &[u8]) {
+        for (byte, &difference) in differences.iter().enumerate() {
+            if difference != 0 {
+                self.bit_errors += difference.count_ones() as u64;
+                self.differences
+                    .push((index + byte as u64) << 8 | difference as u64);
+            }
+        }
+    }
+}
+
+/// Reads the payload along the decoded payload being written and compares
+/// them, see [`Residuals`].
+pub(crate) struct Comparison {
+    payload: Box<dyn Stream<Item = std::io::Result<Vec<u8>>> + Send + Unpin>,
+    // payload bytes read, those before `read` compared already.
+    pending: Vec<u8>,
+    read: usize,
+    residuals: Residuals,
+    audio: Option<Meter>,
+}
+
+impl Comparison {
+    pub(crate) async fn open(payload: &Source) -> Result<Self> {
+        let payload_length = payload.length().await?;
+        let mut payload = payload.chunks().await?;
+        // the header of a WAV file fits in the first chunk.
+        let pending = payload.next().await.transpose()?.unwrap_or_default();
+        let audio = Wav::parse(&pending).map(Meter::new);
+        Ok(Self {
+            payload,
+            pending,
+            read: 0,
+            residuals: Residuals {
+                payload_length,
+                ..Residuals::default()
+            },
+            audio,
+        })
+    }
+
+    /// Compares the next decoded bytes with the payload.
+    pub(crate) async fn compare(&mut self, decoded: &[u8]) -> Result<()> {
+        if self.pending.len() - self.read < decoded.len() {
+            self.pending.drain(..self.read);
+            self.read = 0;
+            while self.pending.len() < decoded.len() {
+                match self.payload.next().await {
+                    Some(chunk) => self.pending.extend(chunk?),
+                    None => break,
+                }
+            }
+        }
+        let length = decoded.len().min(self.pending.len() - self.read);
+        let payload = &self.pending[self.read..self.read + length];
+        let index = self.residuals.decoded_length;
+        self.residuals.compare(index, payload, &decoded[..length]);
+        if let Some(audio) = &mut self.audio {
+            audio.feed(payload, &decoded[..length]);
+        }
+        self.read += length;
+        self.residuals.decoded_length += decoded.len() as u64;
+        Ok(())
+    }
+
+    /// What was decoded wrong, once all of it was compared.
+    pub(crate) async fn finish(mut self) -> Result<Residuals> {
+        // the audio goes on where the decoded payload ended, silent.
+        if let Some(mut audio) = self.audio.take() {
+            audio.feed(&self.pending[self.read..], &[]);
+            while let Some(chunk) = self.payload.next().await {
+                audio.feed(&chunk?, &[]);
+            }
+            self.residuals.audio = Some(audio.finish());
+        }
+        Ok(self.residuals)
+    }
+}
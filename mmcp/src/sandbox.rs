@@ -0,0 +1,109 @@
+use color_eyre::eyre::{eyre, Result};
+use std::path::Path;
+use wasmtime::{
+    Config, Engine, Instance, Memory, Module, Store, StoreLimits, StoreLimitsBuilder, TypedFunc,
+};
+
+use crate::coder::Coder;
+
+/// What a sandboxed coder may use per `encode` or `decode` call.
+#[derive(Debug, Clone, Copy)]
+pub struct SandboxLimits {
+    /// Roughly the WebAssembly instructions executed.
+    pub fuel: u64,
+    pub memory_bytes: usize,
+}
+
+/// A coder compiled to WebAssembly, run in wasmtime within the limits. The
+/// module exports its `memory`, `mmcp_alloc(len) -> ptr` for the input and
+/// `mmcp_encode(ptr, len)` and `mmcp_decode(ptr, len)`, which return the
+/// output as `ptr << 32 | len`. A panic, an exhausted limit or an output
+/// outside the memory fails the call instead of the harness.
+pub struct WasmCoder {
+    name: String,
+    store: Store<StoreLimits>,
+    memory: Memory,
+    alloc: TypedFunc<i32, i32>,
+    encode: TypedFunc<(i32, i32), i64>,
+    decode: TypedFunc<(i32, i32), i64>,
+    fuel: u64,
+}
+
+impl WasmCoder {
+    /// Loads a module in binary or text format, named after its file.
+    pub fn load(path: impl AsRef<Path>, limits: SandboxLimits) -> Result<Self> {
+        let path = path.as_ref();
+        let engine = Engine::new(Config::new().consume_fuel(true)).map_err(wasm_error)?;
+        let module = Module::from_file(&engine, path)
+            .map_err(|error| eyre!("failed to load {}: {:#}", path.display(), error))?;
+        let limits_per_store = StoreLimitsBuilder::new()
+            .memory_size(limits.memory_bytes)
+            .instances(1)
+            .build();
+        let mut store = Store::new(&engine, limits_per_store);
+        store.limiter(|limits| limits);
+        store.set_fuel(limits.fuel).map_err(wasm_error)?;
+        // the coder gets no imports, it can only compute.
+        let instance = Instance::new(&mut store, &module, &[]).map_err(wasm_error)?;
+        let memory = instance
+            .get_memory(&mut store, "memory")
+            .ok_or_else(|| eyre!("{} does not export its memory", path.display()))?;
+        let alloc = instance
+            .get_typed_func(&mut store, "mmcp_alloc")
+            .map_err(wasm_error)?;
+        let encode = instance
+            .get_typed_func(&mut store, "mmcp_encode")
+            .map_err(wasm_error)?;
+        let decode = instance
+            .get_typed_func(&mut store, "mmcp_decode")
+            .map_err(wasm_error)?;
+        let name = path
+            .file_stem()
+            .map_or("wasm".into(), |stem| stem.to_string_lossy());
+        Ok(Self {
+            name: format!("{} (wasm)", name),
+            store,
+            memory,
+            alloc,
+            encode,
+            decode,
+            fuel: limits.fuel,
+        })
+    }
+
+    fn call(&mut self, code: TypedFunc<(i32, i32), i64>, data: &[u8]) -> Result<Vec<u8>> {
+        self.store.set_fuel(self.fuel).map_err(wasm_error)?;
+        let len = i32::try_from(data.len()).map_err(|_| eyre!("input exceeds 2 GiB"))?;
+        let input = self.alloc.call(&mut self.store, len).map_err(wasm_error)?;
+        self.memory
+            .write(&mut self.store, input as u32 as usize, data)
+            .map_err(|_| eyre!("{} allocated outside its memory", self.name))?;
+        let output = code
+            .call(&mut self.store, (input, len))
+            .map_err(wasm_error)?;
+        let (start, len) = ((output >> 32) as u32 as usize, output as u32 as usize);
+        self.memory
+            .data(&self.store)
+            .get(start..start + len)
+            .map(|output| output.to_vec())
+            .ok_or_else(|| eyre!("{} returned output outside its memory", self.name))
+    }
+}
+
+impl Coder for WasmCoder {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn encode(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+        self.call(self.encode.clone(), data)
+    }
+
+    fn decode(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+        self.call(self.decode.clone(), data)
+    }
+}
+
+fn wasm_error(error: wasmtime::Error) -> color_eyre::eyre::Report {
+    eyre!("{:#}", error)
+}
@@ -0,0 +1,60 @@
+use color_eyre::eyre::Result;
+
+use crate::{
+    analytics::{self, Analytics},
+    channel::Channel,
+    coder::Coder,
+    interleaver::Interleaver,
+    pipeline,
+};
+
+/// Grid of Gilbert-Elliott channel parameters a coder is run over, one
+/// report row per point, for residual error curves.
+pub struct Sweep {
+    h: Vec<f64>,
+    tau: Vec<f64>,
+}
+
+impl Sweep {
+    pub fn new(h: Vec<f64>, tau: Vec<f64>) -> Self {
+        Self { h, tau }
+    }
+
+    /// `points` values from `from` to `to`, evenly spaced on a log scale.
+    pub fn log_space(from: f64, to: f64, points: usize) -> Vec<f64> {
+        let step = (to / from).ln() / (points.max(2) - 1) as f64;
+        (0..points)
+            .map(|point| from * (step * point as f64).exp())
+            .collect()
+    }
+
+    /// Symmetric channels for every combination of h and tau, tau varying
+    /// fastest.
+    pub fn channels(&self) -> impl Iterator<Item = Channel> + '_ {
+        self.h
+            .iter()
+            .flat_map(|&h| self.tau.iter().map(move |&tau| Channel::new(h, tau)))
+    }
+
+    pub async fn run(
+        &self,
+        coder: &mut dyn Coder,
+        interleaver: &dyn Interleaver,
+        seed: u64,
+    ) -> Result<Vec<Analytics>> {
+        let mut results = vec![];
+        for channel in self.channels() {
+            let mut channel = channel.with_seed(seed);
+            let run_metrics = pipeline::run(coder, interleaver, &mut channel).await?;
+            results.push(analytics::analyze(&channel, run_metrics).await?);
+        }
+        Ok(results)
+    }
+}
+
+// h from 0.001 to 0.1 against short, medium and long bursts.
+impl Default for Sweep {
+    fn default() -> Self {
+        Self::new(Self::log_space(0.001, 0.1, 7), vec![0.01, 0.05, 0.1])
+    }
+}
@@ -0,0 +1,257 @@
+//! What ideal systems achieve, for holding the runs against it.
+
+use crate::channel::Model;
+
+/// The argument at which the tail of the standard normal distribution is
+/// `p`, for `p` between zero and one, by Acklam's rational approximation.
+pub fn q_inverse(p: f64) -> f64 {
+    const A: [f64; 6] = [
+        -3.969683028665376e1,
+        2.209460984245205e2,
+        -2.759285104469687e2,
+        1.38357751867269e2,
+        -3.066479806614716e1,
+        2.506628277459239,
+    ];
+    const B: [f64; 5] = [
+        -5.447609879822406e1,
+        1.615858368580409e2,
+        -1.556989798598866e2,
+        6.680131188771972e1,
+        -1.328068155288572e1,
+    ];
+    const C: [f64; 6] = [
+        -7.784894002430293e-3,
+        -3.223964580411365e-1,
+        -2.400758277161838,
+        -2.549732539343734,
+        4.374664141464968,
+        2.938163982698783,
+    ];
+    const D: [f64; 4] = [
+        7.784695709041462e-3,
+        3.224671290700398e-1,
+        2.445134137142996,
+        3.754408661907416,
+    ];
+    // where the central approximation gives way to those of the tails.
+    const LOW: f64 = 0.02425;
+    let polynomial = |coefficients: &[f64], x: f64| {
+        coefficients
+            .iter()
+            .fold(0.0, |sum, coefficient| sum * x + coefficient)
+    };
+    let tail = |p: f64| {
+        let q = (-2.0 * p.ln()).sqrt();
+        polynomial(&C, q) / (polynomial(&D, q) * q + 1.0)
+    };
+    // the quantile of the lower tail, the argument of the upper one with
+    // the sign turned.
+    let quantile = if p < LOW {
+        tail(p)
+    } else if p <= 1.0 - LOW {
+        let q = p - 0.5;
+        let r = q * q;
+        polynomial(&A, r) * q / (polynomial(&B, r) * r + 1.0)
+    } else {
+        -tail(1.0 - p)
+    };
+    -quantile
+}
+
+/// The Eb/N0 in dB at which uncoded BPSK over an AWGN channel has the bit
+/// error rate, which has to be below one half.
+pub fn uncoded_eb_n0_db(bit_error_rate: f64) -> f64 {
+    let x = q_inverse(bit_error_rate);
+    10.0 * (x * x / 2.0).log10()
+}
+
+/// The tail of the standard normal distribution beyond `x`.
+pub fn q(x: f64) -> f64 {
+    erfc(x / std::f64::consts::SQRT_2) / 2.0
+}
+
+// the complementary error function, to a relative error below 1.2e-7 by
+// the Chebyshev fit of Numerical Recipes.
+fn erfc(x: f64) -> f64 {
+    const C: [f64; 10] = [
+        0.17087277,
+        -0.82215223,
+        1.48851587,
+        -1.13520398,
+        0.27886807,
+        -0.18628806,
+        0.09678418,
+        0.37409196,
+        1.00002368,
+        -1.26551223,
+    ];
+    let t = 1.0 / (1.0 + x.abs() / 2.0);
+    let polynomial = C.iter().fold(0.0, |sum, coefficient| sum * t + coefficient);
+    let tail = t * (polynomial - x * x).exp();
+    match x >= 0.0 {
+        true => tail,
+        false => 2.0 - tail,
+    }
+}
+
+/// The probability a bit flips on a memoryless channel with the average
+/// error rate of the model, for BPSK at `code_rate` on AWGN channels.
+/// Channels that lose or shift bits have none.
+pub fn crossover(model: &Model, code_rate: f64) -> Option<f64> {
+    match *model {
+        // the bad state takes p / (p + r) of the bits in the long run.
+        Model::GilbertElliott {
+            p,
+            r,
+            good_error,
+            h,
+        } => match p + r > 0.0 {
+            true => Some((good_error * r + h * p) / (p + r)),
+            false => Some(good_error),
+        },
+        Model::Awgn { eb_n0_db } => Some(q((2.0 * code_rate * 10f64.powf(eb_n0_db / 10.0)).sqrt())),
+        Model::Markov { mean_error, .. } => Some(mean_error),
+        Model::PeriodicFade { period, fade, h } => {
+            Some(h * fade.min(period) as f64 / period.max(1) as f64)
+        }
+        Model::PacketLoss { .. } | Model::InsertionDeletion { .. } | Model::Replay { .. } => None,
+    }
+}
+
+/// The payload bit error rate after a Hamming code of length `n`, with the
+/// parity bits at the powers of two, decodes a binary symmetric channel
+/// flipping bits with probability `p`. Exact for every error pattern, so
+/// lengths up to 15 only, None for longer codes or lengths that are no
+/// Hamming code.
+pub fn hamming_bit_error_rate(n: usize, p: f64) -> Option<f64> {
+    if n > 15 || !(n + 1).is_power_of_two() || n < 3 {
+        return None;
+    }
+    let info_positions = (1..=n).filter(|position| !position.is_power_of_two());
+    let info_mask = info_positions.fold(0u32, |mask, position| mask | 1 << (position - 1));
+    let k = info_mask.count_ones();
+    let mut rate = 0.0;
+    for pattern in 0u32..1 << n {
+        // the syndrome points at the one position the decoder flips.
+        let syndrome = (1..=n)
+            .filter(|position| pattern >> (position - 1) & 1 == 1)
+            .fold(0, |syndrome, position| syndrome ^ position);
+        let residual = match syndrome {
+            0 => pattern,
+            _ => pattern ^ 1 << (syndrome - 1),
+        };
+        let flipped = pattern.count_ones() as i32;
+        let probability = p.powi(flipped) * (1.0 - p).powi(n as i32 - flipped);
+        rate += probability * (residual & info_mask).count_ones() as f64 / k as f64;
+    }
+    Some(rate)
+}
+
+/// The entropy in bits of a bit that is one with probability `p`.
+pub fn binary_entropy(p: f64) -> f64 {
+    match p {
+        p if p <= 0.0 || p >= 1.0 => 0.0,
+        p => -p * p.log2() - (1.0 - p) * (1.0 - p).log2(),
+    }
+}
+
+/// The payload bits per channel bit a channel carries at most, that of a
+/// binary symmetric channel with the average error rate of the model, or
+/// of Gaussian inputs at the Es/N0 of `code_rate` on AWGN channels. None
+/// for channels that lose or shift bits.
+pub fn capacity(model: &Model, code_rate: f64) -> Option<f64> {
+    match *model {
+        Model::Awgn { eb_n0_db } => {
+            let es_n0 = code_rate * 10f64.powf(eb_n0_db / 10.0);
+            Some((1.0 + 2.0 * es_n0).log2() / 2.0)
+        }
+        _ => crossover(model, code_rate).map(|p| 1.0 - binary_entropy(p)),
+    }
+}
+
+/// The highest code rate at which any code gets the payload through a
+/// channel of the capacity with the bit error rate, which has to be below
+/// one half: less has to be sent when some of it may arrive wrong.
+pub fn rate_limit(capacity: f64, bit_error_rate: f64) -> f64 {
+    capacity / (1.0 - binary_entropy(bit_error_rate))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a binary symmetric channel flipping bits with probability p.
+    fn symmetric(p: f64) -> Model {
+        Model::GilbertElliott {
+            p: 0.0,
+            r: 0.0,
+            good_error: p,
+            h: 0.0,
+        }
+    }
+
+    fn close(a: f64, b: f64, tolerance: f64) -> bool {
+        (a - b).abs() <= tolerance
+    }
+
+    #[test]
+    fn capacity_at_known_points() {
+        assert_eq!(capacity(&symmetric(0.0), 1.0), Some(1.0));
+        assert_eq!(capacity(&symmetric(0.5), 1.0), Some(0.0));
+        assert_eq!(capacity(&symmetric(1.0), 1.0), Some(1.0));
+        // 1 - H(0.11) is all but one half.
+        assert!(close(capacity(&symmetric(0.11), 1.0).unwrap(), 0.5, 1e-3));
+        // Es/N0 of 1.5 at rate one half and 3 dB Eb/N0: log2(4) / 2.
+        let awgn = Model::Awgn {
+            eb_n0_db: 10.0 * 3f64.log10(),
+        };
+        assert!(close(capacity(&awgn, 0.5).unwrap(), 1.0, 1e-12));
+        let lost = Model::PacketLoss {
+            packet_bytes: 1,
+            loss: 0.1,
+        };
+        assert_eq!(capacity(&lost, 1.0), None);
+    }
+
+    #[test]
+    fn hamming_7_4_matches_the_closed_form() {
+        // residual info bit errors of every weight of error pattern, out of
+        // the 4 info bits: 9 p^2 q^5 + 19 p^3 q^4 + 16 p^4 q^3 + 12 p^5 q^2
+        // + 7 p^6 q + p^7.
+        for p in [0.001f64, 0.01, 0.1, 0.3] {
+            let q = 1.0 - p;
+            let expected = 9.0 * p.powi(2) * q.powi(5)
+                + 19.0 * p.powi(3) * q.powi(4)
+                + 16.0 * p.powi(4) * q.powi(3)
+                + 12.0 * p.powi(5) * q.powi(2)
+                + 7.0 * p.powi(6) * q
+                + p.powi(7);
+            assert!(close(
+                hamming_bit_error_rate(7, p).unwrap(),
+                expected,
+                1e-15
+            ));
+        }
+        assert!(close(
+            hamming_bit_error_rate(7, 0.01).unwrap(),
+            8.742988e-4,
+            1e-9
+        ));
+        assert_eq!(hamming_bit_error_rate(7, 0.0), Some(0.0));
+        assert_eq!(hamming_bit_error_rate(8, 0.01), None);
+        assert_eq!(hamming_bit_error_rate(31, 0.01), None);
+    }
+
+    #[test]
+    fn normal_tail_and_its_inverse_agree() {
+        assert!(close(q(0.0), 0.5, 1e-7));
+        for p in [1e-6, 0.01, 0.1, 0.4] {
+            assert!(close(q(q_inverse(p)), p, 1e-6 * p.max(1e-3)));
+        }
+        // BPSK needs 9.6 dB for a bit error rate of 1e-5.
+        assert!(close(uncoded_eb_n0_db(1e-5), 9.59, 0.01));
+        assert_eq!(binary_entropy(0.5), 1.0);
+        assert_eq!(rate_limit(0.5, 0.0), 0.5);
+    }
+}
@@ -0,0 +1,125 @@
+//! What runs repeated with other seeds tell about the figures of one run.
+
+use crate::theory::q_inverse;
+
+// the two sided 95% critical values of Student's t distribution by degrees
+// of freedom up to 30, beyond which the expansion around the normal one is
+// as close.
+const CRITICAL_T: [f64; 30] = [
+    12.706, 4.303, 3.182, 2.776, 2.571, 2.447, 2.365, 2.306, 2.262, 2.228, 2.201, 2.179, 2.160,
+    2.145, 2.131, 2.120, 2.110, 2.101, 2.093, 2.086, 2.080, 2.074, 2.069, 2.064, 2.060, 2.056,
+    2.052, 2.048, 2.045, 2.042,
+];
+
+/// The mean of a figure over the trials and the half width of its 95%
+/// confidence interval.
+#[derive(Debug, Clone, Copy)]
+pub struct Estimate {
+    pub mean: f64,
+    /// None for a single trial, which tells nothing about the spread.
+    pub margin: Option<f64>,
+    pub trials: usize,
+}
+
+impl Estimate {
+    /// The estimate from the figure of every trial, the trials taken as
+    /// independent and their mean as normally distributed.
+    pub fn new(samples: &[f64]) -> Self {
+        let trials = samples.len();
+        let mean = samples.iter().sum::<f64>() / trials.max(1) as f64;
+        let margin = (trials > 1).then(|| {
+            let variance = samples
+                .iter()
+                .map(|sample| (sample - mean) * (sample - mean))
+                .sum::<f64>()
+                / (trials - 1) as f64;
+            critical_t(trials - 1) * (variance / trials as f64).sqrt()
+        });
+        Self {
+            mean,
+            margin,
+            trials,
+        }
+    }
+}
+
+/// The value a t statistic with these degrees of freedom exceeds in
+/// magnitude only 5% of the time.
+pub fn critical_t(degrees: usize) -> f64 {
+    if (1..=CRITICAL_T.len()).contains(&degrees) {
+        return CRITICAL_T[degrees - 1];
+    }
+    // the Cornish-Fisher expansion in the normal quantile.
+    let z = q_inverse(0.025);
+    let n = degrees.max(1) as f64;
+    z + (z.powi(3) + z) / (4.0 * n)
+        + (5.0 * z.powi(5) + 16.0 * z.powi(3) + 3.0 * z) / (96.0 * n * n)
+}
+
+/// Whether pairs of figures differ, such as the residual errors of two
+/// coders over the same trials, by the t test of the differences of the
+/// pairs at the 5% level.
+#[derive(Debug, Clone, Copy)]
+pub struct PairedTest {
+    pub difference: Estimate,
+    pub t: f64,
+    pub significant: bool,
+}
+
+impl PairedTest {
+    /// The test of the differences of every pair, None with fewer than two.
+    pub fn new(differences: &[f64]) -> Option<Self> {
+        if differences.len() < 2 {
+            return None;
+        }
+        let difference = Estimate::new(differences);
+        let critical = critical_t(difference.trials - 1);
+        let margin = difference.margin?;
+        // without any spread, any difference at all is one.
+        let t = if margin > 0.0 {
+            difference.mean / margin * critical
+        } else if difference.mean != 0.0 {
+            difference.mean.signum() * f64::INFINITY
+        } else {
+            0.0
+        };
+        Some(Self {
+            difference,
+            t,
+            significant: t.abs() > critical,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fewer_than_two_pairs_test_nothing() {
+        assert!(PairedTest::new(&[]).is_none());
+        assert!(PairedTest::new(&[3.0]).is_none());
+    }
+
+    #[test]
+    fn consistent_differences_are_significant() {
+        let test = PairedTest::new(&[10.0, 12.0, 11.0, 9.0, 10.5]).unwrap();
+        assert!(test.significant);
+        assert!(test.t > critical_t(4));
+        let test = PairedTest::new(&[10.0, -12.0, 1.0, -9.0, 10.5]).unwrap();
+        assert!(!test.significant);
+        // no spread, yet a difference.
+        assert_eq!(PairedTest::new(&[2.0, 2.0]).unwrap().t, f64::INFINITY);
+        assert!(!PairedTest::new(&[0.0, 0.0]).unwrap().significant);
+    }
+
+    #[test]
+    fn single_trial_has_no_margin() {
+        let estimate = Estimate::new(&[4.0]);
+        assert_eq!((estimate.mean, estimate.margin), (4.0, None));
+        let estimate = Estimate::new(&[1.0, 3.0]);
+        assert_eq!(estimate.mean, 2.0);
+        // 12.706 * sqrt(2 / 2)
+        assert!((estimate.margin.unwrap() - 12.706).abs() < 1e-9);
+    }
+}